@@ -0,0 +1,502 @@
+//! Table Browsing Commands
+//!
+//! Server-side filtering, sorting and paging of table rows for the data grid.
+
+use crate::commands::data_edit::RowValues;
+use crate::sqlite3x::types::CellValue;
+use crate::sqlite3x::wrapper::Database;
+use crate::state::AppState;
+use crate::utils::{quote_identifier, AppError, AppResult};
+use serde::{Deserialize, Serialize};
+
+/// Rows returned when no limit is given
+const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// Largest page that can be requested
+const MAX_PAGE_SIZE: u32 = 10_000;
+
+/// Rows counted exactly before an estimated count falls back to statistics
+const ESTIMATE_COUNT_CAP: u64 = 10_000;
+
+/// Comparison applied by a filter condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FilterOperator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Like,
+    NotLike,
+    Contains,
+    StartsWith,
+    EndsWith,
+    In,
+    NotIn,
+    IsNull,
+    IsNotNull,
+}
+
+/// Node of a filter tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum FilterNode {
+    Condition {
+        column: String,
+        operator: FilterOperator,
+        /// Operand of single-value operators
+        #[serde(default)]
+        value: Option<CellValue>,
+        /// Operands of `in` and `notIn`
+        #[serde(default)]
+        values: Vec<CellValue>,
+    },
+    And { children: Vec<FilterNode> },
+    Or { children: Vec<FilterNode> },
+}
+
+/// Sort order of one column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SortSpec {
+    pub column: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// How the total row count is computed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CountMode {
+    #[default]
+    Exact,
+    /// Exact up to a cap, then estimated from statistics
+    Estimate,
+    None,
+}
+
+/// Page request for a table
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowseOptions {
+    #[serde(default)]
+    pub filter: Option<FilterNode>,
+    #[serde(default)]
+    pub sort: Vec<SortSpec>,
+    #[serde(default)]
+    pub offset: Option<u64>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+    /// `nextCursor` of the previous page; continues after its last row
+    #[serde(default)]
+    pub cursor: Option<Vec<CellValue>>,
+    #[serde(default)]
+    pub count: CountMode,
+}
+
+/// One page of table rows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowseResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<CellValue>>,
+    /// Key of each row, for the row editing commands
+    pub keys: Vec<RowValues>,
+    pub total_count: Option<u64>,
+    /// Whether `total_count` is an estimate rather than exact
+    pub count_is_estimate: bool,
+    /// Cursor for the next page, `None` on the last page
+    pub next_cursor: Option<Vec<CellValue>>,
+}
+
+/// Get one page of a table's rows
+#[tauri::command]
+pub async fn browse_table(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    table_name: String,
+    options: BrowseOptions,
+) -> AppResult<BrowseResult> {
+    log::info!("Browsing {} on {}", table_name, connection_id);
+
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+
+    let start = std::time::Instant::now();
+
+    let result = {
+        let db = db_handle.lock();
+        browse(&db, &table_name, &options)?
+    };
+
+    state.record_query(&connection_id, start.elapsed().as_millis() as f64, false);
+
+    Ok(result)
+}
+
+pub(crate) fn browse(db: &Database, table: &str, options: &BrowseOptions) -> AppResult<BrowseResult> {
+    let columns = db.get_column_names(table)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    let key = db.get_row_key(table)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    let known = |column: &str| -> AppResult<()> {
+        if columns.iter().any(|c| c == column) || key.columns.iter().any(|c| c == column) {
+            Ok(())
+        } else {
+            Err(AppError::BadRequest(format!("Unknown column: {}", column)))
+        }
+    };
+
+    for spec in &options.sort {
+        known(&spec.column)?;
+    }
+
+    if options.cursor.is_some() && options.offset.is_some() {
+        return Err(AppError::BadRequest("Use either a cursor or an offset, not both".to_string()));
+    }
+
+    let limit = options.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(AppError::BadRequest(format!("Limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+
+    // Key columns break ties so every row has a unique position
+    let mut order: Vec<SortSpec> = options.sort.clone();
+    for column in &key.columns {
+        if !order.iter().any(|s| &s.column == column) {
+            order.push(SortSpec { column: column.clone(), descending: false });
+        }
+    }
+
+    let mut filter_sql = String::new();
+    let mut params = Vec::new();
+    if let Some(filter) = &options.filter {
+        filter_sql = build_filter(filter, &known, &mut params)?;
+    }
+    let count_filter = filter_sql.clone();
+    let count_params = params.clone();
+
+    let mut conditions = Vec::new();
+    if !filter_sql.is_empty() {
+        conditions.push(filter_sql);
+    }
+    if let Some(cursor) = &options.cursor {
+        if cursor.len() != order.len() {
+            return Err(AppError::BadRequest("Cursor does not match the sort order".to_string()));
+        }
+        conditions.push(keyset_condition(&order, cursor, &mut params));
+    }
+
+    let order_columns: Vec<String> = order.iter().map(|s| quote_identifier(&s.column)).collect();
+    let select_columns: Vec<String> = columns.iter().map(|c| quote_identifier(c)).collect();
+    let order_by: Vec<String> = order.iter()
+        .map(|s| format!("{} {}", quote_identifier(&s.column), if s.descending { "DESC" } else { "ASC" }))
+        .collect();
+
+    let mut sql = format!(
+        "SELECT {}, {} FROM {}",
+        order_columns.join(", "),
+        select_columns.join(", "),
+        quote_identifier(table)
+    );
+    if !conditions.is_empty() {
+        sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+    }
+    sql.push_str(&format!(" ORDER BY {} LIMIT {}", order_by.join(", "), limit));
+    if let Some(offset) = options.offset {
+        sql.push_str(&format!(" OFFSET {}", offset));
+    }
+
+    let fetched = db.query_values(&sql, &params)
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+    let next_cursor = if fetched.len() == limit as usize {
+        fetched.last().map(|row| row[..order.len()].to_vec())
+    } else {
+        None
+    };
+
+    let mut rows = Vec::with_capacity(fetched.len());
+    let mut keys = Vec::with_capacity(fetched.len());
+    for mut row in fetched {
+        let data = row.split_off(order.len());
+        keys.push(
+            key.columns.iter()
+                .filter_map(|column| {
+                    let position = order.iter().position(|s| &s.column == column)?;
+                    Some((column.clone(), row[position].clone()))
+                })
+                .collect(),
+        );
+        rows.push(data);
+    }
+
+    let (total_count, count_is_estimate) = count_rows(db, table, options.count, &count_filter, &count_params)?;
+
+    Ok(BrowseResult {
+        columns,
+        rows,
+        keys,
+        total_count,
+        count_is_estimate,
+        next_cursor,
+    })
+}
+
+/// Build the SQL of a filter tree, appending its operands to `params`
+fn build_filter(
+    node: &FilterNode,
+    known: &dyn Fn(&str) -> AppResult<()>,
+    params: &mut Vec<CellValue>,
+) -> AppResult<String> {
+    match node {
+        FilterNode::And { children } | FilterNode::Or { children } => {
+            if children.is_empty() {
+                // Empty groups match everything, like an absent filter
+                return Ok("1".to_string());
+            }
+            let joiner = if matches!(node, FilterNode::And { .. }) { " AND " } else { " OR " };
+            let parts = children.iter()
+                .map(|child| build_filter(child, known, params))
+                .collect::<AppResult<Vec<_>>>()?;
+            Ok(format!("({})", parts.join(joiner)))
+        }
+        FilterNode::Condition { column, operator, value, values } => {
+            known(column)?;
+            let column = quote_identifier(column);
+
+            let operand = |params: &mut Vec<CellValue>| -> AppResult<()> {
+                let value = value.clone().ok_or_else(|| {
+                    AppError::BadRequest(format!("Operator {:?} needs a value", operator))
+                })?;
+                params.push(value);
+                Ok(())
+            };
+            let text_operand = |params: &mut Vec<CellValue>, prefix: &str, suffix: &str| -> AppResult<()> {
+                match value {
+                    Some(CellValue::Text(s)) => {
+                        params.push(CellValue::Text(format!("{}{}{}", prefix, escape_like(s), suffix)));
+                        Ok(())
+                    }
+                    _ => Err(AppError::BadRequest(format!("Operator {:?} needs a text value", operator))),
+                }
+            };
+
+            let sql = match operator {
+                FilterOperator::Eq => { operand(params)?; format!("{} = ?", column) }
+                FilterOperator::Ne => { operand(params)?; format!("{} <> ?", column) }
+                FilterOperator::Lt => { operand(params)?; format!("{} < ?", column) }
+                FilterOperator::Le => { operand(params)?; format!("{} <= ?", column) }
+                FilterOperator::Gt => { operand(params)?; format!("{} > ?", column) }
+                FilterOperator::Ge => { operand(params)?; format!("{} >= ?", column) }
+                FilterOperator::Like => { operand(params)?; format!("{} LIKE ?", column) }
+                FilterOperator::NotLike => { operand(params)?; format!("{} NOT LIKE ?", column) }
+                FilterOperator::Contains => {
+                    text_operand(params, "%", "%")?;
+                    format!("{} LIKE ? ESCAPE '\\'", column)
+                }
+                FilterOperator::StartsWith => {
+                    text_operand(params, "", "%")?;
+                    format!("{} LIKE ? ESCAPE '\\'", column)
+                }
+                FilterOperator::EndsWith => {
+                    text_operand(params, "%", "")?;
+                    format!("{} LIKE ? ESCAPE '\\'", column)
+                }
+                FilterOperator::In | FilterOperator::NotIn => {
+                    if values.is_empty() {
+                        // Nothing is in an empty list
+                        return Ok(if *operator == FilterOperator::In { "0" } else { "1" }.to_string());
+                    }
+                    params.extend(values.iter().cloned());
+                    let placeholders = vec!["?"; values.len()].join(", ");
+                    let not = if *operator == FilterOperator::NotIn { "NOT " } else { "" };
+                    format!("{} {}IN ({})", column, not, placeholders)
+                }
+                FilterOperator::IsNull => format!("{} IS NULL", column),
+                FilterOperator::IsNotNull => format!("{} IS NOT NULL", column),
+            };
+
+            Ok(sql)
+        }
+    }
+}
+
+/// Escape LIKE wildcards so the text matches literally (with `ESCAPE '\'`)
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Condition selecting the rows that sort after `cursor`
+///
+/// SQLite sorts NULL before any value, so it is the first value ascending
+/// and the last one descending.
+fn keyset_condition(order: &[SortSpec], cursor: &[CellValue], params: &mut Vec<CellValue>) -> String {
+    let mut alternatives = Vec::new();
+
+    for (i, spec) in order.iter().enumerate() {
+        let column = quote_identifier(&spec.column);
+        let mut parts = Vec::new();
+
+        for (prev, value) in order[..i].iter().zip(cursor) {
+            parts.push(format!("{} IS ?", quote_identifier(&prev.column)));
+            params.push(value.clone());
+        }
+
+        let after = match (&cursor[i], spec.descending) {
+            (CellValue::Null, false) => format!("{} IS NOT NULL", column),
+            (CellValue::Null, true) => "0".to_string(),
+            (value, false) => {
+                params.push(value.clone());
+                format!("{} > ?", column)
+            }
+            (value, true) => {
+                params.push(value.clone());
+                format!("({} < ? OR {} IS NULL)", column, column)
+            }
+        };
+        parts.push(after);
+
+        alternatives.push(format!("({})", parts.join(" AND ")));
+    }
+
+    format!("({})", alternatives.join(" OR "))
+}
+
+/// Count the rows matching the filter according to `mode`
+fn count_rows(
+    db: &Database,
+    table: &str,
+    mode: CountMode,
+    filter: &str,
+    params: &[CellValue],
+) -> AppResult<(Option<u64>, bool)> {
+    let where_clause = if filter.is_empty() { String::new() } else { format!(" WHERE {}", filter) };
+
+    let count = |sql: String| -> AppResult<u64> {
+        let rows = db.query_values(&sql, params)
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+        match rows.first().and_then(|r| r.first()) {
+            Some(CellValue::Integer(n)) => Ok(*n as u64),
+            _ => Ok(0),
+        }
+    };
+
+    match mode {
+        CountMode::None => Ok((None, false)),
+        CountMode::Exact => {
+            let sql = format!("SELECT COUNT(*) FROM {}{}", quote_identifier(table), where_clause);
+            Ok((Some(count(sql)?), false))
+        }
+        CountMode::Estimate => {
+            let sql = format!(
+                "SELECT COUNT(*) FROM (SELECT 1 FROM {}{} LIMIT {})",
+                quote_identifier(table),
+                where_clause,
+                ESTIMATE_COUNT_CAP + 1
+            );
+            let capped = count(sql)?;
+            if capped <= ESTIMATE_COUNT_CAP {
+                return Ok((Some(capped), false));
+            }
+
+            // Unfiltered tables can use statistics; otherwise report the lower bound
+            let estimate = if filter.is_empty() {
+                table_estimate(db, table).unwrap_or(capped).max(capped)
+            } else {
+                capped
+            };
+            Ok((Some(estimate), true))
+        }
+    }
+}
+
+/// Row count from `sqlite_stat1`, or the largest rowid, without scanning the table
+fn table_estimate(db: &Database, table: &str) -> Option<u64> {
+    let table_param = [CellValue::Text(table.to_string())];
+
+    let from_stats = db
+        .query_values(
+            "SELECT CAST(stat AS INTEGER) FROM sqlite_stat1 WHERE tbl = ? ORDER BY idx IS NULL DESC LIMIT 1",
+            &table_param,
+        )
+        .ok()
+        .and_then(|rows| match rows.first().and_then(|r| r.first()) {
+            Some(CellValue::Integer(n)) => Some(*n as u64),
+            _ => None,
+        });
+
+    from_stats.or_else(|| {
+        let sql = format!("SELECT MAX(rowid) FROM {}", quote_identifier(table));
+        db.query_values(&sql, &[])
+            .ok()
+            .and_then(|rows| match rows.first().and_then(|r| r.first()) {
+                Some(CellValue::Integer(n)) => Some(*n as u64),
+                _ => None,
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(column: &str, operator: FilterOperator, value: Option<CellValue>) -> FilterNode {
+        FilterNode::Condition { column: column.to_string(), operator, value, values: Vec::new() }
+    }
+
+    #[test]
+    fn test_filtered_keyset_paging() {
+        let db = Database::open(":memory:").expect("Failed to open memory db");
+        db.execute("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, price REAL)").unwrap();
+        db.execute(
+            "INSERT INTO items VALUES (1, 'a%b', 5), (2, 'apple', NULL), (3, 'axe', 5), (4, 'box', 1), (5, 'ant', 2)",
+        )
+        .unwrap();
+
+        let filter = FilterNode::Or {
+            children: vec![
+                condition("name", FilterOperator::StartsWith, Some(CellValue::Text("a".to_string()))),
+                condition("price", FilterOperator::IsNull, None),
+            ],
+        };
+        let mut options = BrowseOptions {
+            filter: Some(filter),
+            sort: vec![SortSpec { column: "price".to_string(), descending: true }],
+            limit: Some(2),
+            ..Default::default()
+        };
+
+        // price DESC puts NULL last; id breaks the tie between the two 5s
+        let mut ids = Vec::new();
+        loop {
+            let page = browse(&db, "items", &options).unwrap();
+            assert_eq!(page.total_count, Some(4));
+            ids.extend(page.keys.iter().map(|k| k["id"].clone()));
+            match page.next_cursor {
+                Some(cursor) => options.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        let expected: Vec<CellValue> = [1, 3, 5, 2].iter().map(|i| CellValue::Integer(*i)).collect();
+        assert_eq!(ids, expected);
+
+        // Wildcards in the operand match literally
+        let options = BrowseOptions {
+            filter: Some(condition("name", FilterOperator::Contains, Some(CellValue::Text("%".to_string())))),
+            ..Default::default()
+        };
+        let page = browse(&db, "items", &options).unwrap();
+        assert_eq!(page.rows.len(), 1);
+        assert_eq!(page.columns, vec!["id", "name", "price"]);
+
+        let options = BrowseOptions {
+            filter: Some(condition("id; DROP TABLE items", FilterOperator::IsNull, None)),
+            ..Default::default()
+        };
+        assert!(matches!(browse(&db, "items", &options), Err(AppError::BadRequest(_))));
+    }
+}
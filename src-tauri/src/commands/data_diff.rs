@@ -0,0 +1,84 @@
+//! Table Data Diff Commands
+//!
+//! Compare the rows of two tables, in the same or different connections,
+//! and sync the target's rows from the source.
+
+use crate::commands::data_edit::in_savepoint;
+use crate::commands::history::{push_change, record_changes};
+use crate::sqlite3x::wrapper::Database;
+use crate::state::AppState;
+use crate::utils::{AppError, AppResult};
+use crate::utils::data_diff::{diff_table_data as compare_table_data, DataDiffOptions, TableDataDiff, DEFAULT_ROW_LIMIT};
+
+/// Compare the rows of two tables with the same key, in the same or different connections
+///
+/// The target is made to match the source: the result holds the sync
+/// script, and with `apply` the sync also runs on the target connection
+/// while it is compared, as one edit that is undoable unless too large.
+/// `rowLimit` caps the reported rows and script, not the sync.
+#[tauri::command]
+pub async fn diff_table_data(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    source_connection_id: String,
+    source_table: String,
+    target_connection_id: String,
+    target_table: String,
+    options: Option<DataDiffOptions>,
+) -> AppResult<TableDataDiff> {
+    log::info!(
+        "Comparing rows of {} on {} with {} on {}",
+        source_table, source_connection_id, target_table, target_connection_id
+    );
+
+    let handle = |id: &str| state.get_db_handle(id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", id)));
+    let source = handle(&source_connection_id)?;
+    let target = handle(&target_connection_id)?;
+    let options = options.unwrap_or_default();
+    let key_columns = options.key_columns.as_deref();
+    let row_limit = options.row_limit.unwrap_or(DEFAULT_ROW_LIMIT);
+
+    // The sync runs under the target's lock from the first row compared
+    // to the last statement, in one savepoint
+    let compare = |source: &Database, target: &Database| -> AppResult<(TableDataDiff, Option<Vec<u8>>)> {
+        if !options.apply {
+            let diff = compare_table_data(source, &source_table, target, &target_table, key_columns, row_limit, |_| Ok(()))?;
+            return Ok((diff, None));
+        }
+        record_changes(target, |target| in_savepoint(target, |target| {
+            let mut affected = 0;
+            let mut diff = compare_table_data(source, &source_table, target, &target_table, key_columns, row_limit, |statement| {
+                affected += target.execute_values(&statement.sql, &statement.params)
+                    .map_err(|e| AppError::QueryError(format!("{}\n{}", e, statement.to_sql())))?;
+                Ok(())
+            })?;
+            diff.applied = Some(affected);
+            Ok(diff)
+        }))
+    };
+
+    if options.apply {
+        state.touch_transactions(&target_connection_id);
+    }
+
+    // One lock for one connection; two are always taken in the same order
+    let (diff, changeset) = if std::sync::Arc::ptr_eq(&source, &target) {
+        let db = source.lock();
+        compare(&db, &db)?
+    } else if source_connection_id < target_connection_id {
+        let source = source.lock();
+        let target = target.lock();
+        compare(&source, &target)?
+    } else {
+        let target = target.lock();
+        let source = source.lock();
+        compare(&source, &target)?
+    };
+
+    if let Some(affected) = diff.applied.filter(|&n| n > 0) {
+        let description = format!("Sync {} from {}", target_table, source_table);
+        push_change(&state, &target_connection_id, description, affected, changeset);
+    }
+
+    Ok(diff)
+}
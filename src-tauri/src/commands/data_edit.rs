@@ -0,0 +1,425 @@
+//! Row Editing Commands
+//!
+//! Insert, update and delete rows addressed by primary key (or rowid), with
+//! optimistic concurrency checks against the values the grid last read.
+
+use crate::commands::history::{push_change, record_changes};
+use crate::sqlite3x::types::{CellValue, RowKey};
+use crate::sqlite3x::wrapper::Database;
+use crate::state::AppState;
+use crate::utils::{quote_identifier, AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Savepoint wrapping one edit command, so it also nests inside an open transaction
+const EDIT_SAVEPOINT: &str = "data_edit";
+
+/// Column values keyed by column name
+pub type RowValues = BTreeMap<String, CellValue>;
+
+/// Update of one row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowUpdate {
+    /// Key column values identifying the row
+    pub key: RowValues,
+    /// New values of the edited columns
+    pub values: RowValues,
+    /// Values of the edited columns as last read; the update fails if they changed
+    #[serde(default)]
+    pub original: RowValues,
+}
+
+/// Deletion of one row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowDelete {
+    pub key: RowValues,
+    /// Column values as last read; the delete fails if they changed
+    #[serde(default)]
+    pub original: RowValues,
+}
+
+/// Update cells of existing rows in one transaction
+#[tauri::command]
+pub async fn update_cells(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    table_name: String,
+    updates: Vec<RowUpdate>,
+) -> AppResult<usize> {
+    log::info!("Updating {} rows in {} on {}", updates.len(), table_name, connection_id);
+
+    let description = format!("Update {} rows in {}", updates.len(), table_name);
+    run_edit(&state, &connection_id, description, |db| {
+        update_rows(db, &table_name, &updates)
+    })
+}
+
+/// Insert rows in one transaction, returning the key of each inserted row
+#[tauri::command]
+pub async fn insert_rows(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    table_name: String,
+    rows: Vec<RowValues>,
+) -> AppResult<Vec<RowValues>> {
+    log::info!("Inserting {} rows into {} on {}", rows.len(), table_name, connection_id);
+
+    let description = format!("Insert {} rows into {}", rows.len(), table_name);
+    let keys = run_edit(&state, &connection_id, description, |db| {
+        let keys = insert(db, &table_name, &rows)?;
+        Ok((keys.len(), keys))
+    })?;
+
+    Ok(keys)
+}
+
+/// Delete rows in one transaction
+#[tauri::command]
+pub async fn delete_rows(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    table_name: String,
+    rows: Vec<RowDelete>,
+) -> AppResult<usize> {
+    log::info!("Deleting {} rows from {} on {}", rows.len(), table_name, connection_id);
+
+    let description = format!("Delete {} rows from {}", rows.len(), table_name);
+    run_edit(&state, &connection_id, description, |db| {
+        delete(db, &table_name, &rows)
+    })
+}
+
+/// Get the columns that identify rows of a table
+#[tauri::command]
+pub async fn get_row_key(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    table_name: String,
+) -> AppResult<RowKey> {
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+
+    let db = db_handle.lock();
+    db.get_row_key(&table_name)
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))
+}
+
+/// Run an edit atomically, recording it for undo
+///
+/// `f` returns the number of affected rows along with its result.
+pub(crate) fn run_edit<T>(
+    state: &AppState,
+    connection_id: &str,
+    description: String,
+    f: impl FnOnce(&Database) -> AppResult<(usize, T)>,
+) -> AppResult<T> {
+    let db_handle = state.get_db_handle(connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+
+    state.touch_transactions(connection_id);
+
+    let ((affected, value), changeset) = {
+        let db = db_handle.lock();
+        record_changes(&db, |db| in_savepoint(db, f))?
+    };
+
+    push_change(state, connection_id, description, affected, changeset);
+
+    Ok(value)
+}
+
+/// Run a bulk edit atomically without recording it for undo
+///
+/// For changes too large to keep a changeset of, such as generated data.
+pub(crate) fn run_bulk_edit<T>(
+    state: &AppState,
+    connection_id: &str,
+    f: impl FnOnce(&Database) -> AppResult<T>,
+) -> AppResult<T> {
+    let db_handle = state.get_db_handle(connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+
+    state.touch_transactions(connection_id);
+
+    let db = db_handle.lock();
+    in_savepoint(&db, f)
+}
+
+/// Run `f` inside a savepoint, rolling back everything it did on error
+pub(crate) fn in_savepoint<T>(db: &Database, f: impl FnOnce(&Database) -> AppResult<T>) -> AppResult<T> {
+    db.execute(&format!("SAVEPOINT {}", EDIT_SAVEPOINT))
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+    match f(db) {
+        Ok(value) => {
+            db.execute(&format!("RELEASE {}", EDIT_SAVEPOINT))
+                .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+            Ok(value)
+        }
+        Err(e) => {
+            if let Err(rollback_err) = db.execute(&format!("ROLLBACK TO {}", EDIT_SAVEPOINT)) {
+                log::error!("Failed to roll back edit: {}", rollback_err);
+            }
+            let _ = db.execute(&format!("RELEASE {}", EDIT_SAVEPOINT));
+            Err(e)
+        }
+    }
+}
+
+fn update_rows(db: &Database, table: &str, updates: &[RowUpdate]) -> AppResult<(usize, usize)> {
+    let key = row_key(db, table)?;
+    let mut affected = 0;
+
+    for update in updates {
+        if update.values.is_empty() {
+            continue;
+        }
+
+        let (key_clause, mut params) = key_condition(&key, &update.key)?;
+
+        let assignments: Vec<String> = update.values.keys()
+            .map(|column| format!("{} = ?", quote_identifier(column)))
+            .collect();
+        let mut values: Vec<CellValue> = update.values.values().cloned().collect();
+
+        let (original_clause, original_params) = values_condition(&update.original);
+        values.append(&mut params);
+        values.extend(original_params);
+
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {}{}",
+            quote_identifier(table),
+            assignments.join(", "),
+            key_clause,
+            original_clause
+        );
+
+        let changed = db.execute_values(&sql, &values)
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+        if changed == 0 {
+            return Err(row_conflict(db, table, &key, &update.key));
+        }
+        affected += changed;
+    }
+
+    Ok((affected, affected))
+}
+
+fn insert(db: &Database, table: &str, rows: &[RowValues]) -> AppResult<Vec<RowValues>> {
+    let key = row_key(db, table)?;
+    let returning = key.columns.iter()
+        .map(|c| quote_identifier(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut keys = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        let sql = if row.is_empty() {
+            format!("INSERT INTO {} DEFAULT VALUES RETURNING {}", quote_identifier(table), returning)
+        } else {
+            let columns = row.keys().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", ");
+            let placeholders = vec!["?"; row.len()].join(", ");
+            format!(
+                "INSERT INTO {} ({}) VALUES ({}) RETURNING {}",
+                quote_identifier(table),
+                columns,
+                placeholders,
+                returning
+            )
+        };
+
+        let params: Vec<CellValue> = row.values().cloned().collect();
+        let returned = db.query_values(&sql, &params)
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+        let values = returned.into_iter().next()
+            .ok_or_else(|| AppError::InternalError("Insert returned no key".to_string()))?;
+        keys.push(key.columns.iter().cloned().zip(values).collect());
+    }
+
+    Ok(keys)
+}
+
+fn delete(db: &Database, table: &str, rows: &[RowDelete]) -> AppResult<(usize, usize)> {
+    let key = row_key(db, table)?;
+    let mut affected = 0;
+
+    for row in rows {
+        let (key_clause, mut params) = key_condition(&key, &row.key)?;
+        let (original_clause, original_params) = values_condition(&row.original);
+        params.extend(original_params);
+
+        let sql = format!("DELETE FROM {} WHERE {}{}", quote_identifier(table), key_clause, original_clause);
+
+        let changed = db.execute_values(&sql, &params)
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+        if changed == 0 {
+            return Err(row_conflict(db, table, &key, &row.key));
+        }
+        affected += changed;
+    }
+
+    Ok((affected, affected))
+}
+
+fn row_key(db: &Database, table: &str) -> AppResult<RowKey> {
+    db.get_row_key(table)
+        .map_err(|e| AppError::BadRequest(e.to_string()))
+}
+
+/// `WHERE` condition matching the row with the given key values
+fn key_condition(key: &RowKey, values: &RowValues) -> AppResult<(String, Vec<CellValue>)> {
+    if values.len() != key.columns.len() || key.columns.iter().any(|c| !values.contains_key(c)) {
+        return Err(AppError::BadRequest(format!(
+            "Row key must consist of: {}",
+            key.columns.join(", ")
+        )));
+    }
+
+    let clause = key.columns.iter()
+        .map(|c| format!("{} IS ?", quote_identifier(c)))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let params = key.columns.iter().map(|c| values[c].clone()).collect();
+
+    Ok((clause, params))
+}
+
+/// Additional `AND` conditions requiring columns to still hold the given values
+fn values_condition(values: &RowValues) -> (String, Vec<CellValue>) {
+    let clause: String = values.keys()
+        .map(|c| format!(" AND {} IS ?", quote_identifier(c)))
+        .collect();
+
+    (clause, values.values().cloned().collect())
+}
+
+/// Explain why a keyed statement matched no row
+fn row_conflict(db: &Database, table: &str, key: &RowKey, values: &RowValues) -> AppError {
+    let description = key.columns.iter()
+        .map(|c| format!("{}={}", c, describe_value(&values[c])))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let exists = key_condition(key, values)
+        .ok()
+        .and_then(|(clause, params)| {
+            let sql = format!("SELECT 1 FROM {} WHERE {}", quote_identifier(table), clause);
+            db.query_values(&sql, &params).ok()
+        })
+        .map(|rows| !rows.is_empty())
+        .unwrap_or(false);
+
+    if exists {
+        AppError::Conflict(format!("{} ({}): row was modified since it was read", table, description))
+    } else {
+        AppError::Conflict(format!("{} ({}): row no longer exists", table, description))
+    }
+}
+
+fn describe_value(value: &CellValue) -> String {
+    match value {
+        CellValue::Null => "NULL".to_string(),
+        CellValue::Integer(i) => i.to_string(),
+        CellValue::Real(f) => f.to_string(),
+        CellValue::Text(s) => format!("'{}'", s),
+        CellValue::Blob(b) => format!("<BLOB {} bytes>", b.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> CellValue {
+        CellValue::Text(s.to_string())
+    }
+
+    fn values(pairs: &[(&str, CellValue)]) -> RowValues {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+    }
+
+    #[test]
+    fn test_composite_key_without_rowid() {
+        let db = Database::open(":memory:").expect("Failed to open memory db");
+        db.execute(
+            "CREATE TABLE grades (student TEXT, course TEXT, grade INTEGER, PRIMARY KEY (student, course)) WITHOUT ROWID",
+        )
+        .unwrap();
+
+        let key = db.get_row_key("grades").unwrap();
+        assert_eq!(key.columns, vec!["student", "course"]);
+        assert!(!key.has_rowid);
+
+        let keys = in_savepoint(&db, |db| {
+            insert(db, "grades", &[
+                values(&[("student", text("ann")), ("course", text("math")), ("grade", CellValue::Integer(3))]),
+                values(&[("student", text("ann")), ("course", text("art")), ("grade", CellValue::Null)]),
+            ])
+        })
+        .unwrap();
+        assert_eq!(keys[1], values(&[("student", text("ann")), ("course", text("art"))]));
+
+        let update = RowUpdate {
+            key: keys[0].clone(),
+            values: values(&[("grade", CellValue::Integer(4))]),
+            original: values(&[("grade", CellValue::Integer(3))]),
+        };
+        let (affected, _) = in_savepoint(&db, |db| update_rows(db, "grades", std::slice::from_ref(&update))).unwrap();
+        assert_eq!(affected, 1);
+
+        // The same edit again is stale: grade is no longer 3
+        match in_savepoint(&db, |db| update_rows(db, "grades", std::slice::from_ref(&update))) {
+            Err(AppError::Conflict(msg)) => assert!(msg.contains("modified")),
+            other => panic!("Expected conflict, got {:?}", other),
+        }
+
+        // A failing row rolls back the whole batch
+        let fresh = RowUpdate {
+            key: keys[1].clone(),
+            values: values(&[("grade", CellValue::Integer(1))]),
+            original: values(&[("grade", CellValue::Null)]),
+        };
+        assert!(in_savepoint(&db, |db| update_rows(db, "grades", &[fresh, update])).is_err());
+        let grades = db.query_values("SELECT grade FROM grades ORDER BY course", &[]).unwrap();
+        assert_eq!(grades, vec![vec![CellValue::Null], vec![CellValue::Integer(4)]]);
+
+        let delete_missing = RowDelete {
+            key: values(&[("student", text("bob")), ("course", text("math"))]),
+            original: RowValues::new(),
+        };
+        match in_savepoint(&db, |db| delete(db, "grades", &[delete_missing])) {
+            Err(AppError::Conflict(msg)) => assert!(msg.contains("no longer exists")),
+            other => panic!("Expected conflict, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rowid_table_without_primary_key() {
+        let db = Database::open(":memory:").expect("Failed to open memory db");
+        db.execute("CREATE TABLE notes (body TEXT, data BLOB)").unwrap();
+
+        let key = db.get_row_key("notes").unwrap();
+        assert_eq!(key.columns, vec!["rowid"]);
+
+        let keys = in_savepoint(&db, |db| {
+            insert(db, "notes", &[values(&[("body", text("hi")), ("data", CellValue::Blob(vec![1, 2]))])])
+        })
+        .unwrap();
+        assert_eq!(keys[0], values(&[("rowid", CellValue::Integer(1))]));
+
+        let (affected, _) = in_savepoint(&db, |db| {
+            delete(db, "notes", &[RowDelete {
+                key: keys[0].clone(),
+                original: values(&[("data", CellValue::Blob(vec![1, 2]))]),
+            }])
+        })
+        .unwrap();
+        assert_eq!(affected, 1);
+    }
+}
@@ -0,0 +1,414 @@
+//! Find and Replace Commands
+//!
+//! Search text columns for a literal or regex match and replace it in place.
+//! Updated rows reach the frontend through the update hook registered in
+//! `events::data_change` (SQLite does not report WITHOUT ROWID tables there).
+
+use crate::commands::data_edit::{run_edit, RowValues};
+use crate::sqlite3x::types::{CellValue, RowKey};
+use crate::sqlite3x::wrapper::Database;
+use crate::state::AppState;
+use crate::utils::{quote_identifier, AppError, AppResult};
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Matches returned in a preview when no limit is given
+const DEFAULT_PREVIEW_LIMIT: usize = 200;
+
+/// What to find, where, and what to replace it with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindReplaceOptions {
+    /// Table to search; all tables when `None`
+    #[serde(default)]
+    pub table_name: Option<String>,
+    /// Columns to search in `table_name`; all columns when empty
+    #[serde(default)]
+    pub columns: Vec<String>,
+    pub find: String,
+    pub replace: String,
+    /// Treat `find` as a regular expression; `replace` may then use `$1` groups
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub ignore_case: bool,
+    /// Maximum matches included in a preview
+    #[serde(default)]
+    pub preview_limit: Option<usize>,
+}
+
+/// A cell that would change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindMatch {
+    pub table: String,
+    pub key: RowValues,
+    pub column: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+/// Number of rows changed in one column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnMatchCount {
+    pub table: String,
+    pub column: String,
+    pub rows: usize,
+}
+
+/// Matches that a replacement would change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindReplacePreview {
+    pub matches: Vec<FindMatch>,
+    /// Whether `matches` was cut off at the preview limit
+    pub truncated: bool,
+    pub counts: Vec<ColumnMatchCount>,
+}
+
+/// Outcome of an applied replacement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindReplaceResult {
+    pub affected_rows: usize,
+    pub counts: Vec<ColumnMatchCount>,
+}
+
+/// Replacements found in one row
+struct RowReplacement {
+    key: RowValues,
+    /// (column, old value, new value)
+    cells: Vec<(String, String, String)>,
+}
+
+/// Preview the cells a find and replace would change
+#[tauri::command]
+pub async fn preview_find_replace(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    options: FindReplaceOptions,
+) -> AppResult<FindReplacePreview> {
+    log::info!("Previewing find and replace on {}: {}", connection_id, options.find);
+
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+
+    let db = db_handle.lock();
+    preview(&db, &options)
+}
+
+/// Apply a find and replace in one transaction
+#[tauri::command]
+pub async fn apply_find_replace(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    options: FindReplaceOptions,
+) -> AppResult<FindReplaceResult> {
+    log::info!("Applying find and replace on {}: {}", connection_id, options.find);
+
+    let description = format!("Replace '{}' with '{}'", options.find, options.replace);
+    let result = run_edit(&state, &connection_id, description, |db| {
+        let result = apply(db, &options)?;
+        Ok((result.affected_rows, result))
+    })?;
+
+    log::info!("Find and replace changed {} rows", result.affected_rows);
+
+    Ok(result)
+}
+
+pub(crate) fn preview(db: &Database, options: &FindReplaceOptions) -> AppResult<FindReplacePreview> {
+    let limit = options.preview_limit.unwrap_or(DEFAULT_PREVIEW_LIMIT);
+    let mut matches = Vec::new();
+    let mut truncated = false;
+    let mut counts = Vec::new();
+
+    for_each_table(db, options, |table, _key, rows| {
+        count_columns(table, &rows, &mut counts);
+        for row in rows {
+            for (column, old_value, new_value) in row.cells {
+                if matches.len() == limit {
+                    truncated = true;
+                    continue;
+                }
+                matches.push(FindMatch {
+                    table: table.to_string(),
+                    key: row.key.clone(),
+                    column,
+                    old_value,
+                    new_value,
+                });
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(FindReplacePreview { matches, truncated, counts })
+}
+
+/// Replace all matches; must run inside a transaction
+pub(crate) fn apply(db: &Database, options: &FindReplaceOptions) -> AppResult<FindReplaceResult> {
+    let mut affected_rows = 0;
+    let mut counts = Vec::new();
+
+    for_each_table(db, options, |table, key, rows| {
+        count_columns(table, &rows, &mut counts);
+
+        for row in rows {
+            let assignments: Vec<String> = row.cells.iter()
+                .map(|(column, _, _)| format!("{} = ?", quote_identifier(column)))
+                .collect();
+            let condition: Vec<String> = key.columns.iter()
+                .map(|c| format!("{} IS ?", quote_identifier(c)))
+                .collect();
+
+            let mut params: Vec<CellValue> = row.cells.into_iter()
+                .map(|(_, _, new_value)| CellValue::Text(new_value))
+                .collect();
+            params.extend(key.columns.iter().map(|c| row.key[c].clone()));
+
+            let sql = format!(
+                "UPDATE {} SET {} WHERE {}",
+                quote_identifier(table),
+                assignments.join(", "),
+                condition.join(" AND ")
+            );
+            affected_rows += db.execute_values(&sql, &params)
+                .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+        }
+        Ok(())
+    })?;
+
+    Ok(FindReplaceResult { affected_rows, counts })
+}
+
+/// Find the replacements in every searched table and hand them to `f`
+fn for_each_table<F>(db: &Database, options: &FindReplaceOptions, mut f: F) -> AppResult<()>
+where
+    F: FnMut(&str, &RowKey, Vec<RowReplacement>) -> AppResult<()>,
+{
+    if options.find.is_empty() {
+        return Err(AppError::BadRequest("Search text must not be empty".to_string()));
+    }
+    if options.table_name.is_none() && !options.columns.is_empty() {
+        return Err(AppError::BadRequest("Columns can only be selected for a single table".to_string()));
+    }
+
+    let pattern = if options.regex { options.find.clone() } else { regex::escape(&options.find) };
+    let matcher = RegexBuilder::new(&pattern)
+        .case_insensitive(options.ignore_case)
+        .build()
+        .map_err(|e| AppError::BadRequest(format!("Invalid regular expression: {}", e)))?;
+
+    let tables = match &options.table_name {
+        Some(table) => vec![table.clone()],
+        None => searchable_tables(db)?,
+    };
+
+    for table in tables {
+        let key = db.get_row_key(&table)
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+        let writable = writable_columns(db, &table)?;
+        let columns = if options.columns.is_empty() {
+            writable
+        } else {
+            for column in &options.columns {
+                if !writable.contains(column) {
+                    return Err(AppError::BadRequest(format!("Column not searchable: {}.{}", table, column)));
+                }
+            }
+            options.columns.clone()
+        };
+
+        if columns.is_empty() {
+            continue;
+        }
+
+        let rows = find_in_table(db, &table, &key, &columns, &matcher, options)?;
+        if !rows.is_empty() {
+            f(&table, &key, rows)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Rows of `table` with at least one matching text value
+fn find_in_table(
+    db: &Database,
+    table: &str,
+    key: &RowKey,
+    columns: &[String],
+    matcher: &Regex,
+    options: &FindReplaceOptions,
+) -> AppResult<Vec<RowReplacement>> {
+    // A plain case-sensitive search can be narrowed down by SQLite itself
+    let literal = !options.regex && !options.ignore_case;
+    let mut params = Vec::new();
+    let candidates: Vec<String> = columns.iter()
+        .map(|c| {
+            let column = quote_identifier(c);
+            if literal {
+                params.push(CellValue::Text(options.find.clone()));
+                format!("(typeof({}) = 'text' AND instr({}, ?) > 0)", column, column)
+            } else {
+                format!("typeof({}) = 'text'", column)
+            }
+        })
+        .collect();
+
+    let selected: Vec<String> = key.columns.iter().chain(columns).map(|c| quote_identifier(c)).collect();
+    let sql = format!(
+        "SELECT {} FROM {} WHERE {}",
+        selected.join(", "),
+        quote_identifier(table),
+        candidates.join(" OR ")
+    );
+
+    let rows = db.query_values(&sql, &params)
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+    let mut replacements = Vec::new();
+    for row in rows {
+        let (key_values, values) = row.split_at(key.columns.len());
+        let mut cells = Vec::new();
+
+        for (column, value) in columns.iter().zip(values) {
+            let CellValue::Text(old_value) = value else {
+                continue;
+            };
+            if !matcher.is_match(old_value) {
+                continue;
+            }
+            let new_value = if options.regex {
+                matcher.replace_all(old_value, options.replace.as_str()).into_owned()
+            } else {
+                matcher.replace_all(old_value, NoExpand(&options.replace)).into_owned()
+            };
+            if &new_value != old_value {
+                cells.push((column.clone(), old_value.clone(), new_value));
+            }
+        }
+
+        if !cells.is_empty() {
+            replacements.push(RowReplacement {
+                key: key.columns.iter().cloned().zip(key_values.iter().cloned()).collect(),
+                cells,
+            });
+        }
+    }
+
+    Ok(replacements)
+}
+
+/// Ordinary user tables, without virtual, shadow and internal tables
+fn searchable_tables(db: &Database) -> AppResult<Vec<String>> {
+    let rows = db
+        .query_values(
+            "SELECT name FROM pragma_table_list WHERE schema = 'main' AND type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+            &[],
+        )
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+    Ok(text_column(rows))
+}
+
+/// Columns that can be assigned, i.e. all but generated columns
+fn writable_columns(db: &Database, table: &str) -> AppResult<Vec<String>> {
+    let rows = db
+        .query_values(
+            "SELECT name FROM pragma_table_xinfo(?) WHERE hidden = 0 ORDER BY cid",
+            &[CellValue::Text(table.to_string())],
+        )
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+    Ok(text_column(rows))
+}
+
+fn text_column(rows: Vec<Vec<CellValue>>) -> Vec<String> {
+    rows.into_iter()
+        .filter_map(|row| match row.into_iter().next() {
+            Some(CellValue::Text(name)) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+fn count_columns(table: &str, rows: &[RowReplacement], counts: &mut Vec<ColumnMatchCount>) {
+    for row in rows {
+        for (column, _, _) in &row.cells {
+            match counts.iter_mut().find(|c| c.table == table && &c.column == column) {
+                Some(count) => count.rows += 1,
+                None => counts.push(ColumnMatchCount {
+                    table: table.to_string(),
+                    column: column.clone(),
+                    rows: 1,
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::data_edit::in_savepoint;
+
+    fn options(find: &str, replace: &str) -> FindReplaceOptions {
+        FindReplaceOptions {
+            table_name: None,
+            columns: Vec::new(),
+            find: find.to_string(),
+            replace: replace.to_string(),
+            regex: false,
+            ignore_case: false,
+            preview_limit: None,
+        }
+    }
+
+    #[test]
+    fn test_find_replace_across_tables() {
+        let db = Database::open(":memory:").expect("Failed to open memory db");
+        db.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT, note TEXT)").unwrap();
+        db.execute("CREATE TABLE hosts (name TEXT PRIMARY KEY, url TEXT) WITHOUT ROWID").unwrap();
+        db.execute(
+            "INSERT INTO users VALUES (1, 'a@old.example', 'see OLD.example'), (2, 'b@new.example', NULL), (3, 'x', 42)",
+        )
+        .unwrap();
+        db.execute("INSERT INTO hosts VALUES ('web', 'https://old.example/'), ('db', 'https://a.b/')").unwrap();
+
+        let preview = preview(&db, &options("old.example", "new.example")).unwrap();
+        assert_eq!(preview.matches.len(), 2);
+        assert!(!preview.truncated);
+
+        let mut opts = options("OLD.example", "new.example");
+        opts.ignore_case = true;
+        let result = in_savepoint(&db, |db| apply(db, &opts)).unwrap();
+        assert_eq!(result.affected_rows, 2);
+        let user_counts: Vec<(&str, usize)> = result.counts.iter()
+            .filter(|c| c.table == "users")
+            .map(|c| (c.column.as_str(), c.rows))
+            .collect();
+        assert_eq!(user_counts, vec![("email", 1), ("note", 1)]);
+
+        let users = db.query_values("SELECT email, note FROM users WHERE id = 1", &[]).unwrap();
+        assert_eq!(
+            users[0],
+            vec![CellValue::Text("a@new.example".to_string()), CellValue::Text("see new.example".to_string())]
+        );
+
+        // Regex with capture groups, limited to one column
+        let mut opts = options(r"https://([a-z.]+)/", "$1");
+        opts.regex = true;
+        opts.table_name = Some("hosts".to_string());
+        opts.columns = vec!["url".to_string()];
+        in_savepoint(&db, |db| apply(db, &opts)).unwrap();
+        let urls = db.query_values("SELECT url FROM hosts ORDER BY name", &[]).unwrap();
+        assert_eq!(
+            urls,
+            vec![vec![CellValue::Text("a.b".to_string())], vec![CellValue::Text("new.example".to_string())]]
+        );
+    }
+}
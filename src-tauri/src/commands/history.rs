@@ -0,0 +1,232 @@
+//! Undo/Redo Commands
+//!
+//! Reverts and replays data changes recorded as changesets.
+
+use crate::sqlite3x::errors::Sqlite3xError;
+use crate::sqlite3x::wrapper::Database;
+use crate::state::{AppState, ChangeEntry, ChangeEntrySummary, MAX_CHANGESET_BYTES};
+use crate::utils::{AppResult, AppError};
+use serde::{Deserialize, Serialize};
+
+/// Session name used while recording a single command's changes
+const RECORD_SESSION: &str = "__change_history";
+
+/// Undo and redo stacks of a connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeHistoryInfo {
+    pub undo: Vec<ChangeEntrySummary>,
+    pub redo: Vec<ChangeEntrySummary>,
+}
+
+/// Run `f` while recording the changes it makes
+///
+/// Returns the result of `f` and the recorded changeset, or `None` when the
+/// changes could not be recorded.
+pub(crate) fn record_changes<T>(
+    db: &Database,
+    f: impl FnOnce(&Database) -> AppResult<T>,
+) -> AppResult<(T, Option<Vec<u8>>)> {
+    // Stops recording, rather than building the whole changeset, once it gets too large to keep
+    let recording = match db.start_limited_session(RECORD_SESSION, MAX_CHANGESET_BYTES) {
+        Ok(()) => true,
+        Err(e) => {
+            log::warn!("Changes will not be undoable: {}", e);
+            false
+        }
+    };
+
+    let result = f(db);
+
+    let changeset = if recording && result.is_ok() {
+        db.session_changeset(RECORD_SESSION)
+            .map_err(|e| log::warn!("Failed to read recorded changes: {}", e))
+            .ok()
+    } else {
+        None
+    };
+
+    if recording {
+        db.end_session(RECORD_SESSION);
+    }
+
+    result.map(|value| (value, changeset))
+}
+
+/// Undo the most recent recorded change on a connection
+#[tauri::command]
+pub async fn undo_last_change(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+) -> AppResult<ChangeEntrySummary> {
+    log::info!("Undoing last change on connection: {}", connection_id);
+
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    state.touch_transactions(&connection_id);
+
+    let entry = state.with_change_history(&connection_id, |h| h.pop_undo())
+        .ok_or_else(|| AppError::BadRequest("Nothing to undo".to_string()))?;
+
+    let result = db_handle.lock().apply_changeset(&entry.changeset, true);
+
+    if let Err(e) = result {
+        // Keep the entry so the user can resolve the conflict and retry
+        state.with_change_history(&connection_id, |h| h.push_undo(entry));
+        return Err(apply_error(e));
+    }
+
+    let summary = entry.summary();
+    state.with_change_history(&connection_id, |h| h.push_redo(entry));
+
+    log::info!("Undid change: {}", summary.description);
+
+    Ok(summary)
+}
+
+/// Re-apply the most recently undone change on a connection
+#[tauri::command]
+pub async fn redo_last_change(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+) -> AppResult<ChangeEntrySummary> {
+    log::info!("Redoing last change on connection: {}", connection_id);
+
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    state.touch_transactions(&connection_id);
+
+    let entry = state.with_change_history(&connection_id, |h| h.pop_redo())
+        .ok_or_else(|| AppError::BadRequest("Nothing to redo".to_string()))?;
+
+    let result = db_handle.lock().apply_changeset(&entry.changeset, false);
+
+    if let Err(e) = result {
+        state.with_change_history(&connection_id, |h| h.push_redo(entry));
+        return Err(apply_error(e));
+    }
+
+    let summary = entry.summary();
+    state.with_change_history(&connection_id, |h| h.push_undo(entry));
+
+    log::info!("Redid change: {}", summary.description);
+
+    Ok(summary)
+}
+
+/// Get the undo and redo stacks of a connection
+#[tauri::command]
+pub async fn get_change_history(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+) -> AppResult<ChangeHistoryInfo> {
+    if !state.has_connection(&connection_id) {
+        return Err(AppError::NotFound(format!("Connection not found: {}", connection_id)));
+    }
+
+    Ok(state.with_change_history(&connection_id, |h| ChangeHistoryInfo {
+        undo: h.undo_summaries(),
+        redo: h.redo_summaries(),
+    }))
+}
+
+/// Record a change produced by a command in the connection's history
+pub(crate) fn push_change(
+    state: &AppState,
+    connection_id: &str,
+    description: String,
+    affected_rows: usize,
+    changeset: Option<Vec<u8>>,
+) {
+    if let Some(changeset) = changeset {
+        let mut entry = ChangeEntry::new(description, affected_rows, changeset);
+        // Dropped again if the transaction is rolled back
+        entry.transaction_id = state.get_transactions(connection_id).into_iter().next().map(|t| t.id);
+        state.record_change(connection_id, entry);
+    }
+}
+
+fn apply_error(e: Sqlite3xError) -> AppError {
+    match e {
+        Sqlite3xError::ChangesetConflict(msg) => AppError::Conflict(msg),
+        other => AppError::QueryError(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo_update_without_where() {
+        let state = AppState::new();
+        let db = Database::open(":memory:").expect("Failed to open memory db");
+        db.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)").unwrap();
+        db.execute("INSERT INTO users VALUES (1, 'alice'), (2, 'bob')").unwrap();
+
+        let (affected, changeset) = record_changes(&db, |db| {
+            db.execute("UPDATE users SET name = 'oops'")
+                .map_err(|e| AppError::QueryError(e.to_string()))
+        })
+        .unwrap();
+        push_change(&state, "conn", "UPDATE users".to_string(), affected, changeset);
+
+        let entry = state.with_change_history("conn", |h| h.pop_undo()).unwrap();
+        assert_eq!(entry.affected_rows, 2);
+
+        // Undo restores the original names
+        db.apply_changeset(&entry.changeset, true).unwrap();
+        let names = db.query("SELECT name FROM users ORDER BY id").unwrap();
+        assert_eq!(names.rows, vec![vec![serde_json::json!("alice")], vec![serde_json::json!("bob")]]);
+
+        // Redo re-applies the update
+        db.apply_changeset(&entry.changeset, false).unwrap();
+        let names = db.query("SELECT name FROM users ORDER BY id").unwrap();
+        assert_eq!(names.rows, vec![vec![serde_json::json!("oops")], vec![serde_json::json!("oops")]]);
+
+        // Undo conflicts once the row changed again
+        db.execute("UPDATE users SET name = 'carol' WHERE id = 2").unwrap();
+        match db.apply_changeset(&entry.changeset, true) {
+            Err(Sqlite3xError::ChangesetConflict(msg)) => assert!(msg.contains("users")),
+            other => panic!("Expected conflict, got {:?}", other.map(|_| ())),
+        }
+        let names = db.query("SELECT name FROM users ORDER BY id").unwrap();
+        assert_eq!(names.rows[0], vec![serde_json::json!("oops")]);
+    }
+
+    #[test]
+    fn test_recording_limit_and_rollback() {
+        let db = Database::open(":memory:").expect("Failed to open memory db");
+        db.execute("CREATE TABLE items (id INTEGER PRIMARY KEY, body TEXT)").unwrap();
+
+        // Recording stops while the statement runs once over the limit
+        db.start_limited_session("limited", 4096).unwrap();
+        db.execute(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 5000) \
+             INSERT INTO items SELECT i, hex(randomblob(32)) FROM n",
+        )
+        .unwrap();
+        match db.session_changeset("limited") {
+            Err(e) => assert!(e.to_string().contains("limit")),
+            Ok(changeset) => panic!("Expected the limit to stop recording, got {} bytes", changeset.len()),
+        }
+        db.end_session("limited");
+        db.execute("DELETE FROM items").unwrap();
+
+        // Changes of a rolled back transaction leave the history
+        let state = AppState::new();
+        db.execute("BEGIN").unwrap();
+        let (affected, changeset) = record_changes(&db, |db| {
+            db.execute("INSERT INTO items VALUES (1, 'a')")
+                .map_err(|e| AppError::QueryError(e.to_string()))
+        })
+        .unwrap();
+        let mut entry = ChangeEntry::new("INSERT".to_string(), affected, changeset.unwrap());
+        entry.transaction_id = Some("tx".to_string());
+        state.record_change("conn", entry);
+        db.execute("ROLLBACK").unwrap();
+
+        state.with_change_history("conn", |h| h.end_transaction("tx", false));
+        assert!(state.with_change_history("conn", |h| h.pop_undo()).is_none());
+    }
+}
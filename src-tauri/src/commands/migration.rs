@@ -2,6 +2,8 @@
 //!
 //! Handles importing and exporting data (CSV, JSON).
 
+use crate::commands::history::{push_change, record_changes};
+use crate::sqlite3x::wrapper::Database;
 use crate::state::AppState;
 use crate::utils::{AppError, AppResult};
 use serde::{Deserialize, Serialize};
@@ -31,19 +33,29 @@ pub async fn import_data(
     let db_handle = state
         .get_db_handle(&connection_id)
         .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
-
-    let rows_processed = match format.to_lowercase().as_str() {
-        "csv" => import_csv(&db_handle, &table_name, &file_path).await?,
-        "json" => import_json(&db_handle, &table_name, &file_path).await?,
-        "sql" => import_sql(&db_handle, &file_path).await?,
-        _ => {
-            return Err(AppError::BadRequest(format!(
+    state.touch_transactions(&connection_id);
+
+    let (rows_processed, changeset) = {
+        let db = db_handle.lock();
+        record_changes(&db, |db| match format.to_lowercase().as_str() {
+            "csv" => import_csv(db, &table_name, &file_path),
+            "json" => import_json(db, &table_name, &file_path),
+            "sql" => import_sql(db, &file_path),
+            _ => Err(AppError::BadRequest(format!(
                 "Unsupported format: {}",
                 format
-            )))
-        }
+            ))),
+        })?
     };
 
+    push_change(
+        &state,
+        &connection_id,
+        format!("Import {} into {}", file_path, table_name),
+        rows_processed,
+        changeset,
+    );
+
     Ok(MigrationStats {
         rows_processed,
         success: true,
@@ -85,15 +97,13 @@ pub async fn export_data(
     })
 }
 
-async fn import_sql(
-    db_handle: &std::sync::Arc<parking_lot::Mutex<crate::sqlite3x::wrapper::Database>>,
+fn import_sql(
+    db: &Database,
     file_path: &str,
 ) -> AppResult<usize> {
     let sql_content = std::fs::read_to_string(file_path)
         .map_err(|e| AppError::IoError(format!("Failed to read SQL file: {}", e)))?;
 
-    let db = db_handle.lock();
-
     // We use execute_batch for SQL dumps which might contain multiple statements
     db.execute_batch(&sql_content)
         .map_err(|e| AppError::QueryError(format!("Failed to execute batch SQL: {:?}", e)))?;
@@ -185,8 +195,8 @@ async fn export_sql(
     Ok(count)
 }
 
-async fn import_csv(
-    db_handle: &std::sync::Arc<parking_lot::Mutex<crate::sqlite3x::wrapper::Database>>,
+fn import_csv(
+    db: &Database,
     table_name: &str,
     file_path: &str,
 ) -> AppResult<usize> {
@@ -205,8 +215,6 @@ async fn import_csv(
 
     // ... (rest of CSV logic)
 
-    let db = db_handle.lock();
-
     db.execute("BEGIN TRANSACTION")
         .map_err(|e| AppError::QueryError(format!("Failed to begin transaction: {:?}", e)))?;
 
@@ -241,8 +249,8 @@ async fn import_csv(
     Ok(count)
 }
 
-async fn import_json(
-    db_handle: &std::sync::Arc<parking_lot::Mutex<crate::sqlite3x::wrapper::Database>>,
+fn import_json(
+    db: &Database,
     table_name: &str,
     file_path: &str,
 ) -> AppResult<usize> {
@@ -284,7 +292,6 @@ async fn import_json(
         return Ok(0);
     }
 
-    let db = db_handle.lock();
     db.execute("BEGIN TRANSACTION")
         .map_err(|e| AppError::QueryError(format!("Failed to begin transaction: {:?}", e)))?;
 
@@ -447,6 +454,7 @@ pub async fn copy_table(
     let target_db_handle = state.get_db_handle(&target_db_id).ok_or_else(|| {
         AppError::NotFound(format!("Target connection not found: {}", target_db_id))
     })?;
+    state.touch_transactions(&target_db_id);
 
     // 1. Get CREATE SQL from source
     let create_sql = {
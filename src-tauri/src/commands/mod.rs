@@ -2,11 +2,17 @@
 //!
 //! This module contains all Tauri IPC command handlers organized by domain.
 
+pub mod browse;
+pub mod data_diff;
+pub mod data_edit;
 pub mod database;
 pub mod file;
+pub mod find_replace;
+pub mod history;
 pub mod query;
 pub mod schema;
 pub mod stats;
+pub mod test_data;
 pub mod transaction;
 pub mod udf;
 pub mod partition;
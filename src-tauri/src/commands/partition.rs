@@ -290,6 +290,7 @@ pub async fn run_partition_maintenance(
 
     let db_handle = state.get_db_handle(&connection_id)
         .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    state.touch_transactions(&connection_id);
 
     let db = db_handle.lock();
 
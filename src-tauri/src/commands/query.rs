@@ -1,3 +1,5 @@
+use crate::commands::history::{push_change, record_changes};
+use crate::events::transaction::sync_transaction_state;
 use crate::state::AppState;
 use crate::utils::{AppResult, AppError};
 use serde::{Deserialize, Serialize};
@@ -24,6 +26,7 @@ pub struct QueryResult {
 /// Execute a SQL query with a specific connection
 #[tauri::command]
 pub async fn execute_query(
+    app: tauri::AppHandle,
     state: tauri::State<'_, std::sync::Arc<AppState>>,
     connection_id: String,
     sql: String,
@@ -35,6 +38,9 @@ pub async fn execute_query(
     let db_handle = state.get_db_handle(&connection_id)
         .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
 
+    // Statements keep an open transaction from timing out
+    state.touch_transactions(&connection_id);
+
     let start = std::time::Instant::now();
 
     // Determine if this is a SELECT query or a statement
@@ -72,9 +78,26 @@ pub async fn execute_query(
         }
     } else {
         // Execute as statement (DDL, INSERT, UPDATE, DELETE, etc.)
-        let db = db_handle.lock();
-        let affected = db.execute(&sql)
-            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+        let executed = {
+            let db = db_handle.lock();
+            record_changes(&db, |db| {
+                db.execute(&sql).map_err(|e| AppError::QueryError(format!("{:?}", e)))
+            })
+        };
+
+        // A COMMIT typed in the editor keeps the transaction's changes undoable
+        if executed.is_ok() && (sql_upper.starts_with("COMMIT") || sql_upper.starts_with("END")) {
+            for transaction in state.get_transactions(&connection_id) {
+                state.with_change_history(&connection_id, |h| h.end_transaction(&transaction.id, true));
+            }
+        }
+
+        // COMMIT/ROLLBACK typed in the editor, or a failure that rolled back,
+        // changes the transaction state behind the transaction commands
+        sync_transaction_state(&app, &state, &connection_id);
+
+        let (affected, changeset) = executed?;
+        push_change(&state, &connection_id, sql.clone(), affected, changeset);
 
         let duration = start.elapsed();
         let execution_time_ms = duration.as_millis() as u64;
@@ -104,12 +127,13 @@ pub async fn execute_query(
 /// Legacy wrapper for execute_query (matches frontend calls if necessary)
 #[tauri::command]
 pub async fn execute_query_with_connection(
+    app: tauri::AppHandle,
     state: tauri::State<'_, std::sync::Arc<AppState>>,
     connection_id: String,
     sql: String,
     params: Option<Vec<serde_json::Value>>,
 ) -> AppResult<QueryResult> {
-    execute_query(state, connection_id, sql, params).await
+    execute_query(app, state, connection_id, sql, params).await
 }
 
 /// Get query execution plan
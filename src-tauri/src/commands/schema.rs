@@ -2,10 +2,19 @@
 //!
 //! Handles database schema inspection and metadata retrieval.
 
+use crate::commands::browse::CountMode;
+use crate::sqlite3x::types::CellValue;
+use crate::sqlite3x::wrapper::{
+    self, Database, ForeignKeyConstraint, ForeignKeyInfo, GeneratedKind, IndexColumn, IndexOrigin, TableKind,
+};
 use crate::state::AppState;
-use crate::utils::{AppResult, AppError};
+use crate::utils::{quote_identifier, AppResult, AppError};
+use crate::utils::er_diagram::{render_er_diagram, DiagramFormat, ErDiagramOptions};
+use crate::utils::schema_docs::{DocsFormat, SchemaDocs};
+use crate::utils::schema_search::{search_schema as search, SearchHit};
+use crate::utils::sidecar::Sidecar;
 use serde::{Deserialize, Serialize};
-
+use std::collections::HashMap;
 
 /// Column information for frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +26,11 @@ pub struct ColumnInfo {
     pub nullable: bool,
     pub primary_key: bool,
     pub default_value: Option<String>,
+    /// Hidden column of a virtual table
+    pub hidden: bool,
+    pub generated: Option<GeneratedKind>,
+    /// First foreign key the column takes part in
+    pub foreign_key: Option<ForeignKeyInfo>,
 }
 
 /// Table information for frontend
@@ -24,9 +38,24 @@ pub struct ColumnInfo {
 #[serde(rename_all = "camelCase")]
 pub struct TableInfo {
     pub name: String,
+    pub kind: TableKind,
+    pub strict: bool,
+    pub without_rowid: bool,
+    /// Module of a virtual table
+    pub module: Option<String>,
+    /// Shadow tables of a virtual table
+    pub shadow_tables: Vec<String>,
+    /// Virtual table a shadow table belongs to
+    pub shadow_of: Option<String>,
     pub columns: Vec<ColumnInfo>,
-    pub row_count: i64,
+    /// `None` when not counted
+    pub row_count: Option<i64>,
+    /// Whether `row_count` is an estimate rather than exact
+    pub row_count_estimated: bool,
     pub size_bytes: i64,
+    pub foreign_keys: Vec<ForeignKeyConstraint>,
+    /// Foreign keys in other tables that reference this one
+    pub referenced_by: Vec<ForeignKeyConstraint>,
 }
 
 /// View information for frontend
@@ -43,8 +72,15 @@ pub struct ViewInfo {
 pub struct IndexInfo {
     pub name: String,
     pub table_name: String,
+    /// Key column names, or the expression text for expression terms
     pub columns: Vec<String>,
+    pub key_columns: Vec<IndexColumn>,
     pub is_unique: bool,
+    pub origin: IndexOrigin,
+    pub is_partial: bool,
+    pub where_clause: Option<String>,
+    /// `None` for indexes SQLite creates for constraints
+    pub sql: Option<String>,
 }
 
 /// Trigger information for frontend
@@ -53,8 +89,16 @@ pub struct IndexInfo {
 pub struct TriggerInfo {
     pub name: String,
     pub table_name: String,
+    /// `BEFORE`, `AFTER` or `INSTEAD OF`; empty when the DDL could not be parsed
     pub timing: String,
+    /// `INSERT`, `UPDATE` or `DELETE`; empty when the DDL could not be parsed
     pub event: String,
+    /// Columns of an `UPDATE OF` trigger
+    pub update_columns: Vec<String>,
+    pub for_each_row: bool,
+    pub when_clause: Option<String>,
+    /// Body statements
+    pub body: Vec<String>,
     pub sql: String,
 }
 
@@ -69,10 +113,13 @@ pub struct SchemaInfo {
 }
 
 /// Get database schema
+///
+/// Row counts are estimated unless `row_counts` asks for exact counts or none.
 #[tauri::command]
 pub async fn get_schema(
     state: tauri::State<'_, std::sync::Arc<AppState>>,
     connection_id: String,
+    row_counts: Option<CountMode>,
 ) -> AppResult<SchemaInfo> {
     log::info!("Getting schema for connection: {}", connection_id);
 
@@ -84,69 +131,13 @@ pub async fn get_schema(
     let schema = db.get_schema()
         .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
 
-    // Convert wrapper types to frontend types with column information
-    let mut tables: Vec<TableInfo> = Vec::new();
-
-    for table in schema.tables.iter() {
-        // Get column information for each table
-        let pragma_sql = format!("PRAGMA table_info({})", table.name);
-        let result = db.query(&pragma_sql)
-            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
-
-        let mut columns = Vec::new();
-        for row in result.rows {
-            // PRAGMA table_info returns: cid, name, type, notnull, dflt_value, pk
-            if row.len() >= 6 {
-                let name = match &row[1] {
-                    serde_json::Value::String(s) => s.clone(),
-                    _ => continue,
-                };
-                let col_type = match &row[2] {
-                    serde_json::Value::String(s) => s.clone(),
-                    _ => "TEXT".to_string(),
-                };
-                let not_null = match &row[3] {
-                    serde_json::Value::Number(n) => n.as_i64().unwrap_or(0) != 0,
-                    _ => false,
-                };
-                let default_val = match &row[4] {
-                    serde_json::Value::String(s) => Some(s.clone()),
-                    serde_json::Value::Null => None,
-                    _ => None,
-                };
-                let pk = match &row[5] {
-                    serde_json::Value::Number(n) => n.as_i64().unwrap_or(0) != 0,
-                    _ => false,
-                };
-
-                columns.push(ColumnInfo {
-                    name,
-                    column_type: col_type,
-                    nullable: !not_null,
-                    primary_key: pk,
-                    default_value: default_val,
-                });
-            }
-        }
+    let table_names: Vec<&str> = schema.tables.iter().map(|t| t.name.as_str()).collect();
+    let mut counts = count_rows(&db, &table_names, row_counts.unwrap_or(CountMode::Estimate));
 
-        // Get row count
-        let count_sql = format!("SELECT COUNT(*) FROM \"{}\"", table.name);
-        let row_count = db.query(&count_sql).ok()
-            .and_then(|r| r.rows.first().cloned())
-            .and_then(|row| row.first().cloned())
-            .and_then(|v| match v {
-                serde_json::Value::Number(n) => n.as_i64(),
-                _ => None,
-            })
-            .unwrap_or(0);
-
-        tables.push(TableInfo {
-            name: table.name.clone(),
-            columns,
-            row_count,
-            size_bytes: 0,
-        });
-    }
+    // Convert wrapper types to frontend types
+    let tables: Vec<TableInfo> = schema.tables.iter()
+        .map(|table| to_table_info(table, counts.remove(&table.name)))
+        .collect();
 
     let views: Vec<ViewInfo> = schema.views.iter()
         .map(|v| ViewInfo {
@@ -160,17 +151,29 @@ pub async fn get_schema(
             name: i.name.clone(),
             table_name: i.table_name.clone(),
             columns: i.columns.clone(),
+            key_columns: i.key_columns.clone(),
             is_unique: i.unique,
+            origin: i.origin,
+            is_partial: i.partial,
+            where_clause: i.where_clause.clone(),
+            sql: i.sql.clone(),
         })
         .collect();
 
     let triggers: Vec<TriggerInfo> = schema.triggers.iter()
-        .map(|t| TriggerInfo {
-            name: t.name.clone(),
-            table_name: t.table_name.clone(),
-            timing: String::new(),
-            event: String::new(),
-            sql: t.sql.clone().unwrap_or_default(),
+        .map(|t| {
+            let definition = t.definition.as_ref();
+            TriggerInfo {
+                name: t.name.clone(),
+                table_name: t.table_name.clone(),
+                timing: definition.map(|d| d.timing.as_str().to_string()).unwrap_or_default(),
+                event: definition.map(|d| d.event.as_str().to_string()).unwrap_or_default(),
+                update_columns: definition.map(|d| d.update_columns.clone()).unwrap_or_default(),
+                for_each_row: definition.is_some_and(|d| d.for_each_row),
+                when_clause: definition.and_then(|d| d.when_clause.clone()),
+                body: definition.map(|d| d.body.clone()).unwrap_or_default(),
+                sql: t.sql.clone().unwrap_or_default(),
+            }
         })
         .collect();
 
@@ -190,12 +193,141 @@ pub async fn get_schema(
     })
 }
 
+/// Export tables and their relationships as an ER diagram
+///
+/// Returns Mermaid, Graphviz DOT or PlantUML text, depending on `format`.
+#[tauri::command]
+pub async fn export_er_diagram(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    format: DiagramFormat,
+    options: Option<ErDiagramOptions>,
+) -> AppResult<String> {
+    let options = options.unwrap_or_default();
+
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+
+    let schema = {
+        let db = db_handle.lock();
+        db.get_schema_of(options.schema.as_deref().unwrap_or("main"))
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?
+    };
+
+    render_er_diagram(&schema, format, &options)
+}
+
+/// Search names and view and trigger SQL in the main and all attached databases
+///
+/// Hits are ranked best first; `limit` defaults to 200.
+#[tauri::command]
+pub async fn search_schema(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    query: String,
+    limit: Option<usize>,
+) -> AppResult<Vec<SearchHit>> {
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    let db = db_handle.lock();
+
+    let databases = db.get_attached_databases()
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+    let mut hits = Vec::new();
+    for database in databases {
+        match db.get_schema_of(&database.name) {
+            Ok(schema) => hits.extend(search(&database.name, &schema, &query)),
+            Err(e) => log::warn!("Could not search schema {}: {:?}", database.name, e),
+        }
+    }
+
+    // Stable sort keeps the order within each database for equal scores
+    hits.sort_by_key(|hit| std::cmp::Reverse(hit.score));
+    hits.truncate(limit.unwrap_or(200));
+    Ok(hits)
+}
+
+/// Write a data dictionary of the database
+///
+/// Markdown goes to the file at `output_path`; HTML becomes a site in the
+/// directory at `output_path`. Returns the paths written.
+#[tauri::command]
+pub async fn generate_schema_docs(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    format: DocsFormat,
+    output_path: String,
+    row_counts: Option<CountMode>,
+) -> AppResult<Vec<String>> {
+    let db_path = crate::commands::schema_management::connection_path(&state, &connection_id)?;
+    let descriptions = match Sidecar::path_for(&db_path) {
+        Ok(_) => Sidecar::load(&db_path)?.descriptions,
+        Err(_) => Default::default(),
+    };
+
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+
+    let (schema, counts, sizes) = {
+        let db = db_handle.lock();
+        let schema = db.get_schema()
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+        let table_names: Vec<&str> = schema.tables.iter().map(|t| t.name.as_str()).collect();
+        let counts = count_rows(&db, &table_names, row_counts.unwrap_or(CountMode::Estimate));
+        let sizes = object_sizes(&db);
+        (schema, counts, sizes)
+    };
+
+    let title = std::path::Path::new(&db_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Database".to_string());
+    let docs = SchemaDocs {
+        title: format!("Data dictionary: {}", title),
+        schema: &schema,
+        row_counts: counts,
+        sizes,
+        descriptions: &descriptions,
+    };
+
+    let write = |path: &std::path::Path, content: &str| {
+        std::fs::write(path, content)
+            .map_err(|e| AppError::FsError(format!("Failed to write {}: {}", path.display(), e)))
+    };
+
+    let mut written = Vec::new();
+    match format {
+        DocsFormat::Markdown => {
+            let path = std::path::PathBuf::from(&output_path);
+            write(&path, &docs.to_markdown())?;
+            written.push(output_path.clone());
+        }
+        DocsFormat::Html => {
+            let dir = std::path::PathBuf::from(&output_path);
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| AppError::FsError(format!("Failed to create {}: {}", dir.display(), e)))?;
+            for (name, content) in docs.to_html_site() {
+                let path = dir.join(name);
+                write(&path, &content)?;
+                written.push(path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    log::info!("Schema documentation written to {}", output_path);
+    Ok(written)
+}
+
 /// Get detailed table information
+///
+/// The row count is exact unless `row_counts` asks for an estimate or none.
 #[tauri::command]
 pub async fn get_table_info(
     state: tauri::State<'_, std::sync::Arc<AppState>>,
     connection_id: String,
     table_name: String,
+    row_counts: Option<CountMode>,
 ) -> AppResult<TableInfo> {
     log::info!(
         "Getting table info for {} on connection: {}",
@@ -207,64 +339,131 @@ pub async fn get_table_info(
         .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
 
     let db = db_handle.lock();
-
-    // Query table columns using PRAGMA
-    let pragma_sql = format!("PRAGMA table_info({})", table_name);
-    let result = db.query(&pragma_sql)
+    let schema = db.get_schema()
         .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
 
-    let mut columns = Vec::new();
-    for row in result.rows {
-        // PRAGMA table_info returns: cid, name, type, notnull, dflt_value, pk
-        if row.len() >= 6 {
-            let name = match &row[1] {
-                serde_json::Value::String(s) => s.clone(),
-                _ => continue,
-            };
-            let col_type = match &row[2] {
-                serde_json::Value::String(s) => s.clone(),
-                _ => "TEXT".to_string(),
-            };
-            let not_null = match &row[3] {
-                serde_json::Value::Number(n) => n.as_i64().unwrap_or(0) != 0,
-                _ => false,
-            };
-            let default_val = match &row[4] {
-                serde_json::Value::String(s) => Some(s.clone()),
-                serde_json::Value::Null => None,
-                _ => None,
-            };
-            let pk = match &row[5] {
-                serde_json::Value::Number(n) => n.as_i64().unwrap_or(0) != 0,
-                _ => false,
-            };
-
-            columns.push(ColumnInfo {
-                name,
-                column_type: col_type,
-                nullable: !not_null,
-                primary_key: pk,
-                default_value: default_val,
-            });
-        }
-    }
+    let table = schema.tables.iter()
+        .find(|t| t.name == table_name)
+        .ok_or_else(|| AppError::NotFound(format!("Table not found: {}", table_name)))?;
 
-    // Get row count
-    let count_sql = format!("SELECT COUNT(*) FROM \"{}\"", table_name);
-    let count_result = db.query(&count_sql).ok();
-    let row_count = count_result
-        .and_then(|r| r.rows.first().cloned())
-        .and_then(|row| row.first().cloned())
-        .and_then(|v| match v {
-            serde_json::Value::Number(n) => n.as_i64(),
-            _ => None,
+    let mut counts = count_rows(&db, &[table_name.as_str()], row_counts.unwrap_or(CountMode::Exact));
+
+    Ok(to_table_info(table, counts.remove(&table_name)))
+}
+
+fn to_table_info(table: &wrapper::TableInfo, count: Option<(i64, bool)>) -> TableInfo {
+    let columns = table.columns.iter()
+        .map(|c| ColumnInfo {
+            name: c.name.clone(),
+            column_type: c.data_type.clone(),
+            nullable: !c.not_null,
+            primary_key: c.primary_key,
+            default_value: c.default_value.clone(),
+            hidden: c.hidden,
+            generated: c.generated,
+            foreign_key: c.foreign_key.clone(),
         })
-        .unwrap_or(0);
+        .collect();
 
-    Ok(TableInfo {
-        name: table_name,
+    TableInfo {
+        name: table.name.clone(),
+        kind: table.kind,
+        strict: table.strict,
+        without_rowid: table.without_rowid,
+        module: table.module.clone(),
+        shadow_tables: table.shadow_tables.clone(),
+        shadow_of: table.shadow_of.clone(),
         columns,
-        row_count,
+        row_count: count.map(|(n, _)| n),
+        row_count_estimated: count.map(|(_, estimated)| estimated).unwrap_or(false),
         size_bytes: 0,
-    })
+        foreign_keys: table.foreign_keys.clone(),
+        referenced_by: table.referenced_by.clone(),
+    }
+}
+
+/// Bytes used by each table and index, from the `dbstat` virtual table
+///
+/// Empty when SQLite was built without `dbstat`.
+fn object_sizes(db: &Database) -> HashMap<String, i64> {
+    db.query_values("SELECT name, SUM(pgsize) FROM dbstat WHERE schema = 'main' GROUP BY name", &[])
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [CellValue::Text(name), CellValue::Integer(size)] => Some((name.clone(), *size)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Row counts of tables as (count, estimated), leaving out tables that could not be counted
+///
+/// Estimates come from `sqlite_stat1` when `ANALYZE` has run, otherwise from
+/// the largest rowid, so neither scans a table.
+fn count_rows(db: &Database, tables: &[&str], mode: CountMode) -> HashMap<String, (i64, bool)> {
+    let mut counts = HashMap::new();
+
+    let single = |sql: String| -> Option<i64> {
+        db.query_values(&sql, &[]).ok()?
+            .into_iter().next()?
+            .into_iter().next()
+            .and_then(|v| match v {
+                CellValue::Integer(n) => Some(n),
+                CellValue::Null => Some(0),
+                _ => None,
+            })
+    };
+
+    match mode {
+        CountMode::None => {}
+        CountMode::Exact => {
+            for table in tables {
+                if let Some(n) = single(format!("SELECT COUNT(*) FROM {}", quote_identifier(table))) {
+                    counts.insert(table.to_string(), (n, false));
+                }
+            }
+        }
+        CountMode::Estimate => {
+            let mut stats: HashMap<String, i64> = HashMap::new();
+            let has_stats = db
+                .query_values("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'sqlite_stat1'", &[])
+                .map(|rows| !rows.is_empty())
+                .unwrap_or(false);
+            if has_stats {
+                let rows = db
+                    .query_values("SELECT tbl, MAX(CAST(stat AS INTEGER)) FROM sqlite_stat1 GROUP BY tbl", &[])
+                    .unwrap_or_default();
+                for row in rows {
+                    if let [CellValue::Text(table), CellValue::Integer(n)] = row.as_slice() {
+                        stats.insert(table.clone(), *n);
+                    }
+                }
+            }
+
+            let rowid_tables: Vec<String> = db
+                .query_values("SELECT name FROM pragma_table_list WHERE schema = 'main' AND type = 'table' AND wr = 0", &[])
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|row| match row.into_iter().next() {
+                    Some(CellValue::Text(name)) => Some(name),
+                    _ => None,
+                })
+                .collect();
+
+            for table in tables {
+                let estimate = stats.get(*table).copied().or_else(|| {
+                    if rowid_tables.iter().any(|t| t == table) {
+                        single(format!("SELECT MAX(rowid) FROM {}", quote_identifier(table)))
+                    } else {
+                        None
+                    }
+                });
+                if let Some(n) = estimate {
+                    counts.insert(table.to_string(), (n, true));
+                }
+            }
+        }
+    }
+
+    counts
 }
@@ -1,33 +1,1001 @@
+use crate::sqlite3x::dependencies::{dropped_objects, Dependency, DependencyGraph, DropImpact};
+use crate::sqlite3x::rebuild::{check_compiles, rebuild_table, RebuildScript};
+use crate::sqlite3x::table_definition::TableDefinition;
+use crate::sqlite3x::wrapper::{Database, SchemaInfo};
+use crate::sqlite3x::types::{CellValue, OpenFlags};
 use crate::state::AppState;
-use crate::utils::{AppResult, AppError};
-use crate::utils::schema_diff::{compare_schemas, SchemaDiffResult};
+use crate::utils::{quote_identifier, AppResult, AppError};
+use crate::utils::schema_diff::{compare_schemas, compare_schemas_with_renames, ModifiedObject, Rename, SchemaDiffResult};
+use crate::utils::sidecar::{DisabledTrigger, ObjectDescription, Sidecar};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
+/// Outcome of `alter_table`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlterTableResult {
+    /// The rebuild as one script
+    pub script: String,
+    /// Objects that could not be carried over
+    pub warnings: Vec<String>,
+    /// False for a dry run
+    pub applied: bool,
+}
+
+/// One side of a schema diff
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SchemaSource {
+    /// An open connection
+    #[serde(rename_all = "camelCase")]
+    Connection { connection_id: String },
+    /// A database file, opened read-only to diff and read-write to migrate
+    File { path: String },
+    /// A schema saved by `save_schema_snapshot`
+    Snapshot { path: String },
+}
+
+impl SchemaSource {
+    fn describe(&self) -> String {
+        match self {
+            SchemaSource::Connection { connection_id } => format!("connection {}", connection_id),
+            SchemaSource::File { path } => path.clone(),
+            SchemaSource::Snapshot { path } => format!("snapshot {}", path),
+        }
+    }
+}
+
+/// Load the schema of a diff source
+fn load_source_schema(state: &AppState, source: &SchemaSource) -> AppResult<SchemaInfo> {
+    match source {
+        SchemaSource::Connection { connection_id } => load_schema(state, connection_id),
+        SchemaSource::File { path } => {
+            let flags = OpenFlags { read_only: true, read_write: false, create: false, wal_mode: false };
+            let db = Database::open_with_flags(path, flags)
+                .map_err(|e| AppError::ConnectionError(format!("Failed to open {}: {:?}", path, e)))?;
+            db.get_schema().map_err(|e| AppError::QueryError(format!("{:?}", e)))
+        }
+        SchemaSource::Snapshot { path } => {
+            let json = std::fs::read_to_string(path)
+                .map_err(|e| AppError::FsError(format!("Failed to read {}: {}", path, e)))?;
+            serde_json::from_str(&json)
+                .map_err(|e| AppError::SerializationError(format!("Invalid schema snapshot {}: {}", path, e)))
+        }
+    }
+}
+
+/// Compare two schemas; the migration turns `target` into `source`
+///
+/// `renames` are confirmed renames of the target's tables and columns,
+/// usually picked from the result's `renameCandidates`.
 #[tauri::command]
 pub async fn diff_schemas(
     state: tauri::State<'_, std::sync::Arc<AppState>>,
-    source_connection_id: String,
-    target_path: String,
+    source: SchemaSource,
+    target: SchemaSource,
+    renames: Option<Vec<Rename>>,
 ) -> AppResult<SchemaDiffResult> {
-    log::info!("Comparing schema of {} with {}", source_connection_id, target_path);
+    log::info!("Comparing schema of {} with {}", source.describe(), target.describe());
+
+    let source_schema = load_source_schema(&state, &source)?;
+    let target_schema = load_source_schema(&state, &target)?;
+
+    compare_schemas_with_renames(&source_schema, &target_schema, &renames.unwrap_or_default())
+}
+
+/// Save the schema of a connection as JSON, to diff against later
+#[tauri::command]
+pub async fn save_schema_snapshot(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    output_path: String,
+) -> AppResult<()> {
+    let schema = load_schema(&state, &connection_id)?;
+    let json = serde_json::to_string_pretty(&schema)
+        .map_err(|e| AppError::SerializationError(format!("{}", e)))?;
+    std::fs::write(&output_path, json)
+        .map_err(|e| AppError::FsError(format!("Failed to write {}: {}", output_path, e)))?;
+
+    log::info!("Saved schema snapshot of {} to {}", connection_id, output_path);
+    Ok(())
+}
+
+/// Outcome of `apply_schema_diff`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplySchemaDiffResult {
+    /// The statements that ran, in order
+    pub statements: Vec<String>,
+    /// False for a dry run, which is rolled back
+    pub applied: bool,
+    /// Whether the target matched the source afterwards
+    pub converged: bool,
+    /// What a new diff still found, when it didn't converge
+    pub remaining: Vec<ModifiedObject>,
+    pub remaining_statements: Vec<String>,
+}
+
+/// Migrate `target`, a connection or file, to the schema of `source`
+///
+/// The migration runs in one transaction with foreign keys off and is then
+/// diffed again. With `dry_run` it is rolled back after that, so only
+/// whether the script works is checked.
+#[tauri::command]
+pub async fn apply_schema_diff(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    source: SchemaSource,
+    target: SchemaSource,
+    renames: Option<Vec<Rename>>,
+    dry_run: Option<bool>,
+) -> AppResult<ApplySchemaDiffResult> {
+    let renames = renames.unwrap_or_default();
+    let source_schema = load_source_schema(&state, &source)?;
+    let dry_run = dry_run.unwrap_or(false);
+    log::info!(
+        "{} schema of {} on {}",
+        if dry_run { "Trying migration to" } else { "Migrating to" },
+        source.describe(),
+        target.describe()
+    );
+
+    let migrate = |db: &Database| {
+        let target_schema = db.get_schema()
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+        let diff = compare_schemas_with_renames(&source_schema, &target_schema, &renames)?;
+        run_migration(db, &source_schema, &diff.migration, dry_run)
+    };
+
+    match &target {
+        SchemaSource::Connection { connection_id } => {
+            let db_handle = state.get_db_handle(connection_id)
+                .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+            state.touch_transactions(connection_id);
+            let db = db_handle.lock();
+            migrate(&db)
+        }
+        SchemaSource::File { path } => {
+            // The file must exist and keeps its journal mode
+            let flags = OpenFlags { read_only: false, read_write: true, create: false, wal_mode: false };
+            let db = Database::open_with_flags(path, flags)
+                .map_err(|e| AppError::ConnectionError(format!("Failed to open {}: {:?}", path, e)))?;
+            migrate(&db)
+        }
+        SchemaSource::Snapshot { .. } => Err(AppError::BadRequest("A schema snapshot cannot be migrated".to_string())),
+    }
+}
+
+/// Run migration statements in one transaction with foreign keys off and
+/// diff the result against `source`; rolled back for a dry run or on error
+pub(crate) fn run_migration(
+    db: &Database,
+    source: &SchemaInfo,
+    migration: &[String],
+    dry_run: bool,
+) -> AppResult<ApplySchemaDiffResult> {
+    let is_autocommit = db.is_autocommit()
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    if !is_autocommit {
+        return Err(AppError::BadRequest(
+            "Commit or roll back the open transaction before migrating".to_string(),
+        ));
+    }
+
+    // Switched here instead; the script's own PRAGMAs do nothing in a transaction
+    let statements: Vec<String> = migration.iter()
+        .filter(|s| !s.starts_with("--") && !s.to_ascii_uppercase().starts_with("PRAGMA FOREIGN_KEYS"))
+        .cloned()
+        .collect();
+
+    // Foreign keys can only be switched outside a transaction
+    let foreign_keys = matches!(
+        db.query_values("PRAGMA foreign_keys", &[]).as_deref(),
+        Ok([row]) if row.first() == Some(&CellValue::Integer(1))
+    );
+    if foreign_keys {
+        db.execute_batch("PRAGMA foreign_keys = OFF")
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    }
+    // Renames must rewrite views, triggers and foreign keys as the diff expects
+    let legacy = matches!(
+        db.query_values("PRAGMA legacy_alter_table", &[]).as_deref(),
+        Ok([row]) if row.first() == Some(&CellValue::Integer(1))
+    );
+    if legacy {
+        db.execute_batch("PRAGMA legacy_alter_table = OFF")
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    }
+
+    let result = (|| {
+        db.execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+        for (i, statement) in statements.iter().enumerate() {
+            db.execute_batch(statement).map_err(|e| {
+                AppError::QueryError(format!("Statement {} of {} failed: {}\n{}", i + 1, statements.len(), e, statement))
+            })?;
+        }
+
+        let violations = db
+            .query_values("SELECT COUNT(*) FROM pragma_foreign_key_check", &[])
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+        if let Some(CellValue::Integer(n)) = violations.first().and_then(|row| row.first()) {
+            if *n > 0 {
+                return Err(AppError::Conflict(format!("{} rows violate foreign keys after the migration", n)));
+            }
+        }
+
+        let schema = db.get_schema()
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+        let after = compare_schemas(source, &schema);
+        let remaining_statements: Vec<String> = after.migration.into_iter()
+            .filter(|s| !s.starts_with("--"))
+            .collect();
+
+        db.execute_batch(if dry_run { "ROLLBACK" } else { "COMMIT" })
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+        if dry_run {
+            // The rolled back schema must not be served for the restored version
+            db.clear_schema_cache();
+        }
+
+        Ok(ApplySchemaDiffResult {
+            statements: statements.clone(),
+            applied: !dry_run,
+            converged: after.modified.is_empty() && remaining_statements.is_empty(),
+            remaining: after.modified,
+            remaining_statements,
+        })
+    })();
+
+    if result.is_err() && !db.is_autocommit().unwrap_or(true) {
+        if let Err(e) = db.execute_batch("ROLLBACK") {
+            log::error!("Failed to roll back migration: {}", e);
+        }
+    }
+    if legacy {
+        if let Err(e) = db.execute_batch("PRAGMA legacy_alter_table = ON") {
+            log::error!("Failed to restore legacy_alter_table: {}", e);
+        }
+    }
+    if foreign_keys {
+        if let Err(e) = db.execute_batch("PRAGMA foreign_keys = ON") {
+            log::error!("Failed to re-enable foreign keys: {}", e);
+        }
+    }
+
+    result
+}
+
+/// Create a table from a structured definition
+///
+/// Returns the generated DDL. With `dry_run` the DDL is only generated.
+#[tauri::command]
+pub async fn create_table(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    definition: TableDefinition,
+    dry_run: Option<bool>,
+) -> AppResult<String> {
+    let sql = definition.to_sql()
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    if dry_run.unwrap_or(false) {
+        return Ok(sql);
+    }
+
+    log::info!("Creating table {} on {}", definition.name, connection_id);
+
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    state.touch_transactions(&connection_id);
+    let db = db_handle.lock();
+    db.execute(&sql)
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+    Ok(sql)
+}
+
+/// Change a table in ways ALTER TABLE cannot by rebuilding it
+///
+/// `definition` is the table as it should be; `renamed_columns` maps new
+/// column names to the old columns their values come from. Indexes,
+/// triggers and dependent views are recreated, and foreign keys are checked
+/// before the rebuild commits. A dry run only returns the script.
+#[tauri::command]
+pub async fn alter_table(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    table_name: String,
+    definition: TableDefinition,
+    renamed_columns: Option<HashMap<String, String>>,
+    dry_run: Option<bool>,
+) -> AppResult<AlterTableResult> {
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    state.touch_transactions(&connection_id);
+    let db = db_handle.lock();
+
+    let schema = db.get_schema()
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    let script = rebuild_table(&schema, &table_name, &definition, &renamed_columns.unwrap_or_default())
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run {
+        log::info!("Rebuilding table {} on {}", table_name, connection_id);
+        run_rebuild(&db, &script)?;
+    }
+
+    Ok(AlterTableResult {
+        script: script.to_sql(),
+        warnings: script.warnings,
+        applied: !dry_run,
+    })
+}
+
+/// Run a rebuild in its own transaction with foreign keys off
+pub(crate) fn run_rebuild(db: &Database, script: &RebuildScript) -> AppResult<()> {
+    let is_autocommit = db.is_autocommit()
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    if !is_autocommit {
+        return Err(AppError::BadRequest(
+            "Commit or roll back the open transaction before altering a table".to_string(),
+        ));
+    }
+
+    // Foreign keys can only be switched outside a transaction
+    let foreign_keys = matches!(
+        db.query_values("PRAGMA foreign_keys", &[]).as_deref(),
+        Ok([row]) if row.first() == Some(&CellValue::Integer(1))
+    );
+    if foreign_keys {
+        db.execute_batch("PRAGMA foreign_keys = OFF")
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    }
+
+    let result = (|| {
+        db.execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+        for statement in &script.statements {
+            db.execute(statement)
+                .map_err(|e| AppError::QueryError(format!("{}\n{}", e, statement)))?;
+        }
+
+        for table in &script.check_tables {
+            let violations = db
+                .query_values("SELECT COUNT(*) FROM pragma_foreign_key_check(?1)", &[CellValue::Text(table.clone())])
+                .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+            if let Some(CellValue::Integer(n)) = violations.first().and_then(|row| row.first()) {
+                if *n > 0 {
+                    return Err(AppError::Conflict(format!("{} rows of {} violate foreign keys", n, table)));
+                }
+            }
+        }
+
+        check_compiles(db, &script.views, &script.triggers)
+            .map_err(|e| AppError::Conflict(e.to_string()))?;
+
+        db.execute_batch("COMMIT")
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))
+    })();
+
+    if result.is_err() {
+        if let Err(e) = db.execute_batch("ROLLBACK") {
+            log::error!("Failed to roll back table rebuild: {}", e);
+        }
+    }
+    if foreign_keys {
+        if let Err(e) = db.execute_batch("PRAGMA foreign_keys = ON") {
+            log::error!("Failed to re-enable foreign keys: {}", e);
+        }
+    }
+
+    result
+}
+
+/// Outcome of `rename_table` and `rename_column`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameResult {
+    /// The ALTER TABLE statement that ran
+    pub statement: String,
+    /// Views and triggers whose SQL SQLite rewrote
+    pub updated_views: Vec<String>,
+    pub updated_triggers: Vec<String>,
+    /// Tables whose partition policy was updated
+    pub partition_policies: Vec<String>,
+    /// Whether descriptions or disabled triggers in the sidecar file were updated
+    pub sidecar_updated: bool,
+}
+
+/// Rename a table, updating views, triggers and the app's own references to it
+#[tauri::command]
+pub async fn rename_table(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    table_name: String,
+    new_name: String,
+) -> AppResult<RenameResult> {
+    log::info!("Renaming table {} to {} on {}", table_name, new_name, connection_id);
+
+    let path = connection_path(&state, &connection_id)?;
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    state.touch_transactions(&connection_id);
+    let db = db_handle.lock();
+
+    let schema = db.get_schema()
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    let table = schema.tables.iter()
+        .find(|t| t.name.eq_ignore_ascii_case(&table_name))
+        .ok_or_else(|| AppError::NotFound(format!("Table not found: {}", table_name)))?;
+
+    let statement = format!("ALTER TABLE {} RENAME TO {}", quote_identifier(&table.name), quote_identifier(&new_name));
+    let (updated_views, updated_triggers) = run_rename(&db, &statement)?;
+
+    let mut partition_policies = Vec::new();
+    if let Some(manager) = db.get_partition_manager() {
+        if manager.rename_policy_table(&table.name, &new_name) {
+            partition_policies.push(new_name.clone());
+            save_partition_metadata(&path, &manager);
+        }
+    }
+
+    // The rename is committed; metadata that can't follow is logged, not undone
+    let sidecar_updated = update_sidecar(&path, |sidecar| sidecar.rename_table(&table.name, &new_name))
+        .unwrap_or_else(|e| {
+            log::warn!("Could not update the sidecar file after renaming {}: {}", table.name, e);
+            false
+        });
+
+    Ok(RenameResult { statement, updated_views, updated_triggers, partition_policies, sidecar_updated })
+}
+
+/// Rename a column, updating views, triggers and the app's own references to it
+#[tauri::command]
+pub async fn rename_column(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    table_name: String,
+    column_name: String,
+    new_name: String,
+) -> AppResult<RenameResult> {
+    log::info!("Renaming column {}.{} to {} on {}", table_name, column_name, new_name, connection_id);
+
+    let path = connection_path(&state, &connection_id)?;
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    state.touch_transactions(&connection_id);
+    let db = db_handle.lock();
+
+    let schema = db.get_schema()
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    let table = schema.tables.iter()
+        .find(|t| t.name.eq_ignore_ascii_case(&table_name))
+        .ok_or_else(|| AppError::NotFound(format!("Table not found: {}", table_name)))?;
+    let column = table.columns.iter()
+        .find(|c| c.name.eq_ignore_ascii_case(&column_name))
+        .ok_or_else(|| AppError::NotFound(format!("{} has no column {}", table.name, column_name)))?;
+
+    let statement = format!(
+        "ALTER TABLE {} RENAME COLUMN {} TO {}",
+        quote_identifier(&table.name),
+        quote_identifier(&column.name),
+        quote_identifier(&new_name)
+    );
+    let (updated_views, updated_triggers) = run_rename(&db, &statement)?;
+
+    let mut partition_policies = Vec::new();
+    if let Some(manager) = db.get_partition_manager() {
+        if manager.rename_policy_column(&table.name, &column.name, &new_name) {
+            partition_policies.push(table.name.clone());
+            save_partition_metadata(&path, &manager);
+        }
+    }
+
+    // The rename is committed; metadata that can't follow is logged, not undone
+    let sidecar_updated = update_sidecar(&path, |sidecar| sidecar.rename_column(&schema, &table.name, &column.name, &new_name))
+        .unwrap_or_else(|e| {
+            log::warn!("Could not update the sidecar file after renaming {}.{}: {}", table.name, column.name, e);
+            false
+        });
+
+    Ok(RenameResult { statement, updated_views, updated_triggers, partition_policies, sidecar_updated })
+}
+
+/// Run a RENAME with the modern ALTER TABLE behavior and check that every view and trigger still compiles
+///
+/// Returns the views and triggers whose SQL changed.
+fn run_rename(db: &Database, statement: &str) -> AppResult<(Vec<String>, Vec<String>)> {
+    let is_autocommit = db.is_autocommit()
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    if !is_autocommit {
+        return Err(AppError::BadRequest(
+            "Commit or roll back the open transaction before renaming".to_string(),
+        ));
+    }
+
+    // With legacy_alter_table on, SQLite leaves views and triggers pointing at the old name
+    let legacy = matches!(
+        db.query_values("PRAGMA legacy_alter_table", &[]).as_deref(),
+        Ok([row]) if row.first() == Some(&CellValue::Integer(1))
+    );
+    if legacy {
+        db.execute_batch("PRAGMA legacy_alter_table = OFF")
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    }
+
+    let definitions = |db: &Database| -> AppResult<Vec<(String, String, String)>> {
+        let rows = db
+            .query_values("SELECT type, name, sql FROM sqlite_master WHERE type IN ('view', 'trigger') ORDER BY name", &[])
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+        Ok(rows.into_iter().filter_map(|row| match row.as_slice() {
+            [CellValue::Text(kind), CellValue::Text(name), CellValue::Text(sql)] => Some((kind.clone(), name.clone(), sql.clone())),
+            _ => None,
+        }).collect())
+    };
+
+    let result = (|| {
+        db.execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+        let before = definitions(db)?;
+
+        db.execute(statement)
+            .map_err(|e| AppError::QueryError(format!("{}\n{}", e, statement)))?;
+
+        let after = definitions(db)?;
+        let names = |wanted: &str| -> Vec<String> {
+            after.iter().filter(|(kind, _, _)| kind == wanted).map(|(_, name, _)| name.clone()).collect()
+        };
+        check_compiles(db, &names("view"), &names("trigger"))
+            .map_err(|e| AppError::Conflict(e.to_string()))?;
+
+        db.execute_batch("COMMIT")
+            .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+        let mut views = Vec::new();
+        let mut triggers = Vec::new();
+        for (kind, name, sql) in after {
+            if !before.iter().any(|(_, n, s)| *n == name && *s == sql) {
+                if kind == "view" { views.push(name) } else { triggers.push(name) }
+            }
+        }
+        Ok((views, triggers))
+    })();
+
+    if result.is_err() && !db.is_autocommit().unwrap_or(true) {
+        if let Err(e) = db.execute_batch("ROLLBACK") {
+            log::error!("Failed to roll back rename: {}", e);
+        }
+    }
+    if legacy {
+        if let Err(e) = db.execute_batch("PRAGMA legacy_alter_table = ON") {
+            log::error!("Failed to restore legacy_alter_table: {}", e);
+        }
+    }
+
+    result
+}
+
+/// Persist partition policies if the database keeps a partition metadata file
+fn save_partition_metadata(db_path: &str, manager: &crate::sqlite3x::PartitionManager) {
+    let metadata_path = crate::sqlite3x::PartitionMetadata::get_default_path(db_path);
+    if !std::path::Path::new(&metadata_path).exists() {
+        return;
+    }
+    let result = crate::sqlite3x::PartitionMetadata::load(&metadata_path).and_then(|mut metadata| {
+        metadata.config = manager.get_config().read().clone();
+        metadata.updated_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(metadata.updated_at);
+        metadata.save(&metadata_path)
+    });
+    if let Err(e) = result {
+        log::warn!("Could not update partition metadata {}: {:?}", metadata_path, e);
+    }
+}
 
-    // 1. Get source schema
-    let db_handle = state.get_db_handle(&source_connection_id)
-        .ok_or_else(|| AppError::InternalError(format!("Source connection not found: {}", source_connection_id)))?;
+/// Apply `change` to the sidecar file and save it if it reports a change
+fn update_sidecar(db_path: &str, change: impl FnOnce(&mut Sidecar) -> bool) -> AppResult<bool> {
+    if Sidecar::path_for(db_path).is_err() {
+        return Ok(false);
+    }
+    let mut sidecar = Sidecar::load(db_path)?;
+    if !change(&mut sidecar) {
+        return Ok(false);
+    }
+    sidecar.save(db_path)?;
+    Ok(true)
+}
+
+/// Disable a trigger by dropping it
+///
+/// Its DDL is kept in the database's sidecar file until `enable_trigger`
+/// recreates it.
+#[tauri::command]
+pub async fn disable_trigger(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    trigger_name: String,
+) -> AppResult<DisabledTrigger> {
+    log::info!("Disabling trigger {} on {}", trigger_name, connection_id);
+
+    let path = connection_path(&state, &connection_id)?;
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    state.touch_transactions(&connection_id);
+    let db = db_handle.lock();
 
-    let source_schema = {
-        let db = db_handle.lock();
-        db.get_schema().map_err(|e| AppError::InternalError(format!("{:?}", e)))?
+    let rows = db
+        .query_values(
+            "SELECT tbl_name, sql FROM sqlite_master WHERE type = 'trigger' AND name = ?1",
+            &[CellValue::Text(trigger_name.clone())],
+        )
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    let (table_name, sql) = match rows.into_iter().next().as_deref() {
+        Some([CellValue::Text(table_name), CellValue::Text(sql)]) => (table_name.clone(), sql.clone()),
+        _ => return Err(AppError::NotFound(format!("Trigger not found: {}", trigger_name))),
     };
 
-    // 2. Get target schema (Directly open and read)
-    let target_db = crate::sqlite3x::wrapper::Database::open(&target_path)
-        .map_err(|e| AppError::ConnectionError(format!("Failed to open target DB: {:?}", e)))?;
+    let disabled = DisabledTrigger {
+        name: trigger_name.clone(),
+        table_name,
+        sql,
+        disabled_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+
+    // Save the DDL first so a failed write never loses the trigger
+    let mut sidecar = Sidecar::load(&path)?;
+    sidecar.disabled_triggers.retain(|t| t.name != trigger_name);
+    sidecar.disabled_triggers.push(disabled.clone());
+    sidecar.save(&path)?;
+
+    if let Err(e) = db.execute(&format!("DROP TRIGGER {}", quote_identifier(&trigger_name))) {
+        sidecar.disabled_triggers.retain(|t| t.name != trigger_name);
+        sidecar.save(&path)?;
+        return Err(AppError::QueryError(format!("{:?}", e)));
+    }
+
+    Ok(disabled)
+}
+
+/// Recreate a trigger dropped by `disable_trigger`
+#[tauri::command]
+pub async fn enable_trigger(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    trigger_name: String,
+) -> AppResult<()> {
+    log::info!("Enabling trigger {} on {}", trigger_name, connection_id);
+
+    let path = connection_path(&state, &connection_id)?;
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    state.touch_transactions(&connection_id);
+    let db = db_handle.lock();
+
+    let mut sidecar = Sidecar::load(&path)?;
+    let position = sidecar.disabled_triggers.iter()
+        .position(|t| t.name == trigger_name)
+        .ok_or_else(|| AppError::NotFound(format!("Disabled trigger not found: {}", trigger_name)))?;
+
+    // Fails, and keeps the DDL, if the table or a referenced column is gone
+    db.execute(&sidecar.disabled_triggers[position].sql)
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+    sidecar.disabled_triggers.remove(position);
+    sidecar.save(&path)
+}
+
+/// Triggers of a database that are currently disabled
+#[tauri::command]
+pub async fn list_disabled_triggers(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+) -> AppResult<Vec<DisabledTrigger>> {
+    let path = connection_path(&state, &connection_id)?;
+    if Sidecar::path_for(&path).is_err() {
+        return Ok(Vec::new());
+    }
+
+    Ok(Sidecar::load(&path)?.disabled_triggers)
+}
+
+/// Objects that `object_name` uses: tables and views for views and triggers,
+/// the table of an index, referenced tables for a table
+#[tauri::command]
+pub async fn get_dependencies(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    object_name: String,
+) -> AppResult<Vec<Dependency>> {
+    let schema = load_schema(&state, &connection_id)?;
+    let graph = DependencyGraph::build(&schema);
+    Ok(graph.dependencies(&object_name).into_iter().cloned().collect())
+}
+
+/// Objects that use `object_name`
+///
+/// With `transitive`, views and triggers reached through other views are included.
+#[tauri::command]
+pub async fn get_dependents(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    object_name: String,
+    transitive: Option<bool>,
+) -> AppResult<Vec<Dependency>> {
+    let schema = load_schema(&state, &connection_id)?;
+    let graph = DependencyGraph::build(&schema);
+
+    let mut dependents: Vec<Dependency> = graph.dependents(&object_name).into_iter().cloned().collect();
+    if transitive.unwrap_or(false) {
+        for edge in graph.view_and_trigger_dependents(&object_name) {
+            if !dependents.iter().any(|d| d.dependent == edge.dependent && d.target == edge.target) {
+                dependents.push(edge.clone());
+            }
+        }
+    }
+    Ok(dependents)
+}
+
+/// What dropping an object, or a column of a table, would take with it or break
+#[tauri::command]
+pub async fn get_drop_impact(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    object_name: String,
+    column_name: Option<String>,
+) -> AppResult<DropImpact> {
+    let (schema, policies) = load_schema_and_policies(&state, &connection_id)?;
+    DependencyGraph::build(&schema)
+        .drop_impact(&schema, &object_name, column_name.as_deref(), &policies)
+        .ok_or_else(|| AppError::NotFound(format!("Object not found: {}", object_name)))
+}
+
+/// Impact of every DROP statement, and ALTER TABLE ... DROP COLUMN, in `sql`
+///
+/// Meant to be checked before the statements are run with `execute_query`.
+/// Objects that don't exist are left out.
+#[tauri::command]
+pub async fn get_statement_impact(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    sql: String,
+) -> AppResult<Vec<DropImpact>> {
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    let db = db_handle.lock();
+    statement_impact(&db, &sql)
+}
+
+/// Impact of the drops in `sql`; SQL of any shape, even half typed, is accepted
+fn statement_impact(db: &Database, sql: &str) -> AppResult<Vec<DropImpact>> {
+    let dropped = dropped_objects(sql);
+    if dropped.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (schema, policies) = schema_and_policies(db)?;
+    let graph = DependencyGraph::build(&schema);
+    Ok(dropped
+        .iter()
+        .filter_map(|(name, column)| graph.drop_impact(&schema, name, column.as_deref(), &policies))
+        .collect())
+}
+
+fn load_schema(state: &AppState, connection_id: &str) -> AppResult<SchemaInfo> {
+    let db_handle = state.get_db_handle(connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    let db = db_handle.lock();
+    db.get_schema().map_err(|e| AppError::QueryError(format!("{:?}", e)))
+}
+
+/// The schema and the (table, date column) pairs of the connection's partition policies
+fn load_schema_and_policies(state: &AppState, connection_id: &str) -> AppResult<(SchemaInfo, Vec<(String, String)>)> {
+    let db_handle = state.get_db_handle(connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+    let db = db_handle.lock();
+    schema_and_policies(&db)
+}
+
+fn schema_and_policies(db: &Database) -> AppResult<(SchemaInfo, Vec<(String, String)>)> {
+    let schema = db.get_schema().map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    let policies = db.get_partition_manager()
+        .map(|manager| {
+            manager.get_config().read().policies.iter()
+                .map(|p| (p.table_name.clone(), p.date_column.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok((schema, policies))
+}
+
+/// Descriptions of schema objects kept in the sidecar file, by object name
+#[tauri::command]
+pub async fn get_object_descriptions(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+) -> AppResult<BTreeMap<String, ObjectDescription>> {
+    let path = connection_path(&state, &connection_id)?;
+    if Sidecar::path_for(&path).is_err() {
+        return Ok(BTreeMap::new());
+    }
+
+    Ok(Sidecar::load(&path)?.descriptions)
+}
+
+/// Describe a table, view, index or trigger, or a column of a table
+///
+/// An empty description removes it.
+#[tauri::command]
+pub async fn set_object_description(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    object_name: String,
+    column_name: Option<String>,
+    description: String,
+) -> AppResult<()> {
+    let path = connection_path(&state, &connection_id)?;
+
+    let mut sidecar = Sidecar::load(&path)?;
+    sidecar.set_description(&object_name, column_name.as_deref(), &description);
+    sidecar.save(&path)
+}
+
+pub(crate) fn connection_path(state: &AppState, connection_id: &str) -> AppResult<String> {
+    state.get_connection(connection_id)
+        .map(|c| c.path)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_rename() {
+        let db = Database::open(":memory:").unwrap();
+        db.execute_batch(
+            "PRAGMA legacy_alter_table = ON;
+             CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE TABLE log (message TEXT);
+             CREATE VIEW names AS SELECT name FROM users;
+             CREATE TRIGGER users_log AFTER INSERT ON users BEGIN INSERT INTO log VALUES (NEW.name); END;",
+        )
+        .unwrap();
+
+        let (views, triggers) = run_rename(&db, "ALTER TABLE users RENAME COLUMN name TO full_name").unwrap();
+        assert_eq!(views, vec!["names"]);
+        assert_eq!(triggers, vec!["users_log"]);
+
+        let (views, _) = run_rename(&db, "ALTER TABLE users RENAME TO people").unwrap();
+        assert_eq!(views, vec!["names"]);
+        assert_eq!(db.query_values("SELECT * FROM names", &[]).unwrap().len(), 0);
+
+        // The legacy setting is restored afterwards
+        let legacy = db.query_values("PRAGMA legacy_alter_table", &[]).unwrap();
+        assert_eq!(legacy[0][0], CellValue::Integer(1));
+
+        // A failing rename leaves the schema unchanged
+        assert!(run_rename(&db, "ALTER TABLE people RENAME COLUMN nope TO other").is_err());
+        assert!(db.is_autocommit().unwrap());
+
+        // A broken trigger rolls the rename back
+        db.execute_batch(
+            "CREATE TABLE audit (people_id INTEGER);
+             CREATE TRIGGER audit_check BEFORE INSERT ON audit BEGIN SELECT missing FROM people; END;",
+        )
+        .unwrap();
+        let error = run_rename(&db, "ALTER TABLE audit RENAME COLUMN people_id TO person_id").unwrap_err();
+        assert!(error.to_string().contains("audit_check"), "{}", error);
+        assert!(db.query_values("SELECT people_id FROM audit", &[]).is_ok());
+    }
+
+    #[test]
+    fn test_statement_impact_of_partial_sql() {
+        let db = Database::open(":memory:").unwrap();
+        db.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE VIEW names AS SELECT name FROM users;",
+        )
+        .unwrap();
+
+        // Statements as they look while being typed
+        for sql in ["DROP TABLE \"", "DROP TABLE [", "DROP TABLE '", "ALTER TABLE users DROP COLUMN \"", "ALTER TABLE [", "DROP"] {
+            assert!(statement_impact(&db, sql).is_ok(), "{}", sql);
+        }
+
+        let impact = statement_impact(&db, "DROP TABLE \"users").unwrap();
+        assert_eq!(impact.len(), 1);
+        assert_eq!(impact[0].broken[0].dependent, "names");
+    }
+
+    #[test]
+    fn test_sidecar_rename_column() {
+        let db = Database::open(":memory:").unwrap();
+        db.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE TABLE log (name TEXT, user_id INTEGER);",
+        )
+        .unwrap();
+        let schema = db.get_schema().unwrap();
+
+        let trigger = |name: &str, table: &str, sql: &str| DisabledTrigger {
+            name: name.to_string(),
+            table_name: table.to_string(),
+            sql: sql.to_string(),
+            disabled_at: 0,
+        };
+        let mut sidecar = Sidecar {
+            disabled_triggers: vec![
+                trigger(
+                    "users_log",
+                    "users",
+                    "CREATE TRIGGER users_log AFTER INSERT ON users BEGIN INSERT INTO log (name, user_id) VALUES (NEW.name, NEW.id); END",
+                ),
+                trigger(
+                    "log_check",
+                    "log",
+                    "CREATE TRIGGER log_check AFTER INSERT ON log BEGIN SELECT name FROM users WHERE users.name = NEW.name; END",
+                ),
+            ],
+            ..Default::default()
+        };
+
+        assert!(sidecar.rename_column(&schema, "users", "name", "full_name"));
+        // The log table's own name column is left alone
+        assert_eq!(
+            sidecar.disabled_triggers[0].sql,
+            "CREATE TRIGGER users_log AFTER INSERT ON users BEGIN INSERT INTO log (name, user_id) VALUES (NEW.\"full_name\", NEW.id); END"
+        );
+        assert_eq!(
+            sidecar.disabled_triggers[1].sql,
+            "CREATE TRIGGER log_check AFTER INSERT ON log BEGIN SELECT \"full_name\" FROM users WHERE users.\"full_name\" = NEW.name; END"
+        );
+    }
+
+    #[test]
+    fn test_run_migration() {
+        let source = Database::open(":memory:").unwrap();
+        source.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL DEFAULT '');
+             CREATE VIEW names AS SELECT name FROM users;",
+        )
+        .unwrap();
+        let source = source.get_schema().unwrap();
+
+        let target = Database::open(":memory:").unwrap();
+        target.execute_batch(
+            "PRAGMA foreign_keys = ON;
+             CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, legacy TEXT);
+             CREATE VIEW names AS SELECT name, legacy FROM users;
+             INSERT INTO users VALUES (1, 'ann', 'x');",
+        )
+        .unwrap();
+        let migration = compare_schemas(&source, &target.get_schema().unwrap()).migration;
 
-    let target_schema = target_db.get_schema()
-        .map_err(|e| AppError::InternalError(format!("{:?}", e)))?;
+        // A dry run proves the script and leaves the target as it was
+        let result = run_migration(&target, &source, &migration, true).unwrap();
+        assert!(result.converged && !result.applied);
+        assert_eq!(target.get_schema().unwrap().tables[0].columns.len(), 3);
+        assert_eq!(target.get_schema().unwrap().views[0].sql.as_deref(), Some("CREATE VIEW names AS SELECT name, legacy FROM users"));
 
-    // 3. Compare
-    let diff = compare_schemas(&source_schema, &target_schema);
+        let result = run_migration(&target, &source, &migration, false).unwrap();
+        assert!(result.converged, "{:?}", result.remaining_statements);
+        assert_eq!(target.query_values("SELECT * FROM names", &[]).unwrap().len(), 1);
+        let foreign_keys = target.query_values("PRAGMA foreign_keys", &[]).unwrap();
+        assert_eq!(foreign_keys[0][0], CellValue::Integer(1));
 
-    Ok(diff)
+        // The failing statement is reported and nothing is kept
+        let broken = vec!["CREATE TABLE extra (id INTEGER)".to_string(), "DROP TABLE missing".to_string()];
+        let error = run_migration(&target, &source, &broken, false).unwrap_err().to_string();
+        assert!(error.contains("Statement 2 of 2") && error.contains("DROP TABLE missing"), "{}", error);
+        assert!(target.is_autocommit().unwrap());
+        assert!(!target.get_schema().unwrap().tables.iter().any(|t| t.name == "extra"));
+    }
 }
@@ -0,0 +1,555 @@
+//! Test Data Commands
+//!
+//! Populate tables with generated rows that satisfy the table's constraints.
+
+use crate::commands::data_edit::run_bulk_edit;
+use crate::sqlite3x::table_definition::TableDefinition;
+use crate::sqlite3x::types::CellValue;
+use crate::sqlite3x::errors::Sqlite3xError;
+use crate::sqlite3x::wrapper::{ColumnInfo, Database, TableInfo};
+use crate::state::AppState;
+use crate::utils::data_generator::{affinity, infer_generator, Affinity, ColumnGenerator, DataRng, ValueGenerator};
+use crate::utils::{quote_identifier, AppError, AppResult};
+use rand::{Rng, SeedableRng};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Attempts at a row before it is skipped because of constraint failures
+const MAX_ROW_ATTEMPTS: usize = 10;
+
+/// Parent key values sampled for a foreign key column
+const MAX_PARENT_SAMPLE: usize = 10_000;
+
+/// Chance of NULL in nullable columns without an override
+const DEFAULT_NULL_PROBABILITY: f64 = 0.1;
+
+/// Largest number of rows generated by one call
+const MAX_ROW_COUNT: usize = 1_000_000;
+
+/// Test data generation request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestDataOptions {
+    pub row_count: usize,
+    /// Seed for reproducible data; a random seed is used and reported when absent
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Generators by column name, replacing the inferred ones
+    #[serde(default)]
+    pub overrides: HashMap<String, ColumnGenerator>,
+    #[serde(default)]
+    pub null_probability: Option<f64>,
+}
+
+/// Test data generation outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestDataResult {
+    pub inserted_rows: usize,
+    /// Rows given up on after repeated constraint failures
+    pub skipped_rows: usize,
+    pub seed: u64,
+}
+
+/// How one column gets its values
+enum ColumnSource {
+    Generated(ValueGenerator),
+    /// Sampled from the referenced parent rows
+    Parent(Vec<CellValue>),
+}
+
+struct ColumnPlan {
+    name: String,
+    source: ColumnSource,
+    /// Whether this column alone must be unique
+    unique: bool,
+    /// Chance of NULL instead of a generated value
+    null_probability: f64,
+    /// Declared maximum length of text values, e.g. `VARCHAR(20)`
+    max_length: Option<usize>,
+}
+
+/// Generate rows for a table and insert them in one transaction
+///
+/// Generated rows are not recorded for undo; delete them to take them back.
+#[tauri::command]
+pub async fn generate_test_data(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    table_name: String,
+    options: TestDataOptions,
+) -> AppResult<TestDataResult> {
+    log::info!(
+        "Generating {} test rows for {} on {}",
+        options.row_count,
+        table_name,
+        connection_id
+    );
+
+    let result = run_bulk_edit(&state, &connection_id, |db| generate(db, &table_name, &options))?;
+
+    log::info!(
+        "Inserted {} test rows into {} ({} skipped, seed {})",
+        result.inserted_rows,
+        table_name,
+        result.skipped_rows,
+        result.seed
+    );
+
+    Ok(result)
+}
+
+/// Generate and insert rows; must run inside a transaction
+pub(crate) fn generate(db: &Database, table: &str, options: &TestDataOptions) -> AppResult<TestDataResult> {
+    if options.row_count > MAX_ROW_COUNT {
+        return Err(AppError::BadRequest(format!("At most {} rows can be generated at once", MAX_ROW_COUNT)));
+    }
+
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let mut rng = DataRng::seed_from_u64(seed);
+
+    let schema = db.get_schema()
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    let info = schema.tables.iter()
+        .find(|t| t.name == table)
+        .ok_or_else(|| AppError::NotFound(format!("Table not found: {}", table)))?;
+
+    for column in options.overrides.keys() {
+        match info.columns.iter().find(|c| &c.name == column) {
+            None => return Err(AppError::BadRequest(format!("Unknown column: {}", column))),
+            Some(c) if !c.is_writable() => {
+                return Err(AppError::BadRequest(format!("Column {} is generated or hidden", column)))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let mut plans = plan_columns(db, info, options)?;
+    plans.retain(|plan| !matches!(&plan.source, ColumnSource::Generated(g) if g.is_default()));
+
+    let sql = if plans.is_empty() {
+        format!("INSERT INTO {} DEFAULT VALUES", quote_identifier(table))
+    } else {
+        format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_identifier(table),
+            plans.iter().map(|p| quote_identifier(&p.name)).collect::<Vec<_>>().join(", "),
+            vec!["?"; plans.len()].join(", ")
+        )
+    };
+
+    let mut inserted_rows = 0;
+    let mut skipped_rows = 0;
+
+    for _ in 0..options.row_count {
+        let mut inserted = false;
+
+        for _ in 0..MAX_ROW_ATTEMPTS {
+            let row: Vec<CellValue> = plans.iter_mut().map(|plan| next_value(plan, &mut rng)).collect();
+
+            match db.execute_values(&sql, &row) {
+                Ok(_) => {
+                    inserted = true;
+                    break;
+                }
+                // UNIQUE and CHECK failures are retried with fresh values
+                Err(Sqlite3xError::Constraint(_)) => continue,
+                Err(e) => return Err(AppError::QueryError(format!("{:?}", e))),
+            }
+        }
+
+        if inserted {
+            inserted_rows += 1;
+        } else {
+            skipped_rows += 1;
+        }
+    }
+
+    Ok(TestDataResult { inserted_rows, skipped_rows, seed })
+}
+
+fn next_value(plan: &mut ColumnPlan, rng: &mut DataRng) -> CellValue {
+    if plan.null_probability > 0.0 && rng.gen_bool(plan.null_probability) {
+        return CellValue::Null;
+    }
+
+    let value = match &mut plan.source {
+        ColumnSource::Generated(generator) => generator.next(rng, plan.unique && !generator.is_distinct()),
+        ColumnSource::Parent(values) => values[rng.gen_range(0..values.len())].clone(),
+    };
+
+    match (value, plan.max_length) {
+        (CellValue::Text(s), Some(max)) if s.chars().count() > max => {
+            // Keep the end, where unique suffixes go
+            let skip = s.chars().count() - max;
+            CellValue::Text(s.chars().skip(skip).collect())
+        }
+        (value, _) => value,
+    }
+}
+
+/// Decide how each column of the table is filled
+fn plan_columns(db: &Database, info: &TableInfo, options: &TestDataOptions) -> AppResult<Vec<ColumnPlan>> {
+    let null_probability = options.null_probability.unwrap_or(DEFAULT_NULL_PROBABILITY);
+    if !(0.0..=1.0).contains(&null_probability) {
+        return Err(AppError::BadRequest("Null probability must be between 0 and 1".to_string()));
+    }
+
+    let checks = TableDefinition::from_schema(info)
+        .map(|definition| check_hints(&definition.checks().collect::<Vec<_>>()))
+        .unwrap_or_default();
+    let key = db.get_row_key(&info.name)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    // The rowid alias is assigned by SQLite
+    let is_rowid_alias = |column: &ColumnInfo| {
+        key.has_rowid
+            && key.columns.len() == 1
+            && key.columns[0] == column.name
+            && column.data_type.eq_ignore_ascii_case("INTEGER")
+    };
+
+    let unique_sets = unique_column_sets(db, &info.name)?;
+    let mut unique_columns: Vec<&str> = unique_sets.iter()
+        .filter(|set| set.len() == 1)
+        .map(|set| set[0].as_str())
+        .collect();
+
+    // A composite UNIQUE holds once one of its columns is unique on its own;
+    // where none can be, duplicates are left to retries
+    for set in unique_sets.iter().filter(|set| set.len() > 1) {
+        let columns: Vec<&ColumnInfo> = set.iter()
+            .filter_map(|name| info.columns.iter().find(|c| &c.name == name))
+            .collect();
+        if columns.iter().any(|c| unique_columns.contains(&c.name.as_str()) || is_rowid_alias(c)) {
+            continue;
+        }
+        if let Some(column) = columns.into_iter().find(|c| can_generate_unique(c, options, &checks)) {
+            unique_columns.push(&column.name);
+        }
+    }
+
+    let mut plans = Vec::new();
+
+    for column in info.columns.iter().filter(|c| c.is_writable()) {
+        let unique = unique_columns.contains(&column.name.as_str());
+
+        if let Some(generator) = options.overrides.get(&column.name) {
+            let generator = ValueGenerator::new(generator.clone())
+                .map_err(|e| AppError::BadRequest(format!("{}: {}", column.name, e)))?;
+            plans.push(ColumnPlan {
+                name: column.name.clone(),
+                source: ColumnSource::Generated(generator),
+                unique,
+                null_probability: 0.0,
+                max_length: None,
+            });
+            continue;
+        }
+
+        if is_rowid_alias(column) || column.default_value.is_some() {
+            continue;
+        }
+
+        let nullable = !column.not_null && !column.primary_key;
+        let null_probability = if nullable && !unique { null_probability } else { 0.0 };
+
+        let source = if let Some(fk) = &column.foreign_key {
+            let parents = parent_values(db, &fk.table, &fk.column)?;
+            if parents.is_empty() {
+                if nullable {
+                    ColumnSource::Generated(generator(ColumnGenerator::Null)?)
+                } else {
+                    return Err(AppError::BadRequest(format!(
+                        "{} references {}, which has no rows",
+                        column.name, fk.table
+                    )));
+                }
+            } else {
+                ColumnSource::Parent(parents)
+            }
+        } else {
+            let inferred = match checks.get(&column.name.to_lowercase()) {
+                Some(CheckHint::OneOf(values)) => ColumnGenerator::Enum { values: values.clone() },
+                Some(CheckHint::Range(min, max)) => match affinity(&column.data_type) {
+                    Affinity::Integer => ColumnGenerator::Integer { min: min.ceil() as i64, max: max.floor() as i64 },
+                    _ => ColumnGenerator::Real { min: *min, max: *max },
+                },
+                None => match infer_generator(&column.name, &column.data_type) {
+                    // Unique numbers count up from the current maximum
+                    ColumnGenerator::Integer { .. } if unique => ColumnGenerator::Sequence {
+                        start: max_integer(db, &info.name, &column.name)? + 1,
+                        step: 1,
+                    },
+                    inferred => inferred,
+                },
+            };
+            ColumnSource::Generated(generator(inferred)?)
+        };
+
+        plans.push(ColumnPlan {
+            name: column.name.clone(),
+            source,
+            unique,
+            null_probability,
+            max_length: declared_length(&column.data_type),
+        });
+    }
+
+    Ok(plans)
+}
+
+fn generator(kind: ColumnGenerator) -> AppResult<ValueGenerator> {
+    ValueGenerator::new(kind).map_err(AppError::BadRequest)
+}
+
+/// Whether values of a column can be kept from repeating without retries
+fn can_generate_unique(column: &ColumnInfo, options: &TestDataOptions, checks: &HashMap<String, CheckHint>) -> bool {
+    if !column.is_writable() {
+        return false;
+    }
+    if let Some(generator) = options.overrides.get(&column.name) {
+        return ValueGenerator::new(generator.clone()).is_ok_and(|g| g.can_be_unique());
+    }
+    if column.foreign_key.is_some() || column.default_value.is_some() || checks.contains_key(&column.name.to_lowercase()) {
+        return false;
+    }
+
+    match infer_generator(&column.name, &column.data_type) {
+        // Becomes a sequence once unique
+        ColumnGenerator::Integer { .. } => true,
+        inferred => ValueGenerator::new(inferred).is_ok_and(|g| g.can_be_unique()),
+    }
+}
+
+/// Column lists of the table's unique indexes and primary key
+///
+/// Expression keys are left out of the lists.
+fn unique_column_sets(db: &Database, table: &str) -> AppResult<Vec<Vec<String>>> {
+    let rows = db
+        .query_values(
+            "SELECT il.name, ii.name FROM pragma_index_list(?1) il, pragma_index_info(il.name) ii
+             WHERE il.\"unique\" = 1
+             ORDER BY il.name, ii.seqno",
+            &[CellValue::Text(table.to_string())],
+        )
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+    let mut sets: Vec<(String, Vec<String>)> = Vec::new();
+    for row in rows {
+        let [CellValue::Text(index), column] = row.as_slice() else { continue };
+        if sets.last().map_or(true, |(name, _)| name != index) {
+            sets.push((index.clone(), Vec::new()));
+        }
+        if let (CellValue::Text(column), Some((_, columns))) = (column, sets.last_mut()) {
+            columns.push(column.clone());
+        }
+    }
+
+    Ok(sets.into_iter().map(|(_, columns)| columns).filter(|c| !c.is_empty()).collect())
+}
+
+/// Existing key values of a parent table, in a stable order so seeds reproduce
+fn parent_values(db: &Database, table: &str, column: &str) -> AppResult<Vec<CellValue>> {
+    // A foreign key without a column refers to the parent's primary key
+    let column = if column.is_empty() {
+        let key = db.get_row_key(table)
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+        key.columns[0].clone()
+    } else {
+        column.to_string()
+    };
+
+    let sql = format!(
+        "SELECT DISTINCT {col} FROM {table} WHERE {col} IS NOT NULL ORDER BY {col} LIMIT {limit}",
+        col = quote_identifier(&column),
+        table = quote_identifier(table),
+        limit = MAX_PARENT_SAMPLE
+    );
+
+    let rows = db.query_values(&sql, &[])
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+    Ok(rows.into_iter().filter_map(|row| row.into_iter().next()).collect())
+}
+
+fn max_integer(db: &Database, table: &str, column: &str) -> AppResult<i64> {
+    let sql = format!("SELECT MAX({}) FROM {}", quote_identifier(column), quote_identifier(table));
+    let rows = db.query_values(&sql, &[])
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+    Ok(match rows.first().and_then(|r| r.first()) {
+        Some(CellValue::Integer(n)) => *n,
+        Some(CellValue::Real(f)) => *f as i64,
+        _ => 0,
+    })
+}
+
+/// Maximum length declared in a type such as `VARCHAR(20)`
+fn declared_length(declared_type: &str) -> Option<usize> {
+    if affinity(declared_type) != Affinity::Text {
+        return None;
+    }
+    let open = declared_type.find('(')?;
+    let close = declared_type[open..].find(')')? + open;
+    declared_type[open + 1..close].split(',').next()?.trim().parse().ok()
+}
+
+/// Value restriction read from a CHECK constraint
+#[derive(Debug, Clone, PartialEq)]
+enum CheckHint {
+    OneOf(Vec<CellValue>),
+    Range(f64, f64),
+}
+
+/// Simple CHECK constraints by lowercase column name
+///
+/// Recognizes `col IN (...)`, `col BETWEEN a AND b` and comparisons of a
+/// column with a number. Anything else is left to retries on failure.
+fn check_hints(checks: &[&str]) -> HashMap<String, CheckHint> {
+    let mut hints = HashMap::new();
+    let ident = r#"["`\[]?(\w+)["`\]]?"#;
+    let number = r"(-?\d+(?:\.\d+)?)";
+
+    let one_of = Regex::new(&format!(r"(?i)^\s*{}\s+IN\s*\(([^)]*)\)\s*$", ident))
+        .expect("valid regex");
+    let between = Regex::new(&format!(r"(?i)^\s*{}\s+BETWEEN\s+{}\s+AND\s+{}\s*$", ident, number, number))
+        .expect("valid regex");
+    let comparison = Regex::new(&format!(r"{}\s*(>=|<=|>|<)\s*{}", ident, number)).expect("valid regex");
+
+    for check in checks {
+        if let Some(caps) = one_of.captures(check) {
+            let values: Vec<CellValue> = caps[2].split(',').filter_map(|v| parse_literal(v.trim())).collect();
+            if !values.is_empty() {
+                hints.insert(caps[1].to_lowercase(), CheckHint::OneOf(values));
+            }
+            continue;
+        }
+
+        if let Some(caps) = between.captures(check) {
+            if let (Ok(min), Ok(max)) = (caps[2].parse::<f64>(), caps[3].parse::<f64>()) {
+                hints.insert(caps[1].to_lowercase(), CheckHint::Range(min, max));
+            }
+            continue;
+        }
+
+        // Function calls and alternatives are left alone
+        if check.contains('(') || check.to_uppercase().contains(" OR ") {
+            continue;
+        }
+        for caps in comparison.captures_iter(check) {
+            let Ok(bound) = caps[3].parse::<f64>() else { continue };
+            let column = caps[1].to_lowercase();
+            let (mut min, mut max) = match hints.get(&column) {
+                Some(CheckHint::Range(min, max)) => (*min, *max),
+                Some(CheckHint::OneOf(_)) => continue,
+                None => (0.0, 10_000.0_f64.max(bound)),
+            };
+            match &caps[2] {
+                ">=" => min = bound,
+                ">" => min = bound + 1.0,
+                "<=" => max = bound,
+                _ => max = bound - 1.0,
+            }
+            if min > max {
+                max = min + 10_000.0;
+            }
+            hints.insert(column, CheckHint::Range(min, max));
+        }
+    }
+
+    hints
+}
+
+fn parse_literal(s: &str) -> Option<CellValue> {
+    if s.len() >= 2 && s.starts_with('\'') && s.ends_with('\'') {
+        return Some(CellValue::Text(s[1..s.len() - 1].replace("''", "'")));
+    }
+    if let Ok(i) = s.parse::<i64>() {
+        return Some(CellValue::Integer(i));
+    }
+    s.parse::<f64>().ok().map(CellValue::Real)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(row_count: usize, seed: u64) -> TestDataOptions {
+        TestDataOptions { row_count, seed: Some(seed), overrides: HashMap::new(), null_probability: None }
+    }
+
+    #[test]
+    fn test_generate_respects_constraints() {
+        let db = Database::open(":memory:").expect("Failed to open memory db");
+        db.execute_batch(
+            "CREATE TABLE teams (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+             INSERT INTO teams (name) VALUES ('red'), ('blue');
+             CREATE TABLE players (
+                 id INTEGER PRIMARY KEY,
+                 email VARCHAR(40) NOT NULL UNIQUE,
+                 role TEXT NOT NULL CHECK (role IN ('lead', 'member')),
+                 score INTEGER CHECK (score BETWEEN 1 AND 5),
+                 status TEXT DEFAULT 'active',
+                 code TEXT,
+                 team_id INTEGER NOT NULL REFERENCES teams(id)
+             );",
+        )
+        .unwrap();
+
+        let mut opts = options(50, 7);
+        opts.overrides.insert("code".to_string(), ColumnGenerator::Pattern { regex: r"[A-Z]{3}-\d{4}".to_string() });
+
+        let result = generate(&db, "players", &opts).unwrap();
+        assert_eq!(result.inserted_rows, 50);
+        assert_eq!(result.skipped_rows, 0);
+
+        let invalid = db
+            .query_values(
+                "SELECT COUNT(*) FROM players WHERE length(email) > 40 OR status <> 'active'
+                 OR team_id NOT IN (1, 2) OR code NOT GLOB '[A-Z][A-Z][A-Z]-[0-9][0-9][0-9][0-9]'",
+                &[],
+            )
+            .unwrap();
+        assert_eq!(invalid[0][0], CellValue::Integer(0));
+
+        // The same seed reproduces the same rows
+        let first = db.query_values("SELECT email, role, score, code FROM players ORDER BY id", &[]).unwrap();
+        db.execute("DELETE FROM players").unwrap();
+        generate(&db, "players", &opts).unwrap();
+        let second = db.query_values("SELECT email, role, score, code FROM players ORDER BY id", &[]).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_generate_composite_unique() {
+        let db = Database::open(":memory:").expect("Failed to open memory db");
+        db.execute(
+            "CREATE TABLE offices (
+                 id INTEGER PRIMARY KEY,
+                 wing TEXT NOT NULL CHECK (wing IN ('east', 'west')),
+                 city TEXT NOT NULL,
+                 UNIQUE (wing, city)
+             )",
+        )
+        .unwrap();
+
+        // 2 wings and 20 cities only make 40 distinct pairs
+        let mut opts = options(100, 3);
+        opts.overrides.insert("city".to_string(), ColumnGenerator::City);
+
+        let result = generate(&db, "offices", &opts).unwrap();
+        assert_eq!(result.inserted_rows, 100);
+        assert_eq!(result.skipped_rows, 0);
+    }
+
+    #[test]
+    fn test_check_hints() {
+        let hints = check_hints(&["a >= 10 AND a < 20", "\"b\" IN ('x', 'y''s')"]);
+        assert_eq!(hints.get("a"), Some(&CheckHint::Range(10.0, 19.0)));
+        assert_eq!(
+            hints.get("b"),
+            Some(&CheckHint::OneOf(vec![CellValue::Text("x".to_string()), CellValue::Text("y's".to_string())]))
+        );
+    }
+}
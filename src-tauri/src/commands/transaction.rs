@@ -2,15 +2,21 @@
 //!
 //! Handles database transaction lifecycle.
 
-use crate::state::AppState;
+use crate::events::transaction::sync_transaction_state;
+use crate::sqlite3x::session::TableChanges;
+use crate::state::{AppState, TransactionMode, TransactionTimeoutConfig};
 use crate::utils::{AppResult, AppError};
 use uuid::Uuid;
 
 /// Begin a new transaction
+///
+/// `mode` is one of `deferred` (default), `immediate` or `exclusive`.
 #[tauri::command]
 pub async fn begin_transaction(
+    app: tauri::AppHandle,
     state: tauri::State<'_, std::sync::Arc<AppState>>,
     connection_id: String,
+    mode: Option<String>,
 ) -> AppResult<String> {
     log::info!("Beginning transaction on connection: {}", connection_id);
 
@@ -19,6 +25,14 @@ pub async fn begin_transaction(
         return Err(AppError::NotFound(format!("Connection not found: {}", connection_id)));
     }
 
+    let mode = match mode {
+        Some(m) => m.parse::<TransactionMode>().map_err(AppError::BadRequest)?,
+        None => TransactionMode::default(),
+    };
+
+    // Drop stale transactions before asking SQLite for a new one
+    sync_transaction_state(&app, &state, &connection_id);
+
     // Generate transaction ID
     let transaction_id = Uuid::new_v4().to_string();
 
@@ -29,19 +43,24 @@ pub async fn begin_transaction(
     // Actually begin transaction using sqlite3x
     {
         let db = db_handle.lock();
-        db.execute("BEGIN TRANSACTION")
+        db.execute(mode.begin_sql())
             .map_err(|e| AppError::QueryError(format!("Failed to begin transaction: {:?}", e)))?;
+
+        // Record the transaction's changes for get_pending_changes
+        if let Err(e) = db.start_session(&transaction_id) {
+            log::warn!("Failed to attach change session to {}: {}", transaction_id, e);
+        }
     }
 
     // Add to state
-    if let Err(e) = state.add_transaction(&connection_id, &transaction_id) {
+    if let Err(e) = state.add_transaction(&connection_id, &transaction_id, mode) {
         // Attempt to rollback if adding to state fails
         let db = db_handle.lock();
         let _ = db.execute("ROLLBACK");
         return Err(AppError::InternalError(e));
     }
 
-    log::info!("Transaction started: {}", transaction_id);
+    log::info!("Transaction started: {} ({:?})", transaction_id, mode);
 
     Ok(transaction_id)
 }
@@ -49,6 +68,7 @@ pub async fn begin_transaction(
 /// Commit a transaction
 #[tauri::command]
 pub async fn commit_transaction(
+    app: tauri::AppHandle,
     state: tauri::State<'_, std::sync::Arc<AppState>>,
     transaction_id: String,
 ) -> AppResult<()> {
@@ -65,25 +85,159 @@ pub async fn commit_transaction(
         .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
 
     // Actually commit transaction using sqlite3x
-    {
+    let result = {
         let db = db_handle.lock();
-        db.execute("COMMIT")
-            .map_err(|e| AppError::QueryError(format!("Failed to commit transaction: {:?}", e)))?;
+        let result = db.execute("COMMIT");
+        if result.is_ok() {
+            db.end_session(&transaction_id);
+        }
+        result
+    };
+
+    if let Err(e) = result {
+        // The transaction may already have ended outside our control
+        sync_transaction_state(&app, &state, &connection_id);
+        return Err(AppError::QueryError(format!("Failed to commit transaction: {:?}", e)));
     }
 
     state.remove_transaction(&transaction_id)
         .map_err(|e| AppError::InternalError(e))?;
+    state.with_change_history(&connection_id, |h| h.end_transaction(&transaction_id, true));
 
     log::info!("Transaction committed: {}", transaction_id);
 
     Ok(())
 }
 
+/// Rollback a transaction
+#[tauri::command]
+pub async fn rollback_transaction(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    transaction_id: String,
+) -> AppResult<()> {
+    let connection_id = state.get_transaction(&transaction_id).map(|t| t.connection_id);
+    let result = rollback_transaction_impl(&state, transaction_id).await;
+
+    if let (Err(_), Some(connection_id)) = (&result, connection_id) {
+        // The transaction may already have ended outside our control
+        sync_transaction_state(&app, &state, &connection_id);
+    }
+
+    result
+}
+
+/// Implementation of rollback transaction logic
+pub async fn rollback_transaction_impl(
+    state: &std::sync::Arc<AppState>,
+    transaction_id: String,
+) -> AppResult<()> {
+    log::info!("Rolling back transaction: {}", transaction_id);
+
+    // Get transaction info to find connection
+    let transaction = state.get_transaction(&transaction_id)
+        .ok_or_else(|| AppError::NotFound(format!("Transaction not found: {}", transaction_id)))?;
+
+    let connection_id = transaction.connection_id;
+
+    // Get DB handle
+    let db_handle = state.get_db_handle(&connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
+
+    // Actually rollback transaction using sqlite3x
+    {
+        let db = db_handle.lock();
+        db.execute("ROLLBACK")
+            .map_err(|e| AppError::QueryError(format!("Failed to rollback transaction: {:?}", e)))?;
+        db.end_session(&transaction_id);
+    }
+
+    state.remove_transaction(&transaction_id)
+        .map_err(|e| AppError::InternalError(e))?;
+    state.with_change_history(&connection_id, |h| h.end_transaction(&transaction_id, false));
+
+    log::info!("Transaction rolled back: {}", transaction_id);
+
+    Ok(())
+}
+
+/// Get the rows changed so far by an open transaction, grouped by table
+#[tauri::command]
+pub async fn get_pending_changes(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    transaction_id: String,
+) -> AppResult<Vec<TableChanges>> {
+    log::info!("Getting pending changes for transaction: {}", transaction_id);
+
+    let transaction = state.get_transaction(&transaction_id)
+        .ok_or_else(|| AppError::NotFound(format!("Transaction not found: {}", transaction_id)))?;
+
+    let db_handle = state.get_db_handle(&transaction.connection_id)
+        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", transaction.connection_id)))?;
+
+    let db = db_handle.lock();
+    let changeset = db.session_changeset(&transaction_id)
+        .map_err(|e| AppError::NotFound(e.to_string()))?;
+
+    db.decode_changeset(&changeset)
+        .map_err(|e| AppError::QueryError(e.to_string()))
+}
+
+/// Get the idle and maximum age limits for open transactions
+#[tauri::command]
+pub async fn get_transaction_timeouts(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+) -> AppResult<TransactionTimeoutConfig> {
+    Ok(state.get_transaction_timeouts())
+}
+
+/// Set the idle and maximum age limits for open transactions
+#[tauri::command]
+pub async fn set_transaction_timeouts(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    config: TransactionTimeoutConfig,
+) -> AppResult<()> {
+    log::info!("Setting transaction timeouts: {:?}", config);
+
+    if config.idle_timeout_secs == Some(0) || config.max_age_secs == Some(0) {
+        return Err(AppError::BadRequest("Timeouts must be greater than zero".to_string()));
+    }
+
+    state.set_transaction_timeouts(config);
+
+    Ok(())
+}
+
+/// Opt a connection in or out of automatic transaction rollback
+#[tauri::command]
+pub async fn set_transaction_timeout_enabled(
+    state: tauri::State<'_, std::sync::Arc<AppState>>,
+    connection_id: String,
+    enabled: bool,
+) -> AppResult<()> {
+    log::info!(
+        "Setting transaction timeout enabled={} for connection: {}",
+        enabled,
+        connection_id
+    );
+
+    if !state.has_connection(&connection_id) {
+        return Err(AppError::NotFound(format!("Connection not found: {}", connection_id)));
+    }
+
+    state.set_transaction_timeout_enabled(&connection_id, enabled);
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::sqlite3x::wrapper::Database;
     use crate::commands::database::DatabaseConnection;
+    use crate::state::{
+        TransactionDrift, TransactionInfo, TransactionTimeoutReason, TransactionTimeoutStatus,
+    };
     use std::sync::Arc;
 
     #[tokio::test]
@@ -118,7 +272,7 @@ mod tests {
 
         // 5. Add transaction to state
         let transaction_id = "test-tx".to_string();
-        state.add_transaction(&connection_id, &transaction_id).expect("Failed to add transaction");
+        state.add_transaction(&connection_id, &transaction_id, TransactionMode::Deferred).expect("Failed to add transaction");
 
         // 6. Rollback transaction
         rollback_transaction_impl(&state, transaction_id.clone()).await.expect("Rollback failed");
@@ -134,56 +288,6 @@ mod tests {
             assert!(result.is_err(), "Table 'test' should not exist after rollback");
         }
     }
-}
-
-/// Rollback a transaction
-#[tauri::command]
-pub async fn rollback_transaction(
-    state: tauri::State<'_, std::sync::Arc<AppState>>,
-    transaction_id: String,
-) -> AppResult<()> {
-    rollback_transaction_impl(&state, transaction_id).await
-}
-
-/// Implementation of rollback transaction logic
-pub async fn rollback_transaction_impl(
-    state: &std::sync::Arc<AppState>,
-    transaction_id: String,
-) -> AppResult<()> {
-    log::info!("Rolling back transaction: {}", transaction_id);
-
-    // Get transaction info to find connection
-    let transaction = state.get_transaction(&transaction_id)
-        .ok_or_else(|| AppError::NotFound(format!("Transaction not found: {}", transaction_id)))?;
-
-    let connection_id = transaction.connection_id;
-
-    // Get DB handle
-    let db_handle = state.get_db_handle(&connection_id)
-        .ok_or_else(|| AppError::NotFound(format!("Connection not found: {}", connection_id)))?;
-
-    // Actually rollback transaction using sqlite3x
-    {
-        let db = db_handle.lock();
-        db.execute("ROLLBACK")
-            .map_err(|e| AppError::QueryError(format!("Failed to rollback transaction: {:?}", e)))?;
-    }
-
-    state.remove_transaction(&transaction_id)
-        .map_err(|e| AppError::InternalError(e))?;
-
-    log::info!("Transaction rolled back: {}", transaction_id);
-
-    Ok(())
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::state::AppState;
-    use crate::sqlite3x::wrapper::Database;
-    use crate::commands::database::DatabaseConnection;
-    use std::sync::Arc;
-    use uuid::Uuid;
 
     #[test]
     fn test_transaction_flow() {
@@ -214,7 +318,7 @@ mod tests {
         drop(db); // release lock
 
         let tx_id = Uuid::new_v4().to_string();
-        state.add_transaction(&conn_id, &tx_id).unwrap();
+        state.add_transaction(&conn_id, &tx_id, TransactionMode::Deferred).unwrap();
 
         assert!(state.get_transaction(&tx_id).is_some());
 
@@ -245,4 +349,130 @@ mod tests {
         // Cleanup
         let _ = std::fs::remove_file(db_path);
     }
+
+    #[test]
+    fn test_sync_transaction_state() {
+        let state = Arc::new(AppState::new());
+        let db = Database::open(":memory:").expect("Failed to open memory db");
+        db.execute("CREATE TABLE test (id INTEGER PRIMARY KEY)").unwrap();
+
+        let conn_id = "sync-conn".to_string();
+        let connection = DatabaseConnection {
+            id: conn_id.clone(),
+            path: ":memory:".to_string(),
+            name: "Memory DB".to_string(),
+            is_connected: true,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        state.add_connection(connection, db).unwrap();
+        let db_handle = state.get_db_handle(&conn_id).unwrap();
+
+        // In sync: nothing to reconcile
+        assert!(state.sync_transaction_state(&conn_id).is_none());
+
+        // Transaction committed from the editor leaves a stale entry behind
+        db_handle.lock().execute(TransactionMode::Immediate.begin_sql()).unwrap();
+        state.add_transaction(&conn_id, "tx-1", TransactionMode::Immediate).unwrap();
+        db_handle.lock().execute("COMMIT").unwrap();
+
+        match state.sync_transaction_state(&conn_id) {
+            Some(TransactionDrift::Ended(ids)) => assert_eq!(ids, vec!["tx-1".to_string()]),
+            other => panic!("Expected ended drift, got {:?}", other),
+        }
+        assert!(state.get_transaction("tx-1").is_none());
+
+        // Transaction opened from the editor gets adopted
+        db_handle.lock().execute("BEGIN").unwrap();
+        match state.sync_transaction_state(&conn_id) {
+            Some(TransactionDrift::Adopted(info)) => {
+                assert!(info.mode.is_none());
+                assert!(state.get_transaction(&info.id).is_some());
+            }
+            other => panic!("Expected adopted drift, got {:?}", other),
+        }
+        assert!(state.sync_transaction_state(&conn_id).is_none());
+    }
+
+    #[test]
+    fn test_transaction_timeout_check() {
+        let config = TransactionTimeoutConfig {
+            idle_timeout_secs: Some(60),
+            max_age_secs: Some(600),
+            warning_secs: 10,
+        };
+        let now = chrono::Utc::now();
+        let mut info = TransactionInfo {
+            id: "tx".to_string(),
+            connection_id: "conn".to_string(),
+            started_at: now,
+            mode: Some(TransactionMode::Deferred),
+            last_activity_at: now,
+            warned: false,
+        };
+
+        assert_eq!(config.check(&info, now), None);
+
+        info.last_activity_at = now - chrono::Duration::seconds(55);
+        assert_eq!(
+            config.check(&info, now),
+            Some(TransactionTimeoutStatus::Warning {
+                reason: TransactionTimeoutReason::Idle,
+                seconds_remaining: 5,
+            })
+        );
+
+        info.last_activity_at = now;
+        info.started_at = now - chrono::Duration::seconds(601);
+        assert_eq!(
+            config.check(&info, now),
+            Some(TransactionTimeoutStatus::Expired { reason: TransactionTimeoutReason::MaxAge })
+        );
+
+        // By default a transaction left alone for ten minutes is rolled back
+        let defaults = TransactionTimeoutConfig::default();
+        info.started_at = now - chrono::Duration::seconds(601);
+        info.last_activity_at = now - chrono::Duration::seconds(60);
+        assert_eq!(defaults.check(&info, now), None);
+        info.last_activity_at = now - chrono::Duration::seconds(600);
+        assert_eq!(
+            defaults.check(&info, now),
+            Some(TransactionTimeoutStatus::Expired { reason: TransactionTimeoutReason::Idle })
+        );
+    }
+
+    #[test]
+    fn test_pending_changes() {
+        let db = Database::open(":memory:").expect("Failed to open memory db");
+        db.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, email TEXT)").unwrap();
+        db.execute("CREATE TABLE notes (body TEXT)").unwrap();
+        db.execute("INSERT INTO users VALUES (1, 'alice', 'a@example.com'), (2, 'bob', 'b@example.com')").unwrap();
+
+        db.execute(TransactionMode::Deferred.begin_sql()).unwrap();
+        db.start_session("tx").unwrap();
+        db.execute("INSERT INTO users VALUES (3, 'carol', 'c@example.com')").unwrap();
+        db.execute("UPDATE users SET name = 'alicia' WHERE id = 1").unwrap();
+        db.execute("DELETE FROM users WHERE id = 2").unwrap();
+        db.execute("INSERT INTO notes VALUES ('hello')").unwrap();
+
+        let changeset = db.session_changeset("tx").unwrap();
+        let tables = db.decode_changeset(&changeset).unwrap();
+
+        let users = tables.iter().find(|t| t.table == "users").unwrap();
+        assert_eq!(users.inserted.len(), 1);
+        assert_eq!(users.inserted[0].new_values["name"], "carol");
+        assert_eq!(users.updated.len(), 1);
+        assert_eq!(users.updated[0].primary_key["id"], 1);
+        assert_eq!(users.updated[0].old_values["name"], "alice");
+        assert_eq!(users.updated[0].new_values["name"], "alicia");
+        assert!(!users.updated[0].new_values.contains_key("email"));
+        assert_eq!(users.deleted.len(), 1);
+        assert_eq!(users.deleted[0].old_values["email"], "b@example.com");
+
+        let notes = tables.iter().find(|t| t.table == "notes").unwrap();
+        assert_eq!(notes.inserted[0].new_values["body"], "hello");
+        assert!(notes.inserted[0].primary_key.contains_key("rowid"));
+
+        db.end_session("tx");
+        db.execute("ROLLBACK").unwrap();
+    }
 }
@@ -4,6 +4,7 @@
 
 pub mod data_change;
 mod performance;
+pub mod transaction;
 
 use tauri::AppHandle;
 
@@ -17,7 +18,15 @@ pub fn setup_event_handlers(app: &AppHandle, state: std::sync::Arc<crate::state:
     tauri::async_runtime::spawn(async move {
         performance::start_monitoring(app_clone, state_clone, 1000).await;
     });
+
+    // Roll back transactions left open for too long
+    let app_clone = app.clone();
+    let state_clone = state.clone();
+    tauri::async_runtime::spawn(async move {
+        transaction::start_watchdog(app_clone, state_clone, 1000).await;
+    });
 }
 
 pub use data_change::DataChangeEvent;
 pub use performance::PerformanceEvent;
+pub use transaction::{TransactionStateEvent, TransactionWarningEvent};
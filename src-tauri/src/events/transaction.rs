@@ -0,0 +1,206 @@
+//! Transaction State Events
+//!
+//! Keeps the frontend transaction indicator in sync with the connection.
+
+use crate::state::{
+    AppState, TransactionDrift, TransactionInfo, TransactionTimeoutConfig, TransactionTimeoutReason,
+    TransactionTimeoutStatus,
+};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+/// Transaction state event payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionStateEvent {
+    pub connection_id: String,
+    pub in_transaction: bool,
+    /// Transaction now tracked for the connection, if any
+    pub transaction_id: Option<String>,
+    /// Tracked transactions that ended outside the transaction commands
+    pub ended_transactions: Vec<String>,
+    pub timestamp: String,
+}
+
+/// Transaction timeout warning payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionWarningEvent {
+    pub transaction_id: String,
+    pub connection_id: String,
+    pub reason: TransactionTimeoutReason,
+    /// Seconds until the automatic rollback, 0 once it happened
+    pub seconds_remaining: i64,
+    pub rolled_back: bool,
+    pub timestamp: String,
+}
+
+/// Emit a transaction state event to all listeners
+pub fn emit_transaction_state(app: &AppHandle, event: TransactionStateEvent) {
+    if let Err(e) = app.emit("db:transaction_state", &event) {
+        log::error!("Failed to emit transaction state event: {}", e);
+    }
+}
+
+/// Reconcile tracked transactions for a connection and notify the frontend on drift
+///
+/// Must be called without holding the connection's database lock.
+pub fn sync_transaction_state(app: &AppHandle, state: &AppState, connection_id: &str) {
+    let event = match state.sync_transaction_state(connection_id) {
+        Some(TransactionDrift::Ended(ids)) => {
+            log::warn!(
+                "Transactions ended outside transaction commands on {}: {:?}",
+                connection_id,
+                ids
+            );
+            TransactionStateEvent {
+                connection_id: connection_id.to_string(),
+                in_transaction: false,
+                transaction_id: None,
+                ended_transactions: ids,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            }
+        }
+        Some(TransactionDrift::Adopted(info)) => {
+            log::info!(
+                "Tracking transaction opened outside begin_transaction on {}: {}",
+                connection_id,
+                info.id
+            );
+            TransactionStateEvent {
+                connection_id: connection_id.to_string(),
+                in_transaction: true,
+                transaction_id: Some(info.id),
+                ended_transactions: Vec::new(),
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            }
+        }
+        None => return,
+    };
+
+    emit_transaction_state(app, event);
+}
+
+/// Emit a transaction timeout warning to all listeners
+pub fn emit_transaction_warning(app: &AppHandle, event: TransactionWarningEvent) {
+    if let Err(e) = app.emit("db:transaction_warning", &event) {
+        log::error!("Failed to emit transaction warning event: {}", e);
+    }
+}
+
+/// Start the background watchdog that rolls back idle or long-running transactions
+pub async fn start_watchdog(app: AppHandle, state: std::sync::Arc<AppState>, interval_ms: u64) {
+    log::info!("Transaction watchdog started");
+
+    let mut interval = tokio::time::interval(std::time::Duration::from_millis(interval_ms));
+
+    loop {
+        interval.tick().await;
+
+        let config = state.get_transaction_timeouts();
+        let now = chrono::Utc::now();
+
+        for transaction in state.get_all_transactions() {
+            if !state.is_transaction_timeout_enabled(&transaction.connection_id) {
+                continue;
+            }
+
+            match config.check(&transaction, now) {
+                Some(TransactionTimeoutStatus::Warning { reason, seconds_remaining }) => {
+                    if transaction.warned {
+                        continue;
+                    }
+                    state.mark_transaction_warned(&transaction.id);
+                    emit_transaction_warning(
+                        &app,
+                        TransactionWarningEvent {
+                            transaction_id: transaction.id,
+                            connection_id: transaction.connection_id,
+                            reason,
+                            seconds_remaining,
+                            rolled_back: false,
+                            timestamp: now.to_rfc3339(),
+                        },
+                    );
+                }
+                Some(TransactionTimeoutStatus::Expired { .. }) => {
+                    rollback_expired(&app, &state, &config, transaction);
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// Roll back a transaction that exceeded its limits
+fn rollback_expired(
+    app: &AppHandle,
+    state: &AppState,
+    config: &TransactionTimeoutConfig,
+    transaction: TransactionInfo,
+) {
+    let Some(db_handle) = state.get_db_handle(&transaction.connection_id) else {
+        return;
+    };
+
+    // A statement is running, so the transaction is not idle; retry on the next tick
+    let Some(db) = db_handle.try_lock() else {
+        return;
+    };
+
+    // A command may have touched or ended the transaction since the snapshot
+    let Some(transaction) = state.get_transaction(&transaction.id) else {
+        return;
+    };
+    let reason = match config.check(&transaction, chrono::Utc::now()) {
+        Some(TransactionTimeoutStatus::Expired { reason }) => reason,
+        _ => return,
+    };
+
+    if db.is_autocommit().unwrap_or(true) {
+        // Already ended outside our control
+        drop(db);
+        sync_transaction_state(app, state, &transaction.connection_id);
+        return;
+    }
+
+    if let Err(e) = db.execute("ROLLBACK") {
+        log::error!("Failed to roll back expired transaction {}: {}", transaction.id, e);
+        return;
+    }
+    db.end_session(&transaction.id);
+    drop(db);
+
+    log::warn!(
+        "Rolled back transaction {} on {} after {:?} timeout",
+        transaction.id,
+        transaction.connection_id,
+        reason
+    );
+
+    let _ = state.remove_transaction(&transaction.id);
+    state.with_change_history(&transaction.connection_id, |h| h.end_transaction(&transaction.id, false));
+    let now = chrono::Utc::now().to_rfc3339();
+
+    emit_transaction_warning(
+        app,
+        TransactionWarningEvent {
+            transaction_id: transaction.id.clone(),
+            connection_id: transaction.connection_id.clone(),
+            reason,
+            seconds_remaining: 0,
+            rolled_back: true,
+            timestamp: now.clone(),
+        },
+    );
+    emit_transaction_state(
+        app,
+        TransactionStateEvent {
+            connection_id: transaction.connection_id,
+            in_transaction: false,
+            transaction_id: None,
+            ended_transactions: vec![transaction.id],
+            timestamp: now,
+        },
+    );
+}
@@ -48,13 +48,34 @@ pub fn run() {
             commands::query::execute_query,
             commands::query::execute_query_with_connection,
             commands::query::explain_query,
+            // Row editing commands
+            commands::data_edit::update_cells,
+            commands::data_edit::insert_rows,
+            commands::data_edit::delete_rows,
+            commands::data_edit::get_row_key,
+            commands::data_diff::diff_table_data,
+            commands::browse::browse_table,
+            commands::find_replace::preview_find_replace,
+            commands::find_replace::apply_find_replace,
+            commands::test_data::generate_test_data,
             // Transaction commands
             commands::transaction::begin_transaction,
             commands::transaction::commit_transaction,
             commands::transaction::rollback_transaction,
+            commands::transaction::get_pending_changes,
+            commands::transaction::get_transaction_timeouts,
+            commands::transaction::set_transaction_timeouts,
+            commands::transaction::set_transaction_timeout_enabled,
+            // Undo/redo commands
+            commands::history::undo_last_change,
+            commands::history::redo_last_change,
+            commands::history::get_change_history,
             // Schema commands
             commands::schema::get_schema,
             commands::schema::get_table_info,
+            commands::schema::export_er_diagram,
+            commands::schema::generate_schema_docs,
+            commands::schema::search_schema,
             // Stats commands
             commands::stats::get_stats,
             commands::stats::clear_cache,
@@ -90,6 +111,21 @@ pub fn run() {
             commands::migration::export_data,
             commands::migration::copy_table,
             commands::schema_management::diff_schemas,
+            commands::schema_management::apply_schema_diff,
+            commands::schema_management::save_schema_snapshot,
+            commands::schema_management::create_table,
+            commands::schema_management::alter_table,
+            commands::schema_management::rename_table,
+            commands::schema_management::rename_column,
+            commands::schema_management::disable_trigger,
+            commands::schema_management::enable_trigger,
+            commands::schema_management::list_disabled_triggers,
+            commands::schema_management::get_dependencies,
+            commands::schema_management::get_dependents,
+            commands::schema_management::get_drop_impact,
+            commands::schema_management::get_statement_impact,
+            commands::schema_management::get_object_descriptions,
+            commands::schema_management::set_object_description,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,543 @@
+//! sqlite3x DDL Parsing
+//!
+//! A light tokenizer for schema SQL, for the details SQLite's pragmas don't
+//! report (e.g. whether a foreign key is deferrable).
+
+/// Kind of SQL token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Keyword or bare identifier
+    Word,
+    /// Identifier in `"..."`, `[...]` or `` `...` ``
+    QuotedIdent,
+    /// String literal in `'...'`
+    String,
+    Number,
+    /// Any other single character, e.g. `(`, `,` or `;`
+    Punct,
+}
+
+/// A token and its byte offset in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub start: usize,
+}
+
+impl Token<'_> {
+    /// Whether this is the given keyword, ignoring case
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    /// Whether this is the given punctuation character
+    pub fn is_punct(&self, c: char) -> bool {
+        self.kind == TokenKind::Punct && self.text.starts_with(c)
+    }
+
+    /// The identifier this token names, without quotes
+    pub fn identifier(&self) -> Option<String> {
+        match self.kind {
+            TokenKind::Word => Some(self.text.to_string()),
+            TokenKind::QuotedIdent => {
+                let inner = self.unquoted();
+                Some(match self.text.as_bytes()[0] {
+                    b'"' => inner.replace("\"\"", "\""),
+                    b'`' => inner.replace("``", "`"),
+                    _ => inner.to_string(),
+                })
+            }
+            // SQLite accepts string literals as identifiers in DDL for compatibility
+            TokenKind::String => Some(self.unquoted().replace("''", "'")),
+            _ => None,
+        }
+    }
+
+    /// The text between the quotes; an unterminated quote runs to the end
+    fn unquoted(&self) -> &str {
+        let close = match self.text.as_bytes()[0] {
+            b'[' => ']',
+            quote => quote as char,
+        };
+        let inner = &self.text[1..];
+        inner.strip_suffix(close).unwrap_or(inner)
+    }
+
+    /// Byte offset just past the token
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+}
+
+/// Split SQL into tokens, dropping whitespace and comments
+///
+/// Unterminated quotes and comments run to the end of the input.
+pub fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == b'-' && bytes.get(i + 1) == Some(&b'-') {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        if c == b'/' && bytes.get(i + 1) == Some(&b'*') {
+            i += 2;
+            while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                i += 1;
+            }
+            i = (i + 2).min(bytes.len());
+            continue;
+        }
+
+        let kind = match c {
+            b'\'' | b'"' | b'`' => {
+                // Doubled quotes escape themselves
+                i += 1;
+                loop {
+                    match bytes.get(i) {
+                        None => break,
+                        Some(&q) if q == c => {
+                            if bytes.get(i + 1) == Some(&c) {
+                                i += 2;
+                            } else {
+                                i += 1;
+                                break;
+                            }
+                        }
+                        Some(_) => i += 1,
+                    }
+                }
+                if c == b'\'' { TokenKind::String } else { TokenKind::QuotedIdent }
+            }
+            b'[' => {
+                while i < bytes.len() && bytes[i] != b']' {
+                    i += 1;
+                }
+                i = (i + 1).min(bytes.len());
+                TokenKind::QuotedIdent
+            }
+            b'0'..=b'9' => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                    i += 1;
+                }
+                TokenKind::Number
+            }
+            _ if c == b'_' || c == b'$' || c.is_ascii_alphabetic() || c >= 0x80 => {
+                while i < bytes.len()
+                    && (bytes[i] == b'_' || bytes[i] == b'$' || bytes[i].is_ascii_alphanumeric() || bytes[i] >= 0x80)
+                {
+                    i += 1;
+                }
+                TokenKind::Word
+            }
+            _ => {
+                i += sql[i..].chars().next().map(char::len_utf8).unwrap_or(1);
+                TokenKind::Punct
+            }
+        };
+
+        tokens.push(Token { kind, text: &sql[start..i], start });
+    }
+
+    tokens
+}
+
+/// Column and constraint definitions of a CREATE TABLE statement, as written
+///
+/// Empty for `CREATE TABLE ... AS SELECT`.
+pub fn table_elements(create_sql: &str) -> Vec<String> {
+    let tokens = tokenize(create_sql);
+    let Some(open) = tokens.iter().position(|t| t.is_punct('(')) else {
+        return Vec::new();
+    };
+    if tokens[..open].iter().any(|t| t.is_keyword("AS")) {
+        return Vec::new();
+    }
+
+    let mut elements = Vec::new();
+    let mut start = open + 1;
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate().skip(open + 1) {
+        if token.is_punct('(') {
+            depth += 1;
+        } else if token.is_punct(')') && depth > 0 {
+            depth -= 1;
+        } else if depth == 0 && (token.is_punct(',') || token.is_punct(')')) {
+            if let (Some(first), Some(last)) = (tokens.get(start), tokens.get(i - 1)) {
+                if start < i {
+                    elements.push(create_sql[first.start..last.end()].to_string());
+                }
+            }
+            if token.is_punct(')') {
+                break;
+            }
+            start = i + 1;
+        }
+    }
+
+    elements
+}
+
+/// Index of the `)` matching the `(` at `open`
+pub fn closing_paren(tokens: &[Token<'_>], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        if token.is_punct('(') {
+            depth += 1;
+        } else if token.is_punct(')') {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// Deferral settings of a foreign key clause in a CREATE TABLE statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKeyClause {
+    pub referenced_table: String,
+    pub deferrable: bool,
+    pub initially_deferred: bool,
+}
+
+/// `REFERENCES` clauses of a CREATE TABLE statement, in declaration order
+pub fn foreign_key_clauses(create_sql: &str) -> Vec<ForeignKeyClause> {
+    let tokens = tokenize(create_sql);
+    let mut clauses = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let token = &tokens[i];
+        if token.is_punct('(') {
+            depth += 1;
+        } else if token.is_punct(')') {
+            depth -= 1;
+        } else if depth == 1 && token.is_keyword("REFERENCES") {
+            let Some(referenced_table) = tokens.get(i + 1).and_then(|t| t.identifier()) else {
+                break;
+            };
+
+            // The clause runs to the end of the column or constraint definition
+            let mut clause = ForeignKeyClause { referenced_table, deferrable: false, initially_deferred: false };
+            let mut j = i + 2;
+            let mut nested = 0;
+            while j < tokens.len() {
+                let t = &tokens[j];
+                if t.is_punct('(') {
+                    nested += 1;
+                } else if t.is_punct(')') {
+                    if nested == 0 {
+                        break;
+                    }
+                    nested -= 1;
+                } else if nested == 0 && (t.is_punct(',') || t.is_keyword("REFERENCES")) {
+                    break;
+                } else if t.is_keyword("DEFERRABLE") {
+                    clause.deferrable = !tokens[j - 1].is_keyword("NOT");
+                } else if t.is_keyword("INITIALLY") {
+                    clause.initially_deferred = tokens.get(j + 1).is_some_and(|n| n.is_keyword("DEFERRED"));
+                }
+                j += 1;
+            }
+            clause.initially_deferred &= clause.deferrable;
+
+            clauses.push(clause);
+            i = j;
+            continue;
+        }
+        i += 1;
+    }
+
+    clauses
+}
+
+/// Module name of a CREATE VIRTUAL TABLE statement, lowercased
+pub fn virtual_table_module(create_sql: &str) -> Option<String> {
+    let tokens = tokenize(create_sql);
+    let using = tokens.iter().position(|t| t.is_keyword("USING"))?;
+    tokens.get(using + 1)?.identifier().map(|m| m.to_lowercase())
+}
+
+/// Key terms and predicate of a CREATE INDEX statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexDefinition {
+    /// Each indexed column or expression as written, without COLLATE or ASC/DESC
+    pub terms: Vec<String>,
+    /// Predicate of a partial index
+    pub where_clause: Option<String>,
+}
+
+/// Parse a CREATE INDEX statement
+pub fn parse_create_index(create_sql: &str) -> Option<IndexDefinition> {
+    let tokens = tokenize(create_sql);
+    let on = tokens.iter().position(|t| t.is_keyword("ON"))?;
+    let open = on + tokens[on..].iter().position(|t| t.is_punct('('))?;
+
+    let mut terms = Vec::new();
+    let mut term_start = open + 1;
+    let mut depth = 0;
+    let mut close = None;
+
+    for (i, token) in tokens.iter().enumerate().skip(open + 1) {
+        if token.is_punct('(') {
+            depth += 1;
+        } else if token.is_punct(')') && depth > 0 {
+            depth -= 1;
+        } else if depth == 0 && (token.is_punct(',') || token.is_punct(')')) {
+            terms.push(index_term(create_sql, &tokens[term_start..i]));
+            term_start = i + 1;
+            if token.is_punct(')') {
+                close = Some(i);
+                break;
+            }
+        }
+    }
+
+    let close = close?;
+    let where_clause = tokens
+        .get(close + 1)
+        .filter(|t| t.is_keyword("WHERE"))
+        .map(|t| create_sql[t.end()..].trim().trim_end_matches(';').trim_end().to_string());
+
+    Some(IndexDefinition { terms, where_clause })
+}
+
+/// Source text of an indexed term with any trailing COLLATE and sort order dropped
+fn index_term(sql: &str, tokens: &[Token<'_>]) -> String {
+    let mut end = tokens.len();
+    if end > 0 && (tokens[end - 1].is_keyword("ASC") || tokens[end - 1].is_keyword("DESC")) {
+        end -= 1;
+    }
+    if end > 1 && tokens[end - 2].is_keyword("COLLATE") {
+        end -= 2;
+    }
+
+    match tokens[..end].first().zip(tokens[..end].last()) {
+        Some((first, last)) => sql[first.start..last.end()].to_string(),
+        None => String::new(),
+    }
+}
+
+/// When a trigger fires relative to its event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TriggerTiming {
+    #[serde(rename = "BEFORE")]
+    Before,
+    #[serde(rename = "AFTER")]
+    After,
+    #[serde(rename = "INSTEAD OF")]
+    InsteadOf,
+}
+
+impl TriggerTiming {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TriggerTiming::Before => "BEFORE",
+            TriggerTiming::After => "AFTER",
+            TriggerTiming::InsteadOf => "INSTEAD OF",
+        }
+    }
+}
+
+/// Statement that fires a trigger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TriggerEvent {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl TriggerEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TriggerEvent::Insert => "INSERT",
+            TriggerEvent::Update => "UPDATE",
+            TriggerEvent::Delete => "DELETE",
+        }
+    }
+}
+
+/// The parts of a CREATE TRIGGER statement
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerDefinition {
+    /// `BEFORE` when the statement names no timing, as in SQLite
+    pub timing: TriggerTiming,
+    pub event: TriggerEvent,
+    /// Columns of an `UPDATE OF` trigger; empty for any column
+    pub update_columns: Vec<String>,
+    /// Whether `FOR EACH ROW` is written out; SQLite triggers are always row triggers
+    pub for_each_row: bool,
+    pub when_clause: Option<String>,
+    /// Statements between BEGIN and END, without their semicolons
+    pub body: Vec<String>,
+}
+
+/// Parse a CREATE TRIGGER statement
+pub fn parse_create_trigger(create_sql: &str) -> Option<TriggerDefinition> {
+    let tokens = tokenize(create_sql);
+    let keyword = |i: usize, k: &str| tokens.get(i).is_some_and(|t| t.is_keyword(k));
+
+    let mut i = tokens.iter().position(|t| t.is_keyword("TRIGGER"))? + 1;
+    if keyword(i, "IF") {
+        i += 3;
+    }
+    // Trigger name, possibly schema-qualified
+    i += if tokens.get(i + 1).is_some_and(|t| t.is_punct('.')) { 3 } else { 1 };
+
+    let timing = if keyword(i, "BEFORE") {
+        i += 1;
+        TriggerTiming::Before
+    } else if keyword(i, "AFTER") {
+        i += 1;
+        TriggerTiming::After
+    } else if keyword(i, "INSTEAD") {
+        i += 2;
+        TriggerTiming::InsteadOf
+    } else {
+        TriggerTiming::Before
+    };
+
+    let event = match tokens.get(i)? {
+        t if t.is_keyword("INSERT") => TriggerEvent::Insert,
+        t if t.is_keyword("UPDATE") => TriggerEvent::Update,
+        t if t.is_keyword("DELETE") => TriggerEvent::Delete,
+        _ => return None,
+    };
+    i += 1;
+
+    let mut update_columns = Vec::new();
+    if event == TriggerEvent::Update && keyword(i, "OF") {
+        i += 1;
+        while let Some(column) = tokens.get(i).and_then(|t| t.identifier()) {
+            update_columns.push(column);
+            i += 1;
+            if !tokens.get(i).is_some_and(|t| t.is_punct(',')) {
+                break;
+            }
+            i += 1;
+        }
+    }
+
+    if !keyword(i, "ON") {
+        return None;
+    }
+    i += if tokens.get(i + 2).is_some_and(|t| t.is_punct('.')) { 4 } else { 2 };
+
+    let for_each_row = keyword(i, "FOR");
+    if for_each_row {
+        i += 3;
+    }
+
+    let begin = i + tokens[i.min(tokens.len())..].iter().position(|t| t.is_keyword("BEGIN"))?;
+    let when_clause = keyword(i, "WHEN")
+        .then(|| create_sql[tokens[i].end()..tokens[begin].start].trim().to_string());
+
+    // The body runs to the last END; CASE expressions inside it have their own
+    let end = tokens.iter().rposition(|t| t.is_keyword("END")).filter(|&end| end > begin)?;
+    let body = tokens[begin + 1..end]
+        .split(|t| t.is_punct(';'))
+        .filter_map(|statement| {
+            let (first, last) = (statement.first()?, statement.last()?);
+            Some(create_sql[first.start..last.end()].to_string())
+        })
+        .collect();
+
+    Some(TriggerDefinition { timing, event, update_columns, for_each_row, when_clause, body })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_quotes_and_comments() {
+        let tokens = tokenize("CREATE TABLE \"a \"\"b\"\"\" ([x y], 'it''s') -- done\n/* c */;");
+        let texts: Vec<&str> = tokens.iter().map(|t| t.text).collect();
+        assert_eq!(texts, vec!["CREATE", "TABLE", "\"a \"\"b\"\"\"", "(", "[x y]", ",", "'it''s'", ")", ";"]);
+        assert_eq!(tokens[2].identifier().as_deref(), Some("a \"b\""));
+        assert_eq!(tokens[4].identifier().as_deref(), Some("x y"));
+    }
+
+    #[test]
+    fn test_unterminated_quotes() {
+        for (sql, name) in [("DROP TABLE \"", ""), ("DROP TABLE [", ""), ("DROP TABLE '", ""), ("DROP TABLE \"ab", "ab"), ("DROP TABLE [ab", "ab")] {
+            let tokens = tokenize(sql);
+            assert_eq!(tokens.len(), 3, "{}", sql);
+            assert_eq!(tokens[2].identifier().as_deref(), Some(name), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_foreign_key_clauses() {
+        let clauses = foreign_key_clauses(
+            "CREATE TABLE t (
+                a INTEGER REFERENCES p(id) DEFERRABLE INITIALLY DEFERRED NOT NULL,
+                b INTEGER,
+                c INTEGER,
+                FOREIGN KEY (b, c) REFERENCES \"q\" (x, y) ON DELETE CASCADE NOT DEFERRABLE INITIALLY DEFERRED,
+                CHECK (a > 0)
+            )",
+        );
+        assert_eq!(
+            clauses,
+            vec![
+                ForeignKeyClause { referenced_table: "p".to_string(), deferrable: true, initially_deferred: true },
+                ForeignKeyClause { referenced_table: "q".to_string(), deferrable: false, initially_deferred: false },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_create_index() {
+        let definition = parse_create_index(
+            "CREATE UNIQUE INDEX idx ON \"t\" (lower(name) COLLATE NOCASE DESC, \"b\", substr(c, 1, 2)) WHERE b IS NOT NULL;",
+        )
+        .unwrap();
+        assert_eq!(definition.terms, vec!["lower(name)", "\"b\"", "substr(c, 1, 2)"]);
+        assert_eq!(definition.where_clause.as_deref(), Some("b IS NOT NULL"));
+    }
+
+    #[test]
+    fn test_parse_create_trigger() {
+        let definition = parse_create_trigger(
+            "CREATE TRIGGER IF NOT EXISTS main.audit AFTER UPDATE OF name, \"email\" ON users
+             FOR EACH ROW WHEN new.name <> old.name
+             BEGIN
+                 INSERT INTO log (msg) VALUES (CASE WHEN new.name IS NULL THEN 'cleared' ELSE 'renamed; ok' END);
+                 DELETE FROM cache WHERE id = new.id;
+             END",
+        )
+        .unwrap();
+        assert_eq!(definition.timing, TriggerTiming::After);
+        assert_eq!(definition.event, TriggerEvent::Update);
+        assert_eq!(definition.update_columns, vec!["name", "email"]);
+        assert!(definition.for_each_row);
+        assert_eq!(definition.when_clause.as_deref(), Some("new.name <> old.name"));
+        assert_eq!(definition.body.len(), 2);
+        assert!(definition.body[1].starts_with("DELETE FROM cache"));
+
+        let definition = parse_create_trigger("CREATE TRIGGER t INSTEAD OF DELETE ON v BEGIN SELECT 1; END").unwrap();
+        assert_eq!(definition.timing, TriggerTiming::InsteadOf);
+        assert!(!definition.for_each_row && definition.when_clause.is_none());
+    }
+}
@@ -0,0 +1,609 @@
+//! sqlite3x Object Dependencies
+//!
+//! Which schema objects use which: views and triggers are scanned for the
+//! tables, views and columns they name; indexes, trigger targets and
+//! foreign keys come from the loaded schema.
+
+use super::ddl::{self, Token, TokenKind};
+use super::wrapper::{ForeignKeyConstraint, SchemaInfo, TableKind};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Words that end a table reference rather than alias it
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "AS", "ON", "USING", "WHERE", "JOIN", "LEFT", "RIGHT", "FULL", "INNER", "OUTER", "CROSS", "NATURAL",
+    "GROUP", "ORDER", "HAVING", "WINDOW", "LIMIT", "UNION", "EXCEPT", "INTERSECT", "SET", "VALUES",
+    "DEFAULT", "SELECT", "FROM", "RETURNING", "INDEXED", "NOT", "DO", "WHEN", "THEN", "ELSE", "END",
+];
+
+/// Kind of schema object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ObjectKind {
+    Table,
+    View,
+    Index,
+    Trigger,
+}
+
+/// How one object uses another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DependencyKind {
+    /// A view selects from it
+    ViewReference,
+    /// A trigger's WHEN clause or body reads or writes it
+    TriggerReference,
+    /// A trigger fires on it
+    TriggerTarget,
+    /// An index is built on it
+    Index,
+    /// A foreign key of another table references it
+    ForeignKey,
+}
+
+/// An edge of the dependency graph: `dependent` uses `target`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dependency {
+    pub dependent: String,
+    pub dependent_kind: ObjectKind,
+    pub target: String,
+    pub target_kind: ObjectKind,
+    pub kind: DependencyKind,
+    /// Columns of `target` that are used; empty for the whole row or when unknown
+    pub columns: Vec<String>,
+}
+
+/// All dependencies between the objects of a schema
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    pub edges: Vec<Dependency>,
+}
+
+/// What dropping an object, or a column of a table, takes with it or breaks
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DropImpact {
+    pub object: String,
+    pub kind: ObjectKind,
+    pub column: Option<String>,
+    /// Indexes and triggers SQLite drops along with the object
+    pub dropped_with: Vec<String>,
+    /// Views and triggers that fail once it is gone, directly or through other views
+    pub broken: Vec<Dependency>,
+    /// Foreign keys that reference the table or column
+    pub foreign_keys: Vec<ForeignKeyConstraint>,
+    /// Tables whose partition policy uses the table or column
+    pub partition_policies: Vec<String>,
+    /// The above as sentences for a confirmation prompt
+    pub warnings: Vec<String>,
+}
+
+impl DependencyGraph {
+    pub fn build(schema: &SchemaInfo) -> Self {
+        let objects = Objects::new(schema);
+        let mut edges = Vec::new();
+
+        for table in &schema.tables {
+            for fk in &table.foreign_keys {
+                edges.push(Dependency {
+                    dependent: table.name.clone(),
+                    dependent_kind: ObjectKind::Table,
+                    target: fk.referenced_table.clone(),
+                    target_kind: ObjectKind::Table,
+                    kind: DependencyKind::ForeignKey,
+                    columns: fk.columns.iter().map(|p| p.to.clone()).collect(),
+                });
+            }
+        }
+
+        for index in &schema.indexes {
+            edges.push(Dependency {
+                dependent: index.name.clone(),
+                dependent_kind: ObjectKind::Index,
+                target: index.table_name.clone(),
+                target_kind: ObjectKind::Table,
+                kind: DependencyKind::Index,
+                columns: index.key_columns.iter().filter_map(|c| c.name.clone()).filter(|n| n != "rowid").collect(),
+            });
+        }
+
+        for view in &schema.views {
+            let Some(sql) = &view.sql else { continue };
+            let tokens = ddl::tokenize(sql);
+            let body = tokens.iter().position(|t| t.is_keyword("AS")).map(|i| i + 1).unwrap_or(tokens.len());
+            for (target, columns) in objects.references(&tokens[body..], None) {
+                edges.push(Dependency {
+                    dependent: view.name.clone(),
+                    dependent_kind: ObjectKind::View,
+                    target_kind: objects.kind(&target),
+                    target,
+                    kind: DependencyKind::ViewReference,
+                    columns,
+                });
+            }
+        }
+
+        for trigger in &schema.triggers {
+            let Some(definition) = &trigger.definition else { continue };
+
+            // Columns of the target are named in UPDATE OF and through NEW and OLD
+            let mut target_columns = definition.update_columns.clone();
+            let mut sources = definition.body.clone();
+            sources.extend(definition.when_clause.clone());
+            let mut references: Vec<(String, Vec<String>)> = Vec::new();
+            for source in &sources {
+                let tokens = ddl::tokenize(source);
+                for (target, columns) in objects.references(&tokens, Some(&trigger.table_name)) {
+                    if target.eq_ignore_ascii_case(&trigger.table_name) && is_row_alias_only(&tokens, &target) {
+                        merge(&mut target_columns, columns);
+                        continue;
+                    }
+                    match references.iter_mut().find(|(name, _)| *name == target) {
+                        Some((_, existing)) => merge(existing, columns),
+                        None => references.push((target, columns)),
+                    }
+                }
+            }
+
+            edges.push(Dependency {
+                dependent: trigger.name.clone(),
+                dependent_kind: ObjectKind::Trigger,
+                target: trigger.table_name.clone(),
+                target_kind: objects.kind(&trigger.table_name),
+                kind: DependencyKind::TriggerTarget,
+                columns: target_columns,
+            });
+            for (target, columns) in references {
+                edges.push(Dependency {
+                    dependent: trigger.name.clone(),
+                    dependent_kind: ObjectKind::Trigger,
+                    target_kind: objects.kind(&target),
+                    target,
+                    kind: DependencyKind::TriggerReference,
+                    columns,
+                });
+            }
+        }
+
+        Self { edges }
+    }
+
+    /// Objects `name` uses directly
+    pub fn dependencies(&self, name: &str) -> Vec<&Dependency> {
+        self.edges.iter().filter(|e| e.dependent.eq_ignore_ascii_case(name)).collect()
+    }
+
+    /// Objects that use `name` directly
+    pub fn dependents(&self, name: &str) -> Vec<&Dependency> {
+        self.edges.iter().filter(|e| e.target.eq_ignore_ascii_case(name)).collect()
+    }
+
+    /// Views and triggers that use `name` directly or through other views
+    ///
+    /// Ordered so that every view comes after the views it selects from, i.e.
+    /// the order to recreate them in.
+    pub fn view_and_trigger_dependents(&self, name: &str) -> Vec<&Dependency> {
+        let through_sql = |e: &&Dependency| {
+            matches!(e.kind, DependencyKind::ViewReference | DependencyKind::TriggerReference | DependencyKind::TriggerTarget)
+        };
+
+        let mut names: HashSet<String> = HashSet::from([name.to_lowercase()]);
+        let mut found: Vec<&Dependency> = Vec::new();
+        let mut queue = vec![name.to_string()];
+        while let Some(current) = queue.pop() {
+            for edge in self.dependents(&current).into_iter().filter(through_sql) {
+                if names.insert(edge.dependent.to_lowercase()) {
+                    found.push(edge);
+                    if edge.dependent_kind == ObjectKind::View {
+                        queue.push(edge.dependent.clone());
+                    }
+                }
+            }
+        }
+
+        // Order views after the found views they use
+        let mut ordered: Vec<&Dependency> = Vec::with_capacity(found.len());
+        let mut placed: HashSet<String> = HashSet::new();
+        while ordered.len() < found.len() {
+            let before = ordered.len();
+            for edge in &found {
+                if placed.contains(&edge.dependent.to_lowercase()) {
+                    continue;
+                }
+                let ready = self.dependencies(&edge.dependent).iter().all(|d| {
+                    d.target.eq_ignore_ascii_case(name)
+                        || placed.contains(&d.target.to_lowercase())
+                        || !found.iter().any(|f| f.dependent.eq_ignore_ascii_case(&d.target))
+                });
+                if ready {
+                    placed.insert(edge.dependent.to_lowercase());
+                    ordered.push(edge);
+                }
+            }
+            if ordered.len() == before {
+                // A cycle can't be created in SQLite; keep the rest as found
+                ordered.extend(found.iter().filter(|e| !placed.contains(&e.dependent.to_lowercase())));
+                break;
+            }
+        }
+
+        ordered
+    }
+
+    /// What dropping `name`, or its `column`, would affect
+    ///
+    /// `partition_policies` are (table, date column) pairs of the connection's
+    /// partition policies.
+    pub fn drop_impact(
+        &self,
+        schema: &SchemaInfo,
+        name: &str,
+        column: Option<&str>,
+        partition_policies: &[(String, String)],
+    ) -> Option<DropImpact> {
+        let objects = Objects::new(schema);
+        let kind = objects.find(name).map(|(_, kind)| kind)
+            .or_else(|| schema.indexes.iter().any(|i| i.name.eq_ignore_ascii_case(name)).then_some(ObjectKind::Index))
+            .or_else(|| schema.triggers.iter().any(|t| t.name.eq_ignore_ascii_case(name)).then_some(ObjectKind::Trigger))?;
+        let object = objects.find(name).map(|(n, _)| n.to_string())
+            .or_else(|| schema.indexes.iter().find(|i| i.name.eq_ignore_ascii_case(name)).map(|i| i.name.clone()))
+            .or_else(|| schema.triggers.iter().find(|t| t.name.eq_ignore_ascii_case(name)).map(|t| t.name.clone()))?;
+
+        let uses_column = |e: &Dependency| match column {
+            None => true,
+            Some(column) => e.columns.iter().any(|c| c.eq_ignore_ascii_case(column)),
+        };
+
+        // Indexes and the table's own triggers go with a dropped table; with a
+        // dropped column SQLite refuses instead, so they count as broken
+        let mut dropped_with = Vec::new();
+        let mut broken: Vec<Dependency> = Vec::new();
+        for edge in self.dependents(&object) {
+            match edge.kind {
+                DependencyKind::Index | DependencyKind::TriggerTarget if column.is_none() && kind == ObjectKind::Table => {
+                    dropped_with.push(edge.dependent.clone());
+                }
+                DependencyKind::TriggerTarget if column.is_none() => dropped_with.push(edge.dependent.clone()),
+                DependencyKind::ForeignKey => {}
+                _ if uses_column(edge) => broken.push(edge.clone()),
+                _ => {}
+            }
+        }
+
+        // Views built on broken views break too
+        let broken_views: Vec<String> = broken.iter()
+            .filter(|e| e.dependent_kind == ObjectKind::View)
+            .map(|e| e.dependent.clone())
+            .collect();
+        for view in broken_views {
+            for edge in self.view_and_trigger_dependents(&view) {
+                if !broken.iter().any(|b| b.dependent.eq_ignore_ascii_case(&edge.dependent)) {
+                    broken.push(edge.clone());
+                }
+            }
+        }
+        broken.retain(|e| !dropped_with.iter().any(|d| d.eq_ignore_ascii_case(&e.dependent)));
+
+        let mut foreign_keys: Vec<ForeignKeyConstraint> = Vec::new();
+        if let Some(table) = schema.tables.iter().find(|t| t.name.eq_ignore_ascii_case(&object)) {
+            foreign_keys.extend(table.referenced_by.iter()
+                .filter(|fk| !fk.table.eq_ignore_ascii_case(&object) || column.is_some())
+                .filter(|fk| column.map_or(true, |c| fk.columns.iter().any(|p| p.to.eq_ignore_ascii_case(c))))
+                .cloned());
+            if let Some(column) = column {
+                foreign_keys.extend(table.foreign_keys.iter()
+                    .filter(|fk| fk.columns.iter().any(|p| p.from.eq_ignore_ascii_case(column)))
+                    .filter(|fk| !foreign_keys.iter().any(|f| f.table == fk.table && f.id == fk.id))
+                    .cloned()
+                    .collect::<Vec<_>>());
+            }
+        }
+
+        let partition_policies: Vec<String> = partition_policies.iter()
+            .filter(|(table, date_column)| {
+                table.eq_ignore_ascii_case(&object) && column.map_or(true, |c| date_column.eq_ignore_ascii_case(c))
+            })
+            .map(|(table, _)| table.clone())
+            .collect();
+
+        let mut warnings = Vec::new();
+        let subject = match column {
+            Some(column) => format!("column {} of {}", column, object),
+            None => object.clone(),
+        };
+        if !dropped_with.is_empty() {
+            warnings.push(format!("Dropping {} also drops {}", subject, dropped_with.join(", ")));
+        }
+        for edge in &broken {
+            let what = match edge.dependent_kind {
+                ObjectKind::View => "View",
+                ObjectKind::Index => "Index",
+                ObjectKind::Trigger => "Trigger",
+                ObjectKind::Table => "Table",
+            };
+            warnings.push(format!("{} {} uses {} and will fail", what, edge.dependent, edge.target));
+        }
+        for fk in &foreign_keys {
+            let columns: Vec<&str> = fk.columns.iter().map(|p| p.from.as_str()).collect();
+            warnings.push(format!(
+                "Foreign key {}({}) references {}",
+                fk.table,
+                columns.join(", "),
+                fk.referenced_table
+            ));
+        }
+        for table in &partition_policies {
+            warnings.push(format!("The partition policy of {} uses {}", table, subject));
+        }
+
+        Some(DropImpact {
+            object,
+            kind,
+            column: column.map(str::to_string),
+            dropped_with,
+            broken,
+            foreign_keys,
+            partition_policies,
+            warnings,
+        })
+    }
+}
+
+/// Objects DROP statements in `sql` would remove, as (name, column) pairs
+///
+/// Covers DROP TABLE, VIEW, INDEX and TRIGGER, and ALTER TABLE ... DROP COLUMN.
+pub fn dropped_objects(sql: &str) -> Vec<(String, Option<String>)> {
+    let tokens = ddl::tokenize(sql);
+    let mut dropped = Vec::new();
+
+    for statement in tokens.split(|t| t.is_punct(';')) {
+        let keyword = |i: usize, k: &str| statement.get(i).is_some_and(|t| t.is_keyword(k));
+        // Skip an optional `schema.` before a name starting at `i`
+        let name_at = |mut i: usize| {
+            if statement.get(i + 1).is_some_and(|t| t.is_punct('.')) {
+                i += 2;
+            }
+            statement.get(i).filter(|t| matches!(t.kind, TokenKind::Word | TokenKind::QuotedIdent)).and_then(|t| t.identifier()).map(|n| (n, i))
+        };
+
+        if keyword(0, "DROP") && ["TABLE", "VIEW", "INDEX", "TRIGGER"].iter().any(|k| keyword(1, k)) {
+            let start = if keyword(2, "IF") && keyword(3, "EXISTS") { 4 } else { 2 };
+            if let Some((name, _)) = name_at(start) {
+                dropped.push((name, None));
+            }
+        } else if keyword(0, "ALTER") && keyword(1, "TABLE") {
+            let Some((table, end)) = name_at(2) else { continue };
+            if keyword(end + 1, "DROP") {
+                let at = if keyword(end + 2, "COLUMN") { end + 3 } else { end + 2 };
+                if let Some(column) = statement.get(at).filter(|t| matches!(t.kind, TokenKind::Word | TokenKind::QuotedIdent)).and_then(|t| t.identifier()) {
+                    dropped.push((table, Some(column)));
+                }
+            }
+        }
+    }
+
+    dropped
+}
+
+/// Whether every mention of `table` in the tokens is as the NEW or OLD row of a trigger
+fn is_row_alias_only(tokens: &[Token], table: &str) -> bool {
+    !tokens.iter()
+        .filter(|t| matches!(t.kind, TokenKind::Word | TokenKind::QuotedIdent))
+        .filter_map(|t| t.identifier())
+        .any(|name| name.eq_ignore_ascii_case(table))
+}
+
+fn merge(into: &mut Vec<String>, columns: Vec<String>) {
+    for column in columns {
+        if !into.iter().any(|c| c.eq_ignore_ascii_case(&column)) {
+            into.push(column);
+        }
+    }
+}
+
+/// Tables and views by lowercase name, with their column names
+struct Objects<'a> {
+    by_name: HashMap<String, (&'a str, ObjectKind, Vec<&'a str>)>,
+}
+
+impl<'a> Objects<'a> {
+    fn new(schema: &'a SchemaInfo) -> Self {
+        let mut by_name = HashMap::new();
+        for table in schema.tables.iter().filter(|t| t.kind != TableKind::Shadow) {
+            let columns = table.columns.iter().map(|c| c.name.as_str()).collect();
+            by_name.insert(table.name.to_lowercase(), (table.name.as_str(), ObjectKind::Table, columns));
+        }
+        for view in &schema.views {
+            by_name.insert(view.name.to_lowercase(), (view.name.as_str(), ObjectKind::View, Vec::new()));
+        }
+        Self { by_name }
+    }
+
+    fn find(&self, name: &str) -> Option<(&'a str, ObjectKind)> {
+        self.by_name.get(&name.to_lowercase()).map(|(name, kind, _)| (*name, *kind))
+    }
+
+    fn kind(&self, name: &str) -> ObjectKind {
+        self.find(name).map(|(_, kind)| kind).unwrap_or(ObjectKind::Table)
+    }
+
+    fn column(&self, table: &str, column: &str) -> Option<&'a str> {
+        let (_, _, columns) = self.by_name.get(&table.to_lowercase())?;
+        columns.iter().copied().find(|c| c.eq_ignore_ascii_case(column))
+    }
+
+    /// Tables and views the tokens name, each with the columns used of it
+    ///
+    /// `row_table` is the table NEW and OLD refer to in a trigger.
+    fn references(&self, tokens: &[Token], row_table: Option<&str>) -> Vec<(String, Vec<String>)> {
+        let word = |i: usize| {
+            tokens.get(i)
+                .filter(|t| matches!(t.kind, TokenKind::Word | TokenKind::QuotedIdent))
+                .and_then(|t| t.identifier())
+        };
+        let dot = |i: usize| tokens.get(i).is_some_and(|t| t.is_punct('.'));
+
+        let mut found: Vec<(String, Vec<String>)> = Vec::new();
+        let mut aliases: HashMap<String, String> = HashMap::new();
+        let mut qualified: Vec<(String, String)> = Vec::new();
+        let mut bare: Vec<String> = Vec::new();
+
+        let mut i = 0;
+        while i < tokens.len() {
+            let Some(name) = word(i) else {
+                i += 1;
+                continue;
+            };
+            if i > 0 && dot(i - 1) {
+                i += 1;
+                continue;
+            }
+
+            // `schema.table` or `qualifier.column`
+            if dot(i + 1) {
+                if let Some(second) = word(i + 2) {
+                    let is_schema = ["main", "temp"].iter().any(|s| s.eq_ignore_ascii_case(&name));
+                    if is_schema && self.find(&second).is_some() {
+                        i += 2;
+                        continue;
+                    }
+                    qualified.push((name, second));
+                    i += 3;
+                    continue;
+                }
+            }
+
+            if let Some((object, _)) = self.find(&name) {
+                if !found.iter().any(|(n, _)| n == object) {
+                    found.push((object.to_string(), Vec::new()));
+                }
+                // `table AS alias` or `table alias`
+                let alias_at = if tokens.get(i + 1).is_some_and(|t| t.is_keyword("AS")) { i + 2 } else { i + 1 };
+                if let Some(alias) = word(alias_at) {
+                    let keyword = tokens[alias_at].kind == TokenKind::Word
+                        && CLAUSE_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(&alias));
+                    if !keyword {
+                        aliases.insert(alias.to_lowercase(), object.to_string());
+                    }
+                }
+                aliases.insert(name.to_lowercase(), object.to_string());
+
+                // `INSERT INTO table (columns)`
+                if i > 0 && tokens[i - 1].is_keyword("INTO") && tokens.get(i + 1).is_some_and(|t| t.is_punct('(')) {
+                    if let Some(close) = ddl::closing_paren(tokens, i + 1) {
+                        for j in i + 2..close {
+                            if let Some(column) = word(j) {
+                                qualified.push((name.clone(), column));
+                            }
+                        }
+                        i = close + 1;
+                        continue;
+                    }
+                }
+            } else {
+                bare.push(name);
+            }
+            i += 1;
+        }
+
+        let add = |found: &mut Vec<(String, Vec<String>)>, table: &str, column: &str| {
+            if let Some((_, columns)) = found.iter_mut().find(|(n, _)| n.eq_ignore_ascii_case(table)) {
+                let column = self.column(table, column).unwrap_or(column).to_string();
+                merge(columns, vec![column]);
+            }
+        };
+
+        let mut row_columns = Vec::new();
+        for (qualifier, column) in &qualified {
+            let is_row = ["new", "old"].iter().any(|r| r.eq_ignore_ascii_case(qualifier));
+            if is_row && row_table.is_some() {
+                row_columns.push(column.clone());
+            } else if let Some(table) = aliases.get(&qualifier.to_lowercase()).cloned() {
+                add(&mut found, &table, column);
+            }
+        }
+
+        // Bare names count for every table named in the statement that has such a column
+        let tables: Vec<String> = found.iter().map(|(n, _)| n.clone()).collect();
+        for name in &bare {
+            for table in &tables {
+                if self.column(table, name).is_some() {
+                    add(&mut found, table, name);
+                }
+            }
+        }
+
+        if let (Some(table), false) = (row_table, row_columns.is_empty()) {
+            if !found.iter().any(|(n, _)| n.eq_ignore_ascii_case(table)) {
+                found.push((self.find(table).map(|(n, _)| n).unwrap_or(table).to_string(), Vec::new()));
+            }
+            for column in &row_columns {
+                add(&mut found, table, column);
+            }
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlite3x::wrapper::Database;
+
+    #[test]
+    fn test_dependency_graph() {
+        let db = Database::open(":memory:").unwrap();
+        db.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, email TEXT);
+             CREATE TABLE posts (id INTEGER PRIMARY KEY, author_id INTEGER REFERENCES users(id), title TEXT);
+             CREATE TABLE audit (message TEXT);
+             CREATE INDEX idx_users_email ON users (email);
+             CREATE VIEW authors AS SELECT u.name, p.title FROM users u JOIN posts AS p ON p.author_id = u.id;
+             CREATE VIEW author_names AS SELECT name FROM authors;
+             CREATE TRIGGER posts_audit AFTER INSERT ON posts BEGIN
+                 INSERT INTO audit (message) SELECT name FROM users WHERE id = NEW.author_id;
+             END;",
+        )
+        .unwrap();
+        let schema = db.get_schema().unwrap();
+        let graph = DependencyGraph::build(&schema);
+
+        let authors = graph.dependencies("authors");
+        let users = authors.iter().find(|d| d.target == "users").unwrap();
+        assert_eq!(users.columns, vec!["name", "id"]);
+        let posts = authors.iter().find(|d| d.target == "posts").unwrap();
+        assert_eq!(posts.columns, vec!["title", "author_id"]);
+
+        let trigger = graph.dependencies("posts_audit");
+        let target = trigger.iter().find(|d| d.kind == DependencyKind::TriggerTarget).unwrap();
+        assert_eq!((target.target.as_str(), target.columns.clone()), ("posts", vec!["author_id".to_string()]));
+        let audit = trigger.iter().find(|d| d.target == "audit").unwrap();
+        assert_eq!(audit.columns, vec!["message"]);
+
+        let dependents: Vec<&str> = graph.view_and_trigger_dependents("users").iter().map(|d| d.dependent.as_str()).collect();
+        let position = |name: &str| dependents.iter().position(|&n| n == name).unwrap();
+        assert!(position("authors") < position("author_names"));
+        assert!(dependents.contains(&"posts_audit"));
+
+        // Dropping users takes its index and breaks both views, the trigger and a foreign key
+        let policies = vec![("users".to_string(), "email".to_string())];
+        let impact = graph.drop_impact(&schema, "users", None, &policies).unwrap();
+        assert_eq!(impact.dropped_with, vec!["idx_users_email"]);
+        assert_eq!(impact.broken.len(), 3);
+        assert_eq!(impact.foreign_keys.len(), 1);
+        assert_eq!(impact.partition_policies, vec!["users"]);
+
+        // Only what uses the column is affected by dropping it
+        let impact = graph.drop_impact(&schema, "users", Some("email"), &policies).unwrap();
+        assert_eq!(impact.broken.iter().map(|d| d.dependent.as_str()).collect::<Vec<_>>(), vec!["idx_users_email"]);
+        assert!(impact.foreign_keys.is_empty());
+
+        assert_eq!(
+            dropped_objects("DROP VIEW IF EXISTS main.authors; ALTER TABLE \"users\" DROP COLUMN email"),
+            vec![("authors".to_string(), None), ("users".to_string(), Some("email".to_string()))]
+        );
+    }
+}
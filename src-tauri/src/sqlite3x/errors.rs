@@ -13,12 +13,22 @@ pub enum Sqlite3xError {
     #[error("Query error: {0}")]
     Query(String),
 
+    /// A statement failed a UNIQUE, CHECK, NOT NULL or foreign key constraint
+    #[error("Constraint violation: {0}")]
+    Constraint(String),
+
     #[error("Transaction error: {0}")]
     Transaction(String),
 
     #[error("Schema error: {0}")]
     Schema(String),
 
+    #[error("Session error: {0}")]
+    Session(String),
+
+    #[error("Changeset conflict: {0}")]
+    ChangesetConflict(String),
+
     #[error("FFI error: {0}")]
     Ffi(String),
 
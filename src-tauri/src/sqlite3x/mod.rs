@@ -7,7 +7,13 @@ pub mod types;
 pub mod wrapper;
 pub mod partition;
 pub mod ffi;
+pub mod session;
+pub mod ddl;
+pub mod table_definition;
+pub mod rebuild;
+pub mod dependencies;
 
 pub use errors::Sqlite3xError;
-pub use wrapper::{Database, QueryResult, SchemaInfo, TableInfo, ViewInfo, IndexInfo, TriggerInfo, ColumnInfo, AttachedDatabase};
+pub use wrapper::{Database, QueryResult, SchemaInfo, TableInfo, ViewInfo, IndexInfo, TriggerInfo, ColumnInfo, AttachedDatabase, ForeignKeyConstraint};
+pub use table_definition::TableDefinition;
 pub use partition::{PartitionManager, PartitionConfig, PartitionStrategy, PartitionPolicy, PartitionMetadata};
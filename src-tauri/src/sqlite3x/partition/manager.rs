@@ -301,6 +301,30 @@ impl PartitionManager {
         Ok(())
     }
 
+    /// 테이블 이름 변경을 정책에 반영합니다. 변경된 정책이 있으면 true
+    pub fn rename_policy_table(&self, old_name: &str, new_name: &str) -> bool {
+        let mut config = self.config.write();
+        let mut changed = false;
+        for policy in config.policies.iter_mut().filter(|p| p.table_name.eq_ignore_ascii_case(old_name)) {
+            policy.table_name = new_name.to_string();
+            changed = true;
+        }
+        changed
+    }
+
+    /// 컬럼 이름 변경을 정책의 날짜 컬럼에 반영합니다. 변경된 정책이 있으면 true
+    pub fn rename_policy_column(&self, table_name: &str, old_name: &str, new_name: &str) -> bool {
+        let mut config = self.config.write();
+        let mut changed = false;
+        for policy in config.policies.iter_mut()
+            .filter(|p| p.table_name.eq_ignore_ascii_case(table_name) && p.date_column.eq_ignore_ascii_case(old_name))
+        {
+            policy.date_column = new_name.to_string();
+            changed = true;
+        }
+        changed
+    }
+
     pub fn run_partition_maintenance(&self) -> Sqlite3Result<usize> {
         let config = self.config.read();
         let dbs = self.attached_dbs.lock();
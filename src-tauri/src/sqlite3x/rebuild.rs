@@ -0,0 +1,483 @@
+//! sqlite3x Table Rebuilds
+//!
+//! The 12-step procedure from the SQLite documentation for changes ALTER
+//! TABLE cannot make: create the new table, copy the rows, drop the old
+//! table, rename the new one into place, and recreate what depended on it.
+
+use super::ddl::{self, TokenKind, TriggerEvent};
+use super::dependencies::{DependencyGraph, ObjectKind};
+use super::errors::{Sqlite3xError, Sqlite3xResult};
+use super::table_definition::TableDefinition;
+use super::types::CellValue;
+use super::wrapper::{Database, SchemaInfo, TableKind};
+use crate::utils::quote_identifier;
+use std::collections::{HashMap, HashSet};
+
+/// Statements that rebuild one table
+#[derive(Debug, Clone)]
+pub struct RebuildScript {
+    /// Statements to run in one transaction with foreign keys off
+    pub statements: Vec<String>,
+    /// Tables whose foreign keys must be checked before committing
+    pub check_tables: Vec<String>,
+    /// Objects that could not be carried over, e.g. indexes on dropped columns
+    pub warnings: Vec<String>,
+    /// Recreated views and triggers, to compile before committing
+    pub views: Vec<String>,
+    pub triggers: Vec<String>,
+}
+
+impl RebuildScript {
+    /// The whole script, including the transaction and foreign key handling
+    pub fn to_sql(&self) -> String {
+        let mut lines = vec!["PRAGMA foreign_keys = OFF;".to_string(), "BEGIN;".to_string()];
+        lines.extend(self.statements.iter().map(|s| format!("{};", s)));
+        lines.extend(self.check_tables.iter().map(|t| format!("PRAGMA foreign_key_check({});", quote_identifier(t))));
+        lines.push("COMMIT;".to_string());
+        lines.push("PRAGMA foreign_keys = ON;".to_string());
+        lines.join("\n")
+    }
+}
+
+/// Plan the rebuild of `table` into `definition`
+///
+/// Columns are copied by name; `renamed_columns` maps a new column name to
+/// the old column its values come from. New columns without a source get
+/// their default.
+pub fn rebuild_table(
+    schema: &SchemaInfo,
+    table: &str,
+    definition: &TableDefinition,
+    renamed_columns: &HashMap<String, String>,
+) -> Sqlite3xResult<RebuildScript> {
+    definition.validate()?;
+
+    let old = schema.tables.iter()
+        .find(|t| t.name.eq_ignore_ascii_case(table))
+        .ok_or_else(|| Sqlite3xError::Schema(format!("Table not found: {}", table)))?;
+    if old.kind != TableKind::Table {
+        return Err(Sqlite3xError::Schema(format!("{} is not an ordinary table", old.name)));
+    }
+
+    let taken: HashSet<String> = schema.tables.iter().map(|t| t.name.to_lowercase())
+        .chain(schema.views.iter().map(|v| v.name.to_lowercase()))
+        .chain(schema.indexes.iter().map(|i| i.name.to_lowercase()))
+        .chain(schema.triggers.iter().map(|t| t.name.to_lowercase()))
+        .collect();
+
+    let target = definition.name.clone();
+    if !target.eq_ignore_ascii_case(&old.name) && taken.contains(&target.to_lowercase()) {
+        return Err(Sqlite3xError::Schema(format!("{} already exists", target)));
+    }
+
+    let mut temp = format!("{}_new", target);
+    let mut suffix = 1;
+    while taken.contains(&temp.to_lowercase()) {
+        suffix += 1;
+        temp = format!("{}_new{}", target, suffix);
+    }
+
+    let mut statements = Vec::new();
+    let mut warnings = Vec::new();
+
+    // 1. Views and other tables' triggers that mention the table; the rename
+    //    below fails while they point at a missing table
+    let dependents = dependents_of(schema, &old.name);
+    for (kind, name, _) in dependents.iter().rev() {
+        statements.push(format!("DROP {} {}", kind, quote_identifier(name)));
+    }
+
+    // 2. The new table under a temporary name
+    let mut new_definition = definition.clone();
+    new_definition.name = temp.clone();
+    statements.push(new_definition.to_sql()?);
+
+    // 3. Copy the rows
+    let mut targets = Vec::new();
+    let mut sources = Vec::new();
+    for column in definition.columns.iter().filter(|c| c.generated.is_none()) {
+        let source = renamed_columns.get(&column.name).unwrap_or(&column.name);
+        match old.columns.iter().find(|c| !c.hidden && c.name.eq_ignore_ascii_case(source)) {
+            Some(old_column) => {
+                targets.push(quote_identifier(&column.name));
+                sources.push(quote_identifier(&old_column.name));
+            }
+            None if renamed_columns.contains_key(&column.name) => {
+                return Err(Sqlite3xError::Schema(format!("{} has no column {}", old.name, source)));
+            }
+            None => {}
+        }
+    }
+    if targets.is_empty() {
+        warnings.push(format!("No columns of {} are kept; its rows are not copied", old.name));
+    } else {
+        statements.push(format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {}",
+            quote_identifier(&temp),
+            targets.join(", "),
+            sources.join(", "),
+            quote_identifier(&old.name)
+        ));
+    }
+
+    // 4. Swap the tables
+    statements.push(format!("DROP TABLE {}", quote_identifier(&old.name)));
+    statements.push(format!("ALTER TABLE {} RENAME TO {}", quote_identifier(&temp), quote_identifier(&target)));
+
+    // 5. Indexes and triggers of the table, which went with the old table
+    let mut table_names = HashMap::new();
+    if target != old.name {
+        table_names.insert(old.name.to_lowercase(), target.clone());
+    }
+    let mut column_names = table_names.clone();
+    for (new, old_name) in renamed_columns {
+        column_names.insert(old_name.to_lowercase(), new.clone());
+    }
+    let has_column = |name: &str| {
+        definition.columns.iter().any(|c| c.name.eq_ignore_ascii_case(name))
+    };
+    let old_columns: HashSet<String> = old.columns.iter().map(|c| c.name.to_lowercase()).collect();
+
+    for index in schema.indexes.iter().filter(|i| i.table_name.eq_ignore_ascii_case(&old.name)) {
+        let Some(sql) = &index.sql else { continue };
+        // Columns named in the key, in key expressions and in the WHERE clause
+        let missing = identifiers_after(sql, "ON")
+            .into_iter()
+            .skip(1)
+            .filter(|name| old_columns.contains(&name.to_lowercase()))
+            .map(|name| column_names.get(&name.to_lowercase()).cloned().unwrap_or(name))
+            .find(|name| !has_column(name));
+        if let Some(column) = missing {
+            warnings.push(format!("Index {} is dropped: column {} no longer exists", index.name, column));
+            continue;
+        }
+        statements.push(rewrite_after(sql, "ON", &column_names));
+    }
+
+    // Renamed columns are rewritten in trigger bodies and dependents by
+    // SQLite itself; failing that, by name, and compiling them catches mistakes
+    let own_triggers: Vec<(&str, &str)> = schema.triggers.iter()
+        .filter(|t| t.table_name.eq_ignore_ascii_case(&old.name))
+        .filter_map(|t| Some((t.name.as_str(), t.sql.as_deref()?)))
+        .collect();
+    let renamed = if renamed_columns.iter().all(|(new, old)| new.eq_ignore_ascii_case(old)) {
+        HashMap::new()
+    } else {
+        let triggers: Vec<(&str, &str)> = own_triggers.iter().copied()
+            .chain(dependents.iter()
+                .filter(|(kind, _, _)| *kind == "TRIGGER")
+                .map(|(_, name, sql)| (name.as_str(), sql.as_str())))
+            .collect();
+        renamed_in_copy(schema, &old.name, &triggers, renamed_columns).unwrap_or_else(|e| {
+            log::warn!("Rewriting renamed columns of {} by name: {}", old.name, e);
+            HashMap::new()
+        })
+    };
+    let rewrite = |name: &str, sql: &str, keyword: &str| match renamed.get(&name.to_lowercase()) {
+        Some(sql) => rewrite_after(sql, keyword, &table_names),
+        None => rewrite_after(sql, keyword, &column_names),
+    };
+
+    let mut triggers = Vec::new();
+    for (name, sql) in &own_triggers {
+        statements.push(rewrite(name, sql, "ON"));
+        triggers.push(name.to_string());
+    }
+
+    // 6. The dependents, in the order they were found
+    let mut views = Vec::new();
+    for (kind, name, sql) in &dependents {
+        let keyword = if *kind == "VIEW" { "AS" } else { "ON" };
+        statements.push(rewrite(name, sql, keyword));
+        if *kind == "VIEW" {
+            views.push(name.clone());
+        } else {
+            triggers.push(name.clone());
+        }
+    }
+
+    // Foreign keys of the table and of tables referencing it
+    let mut check_tables = vec![target.clone()];
+    for fk in &old.referenced_by {
+        if !check_tables.iter().any(|t| t.eq_ignore_ascii_case(&fk.table)) && !fk.table.eq_ignore_ascii_case(&old.name) {
+            check_tables.push(fk.table.clone());
+        }
+    }
+
+    Ok(RebuildScript { statements, check_tables, warnings, views, triggers })
+}
+
+/// Check that views, and triggers on tables and views, still compile
+///
+/// SQLite accepts views and triggers that refer to missing tables or columns
+/// and only fails once they are used. Views are queried for no rows, and
+/// each trigger's table gets a statement that fires it but matches no rows.
+pub fn check_compiles(db: &Database, views: &[String], triggers: &[String]) -> Sqlite3xResult<()> {
+    for view in views {
+        db.query_values(&format!("SELECT * FROM {} LIMIT 0", quote_identifier(view)), &[])
+            .map_err(|e| Sqlite3xError::Schema(format!("View {} no longer works: {}", view, e)))?;
+    }
+
+    if triggers.is_empty() {
+        return Ok(());
+    }
+    let schema = db.get_schema()?;
+    for name in triggers {
+        let Some(trigger) = schema.triggers.iter().find(|t| t.name.eq_ignore_ascii_case(name)) else { continue };
+        let Some(definition) = &trigger.definition else { continue };
+
+        let table = quote_identifier(&trigger.table_name);
+        let columns: Vec<String> = db
+            .query_values(
+                "SELECT name FROM pragma_table_xinfo(?1) WHERE hidden = 0 ORDER BY cid",
+                &[CellValue::Text(trigger.table_name.clone())],
+            )?
+            .into_iter()
+            .filter_map(|row| match row.into_iter().next() {
+                Some(CellValue::Text(name)) => Some(quote_identifier(&name)),
+                _ => None,
+            })
+            .collect();
+
+        let statement = match definition.event {
+            TriggerEvent::Insert => format!(
+                "INSERT INTO {table} ({columns}) SELECT {columns} FROM {table} WHERE 0",
+                table = table,
+                columns = columns.join(", ")
+            ),
+            TriggerEvent::Update => {
+                let set = if definition.update_columns.is_empty() {
+                    columns.clone()
+                } else {
+                    definition.update_columns.iter().map(|c| quote_identifier(c)).collect()
+                };
+                let assignments: Vec<String> = set.iter().map(|c| format!("{} = {}", c, c)).collect();
+                format!("UPDATE {} SET {} WHERE 0", table, assignments.join(", "))
+            }
+            TriggerEvent::Delete => format!("DELETE FROM {} WHERE 0", table),
+        };
+        db.execute(&statement)
+            .map_err(|e| Sqlite3xError::Schema(format!("Trigger {} no longer works: {}", trigger.name, e)))?;
+    }
+
+    Ok(())
+}
+
+/// SQL of the views and the given (name, SQL) triggers after SQLite renames
+/// columns of `table`, by lowercase name
+///
+/// The renames run on an in-memory database with all tables, views and the
+/// given triggers, so only references to `table` change. Tables other than
+/// ordinary ones are stood in for by plain tables with the same columns.
+pub(crate) fn renamed_in_copy(
+    schema: &SchemaInfo,
+    table: &str,
+    triggers: &[(&str, &str)],
+    renamed_columns: &HashMap<String, String>,
+) -> Sqlite3xResult<HashMap<String, String>> {
+    let copy = Database::open(":memory:")?;
+    let tables = schema.tables.iter().map(|t| match (&t.kind, &t.sql) {
+        (TableKind::Table, Some(sql)) => sql.clone(),
+        _ => format!(
+            "CREATE TABLE {} ({})",
+            quote_identifier(&t.name),
+            t.columns.iter().map(|c| quote_identifier(&c.name)).collect::<Vec<_>>().join(", ")
+        ),
+    });
+    let views = schema.views.iter().filter_map(|v| v.sql.clone());
+    for statement in tables.chain(views).chain(triggers.iter().map(|(_, sql)| sql.to_string())) {
+        copy.execute(&statement)?;
+    }
+
+    // Through temporary names, so columns can swap names
+    let renames: Vec<(&String, &String)> = renamed_columns.iter()
+        .filter(|(new, old)| !new.eq_ignore_ascii_case(old))
+        .map(|(new, old)| (old, new))
+        .collect();
+    let temporary = |i: usize| format!("__renamed_column_{}", i);
+    for (i, (old, _)) in renames.iter().enumerate() {
+        copy.execute(&format!(
+            "ALTER TABLE {} RENAME COLUMN {} TO {}",
+            quote_identifier(table),
+            quote_identifier(old),
+            quote_identifier(&temporary(i))
+        ))?;
+    }
+    for (i, (_, new)) in renames.iter().enumerate() {
+        copy.execute(&format!(
+            "ALTER TABLE {} RENAME COLUMN {} TO {}",
+            quote_identifier(table),
+            quote_identifier(&temporary(i)),
+            quote_identifier(new)
+        ))?;
+    }
+
+    let rows = copy.query_values("SELECT name, sql FROM sqlite_master WHERE type IN ('view', 'trigger')", &[])?;
+    Ok(rows.into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [CellValue::Text(name), CellValue::Text(sql)] => Some((name.to_lowercase(), sql.clone())),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Identifiers that follow the first `keyword`
+fn identifiers_after(sql: &str, keyword: &str) -> Vec<String> {
+    let tokens = ddl::tokenize(sql);
+    let Some(start) = tokens.iter().position(|t| t.is_keyword(keyword)) else {
+        return Vec::new();
+    };
+
+    tokens[start + 1..].iter()
+        .filter(|t| matches!(t.kind, TokenKind::Word | TokenKind::QuotedIdent))
+        .filter_map(|t| t.identifier())
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Views, and triggers of other tables, that use `table` directly or
+/// through another such view, as (kind, name, sql) in the order to recreate them
+fn dependents_of(schema: &SchemaInfo, table: &str) -> Vec<(&'static str, String, String)> {
+    DependencyGraph::build(schema)
+        .view_and_trigger_dependents(table)
+        .into_iter()
+        .filter_map(|edge| match edge.dependent_kind {
+            ObjectKind::View => {
+                let view = schema.views.iter().find(|v| v.name == edge.dependent)?;
+                Some(("VIEW", view.name.clone(), view.sql.clone()?))
+            }
+            ObjectKind::Trigger => {
+                let trigger = schema.triggers.iter().find(|t| t.name == edge.dependent)?;
+                if trigger.table_name.eq_ignore_ascii_case(table) {
+                    return None;
+                }
+                Some(("TRIGGER", trigger.name.clone(), trigger.sql.clone()?))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Replace identifiers named in `renames` (lowercase keys) that follow the first `keyword`
+pub(crate) fn rewrite_after(sql: &str, keyword: &str, renames: &HashMap<String, String>) -> String {
+    if renames.is_empty() {
+        return sql.to_string();
+    }
+
+    let tokens = ddl::tokenize(sql);
+    let Some(start) = tokens.iter().position(|t| t.is_keyword(keyword)) else {
+        return sql.to_string();
+    };
+
+    let mut out = String::with_capacity(sql.len());
+    let mut copied = 0;
+    for token in &tokens[start + 1..] {
+        if !matches!(token.kind, TokenKind::Word | TokenKind::QuotedIdent) {
+            continue;
+        }
+        if let Some(new) = token.identifier().and_then(|name| renames.get(&name.to_lowercase())) {
+            out.push_str(&sql[copied..token.start]);
+            out.push_str(&quote_identifier(new));
+            copied = token.end();
+        }
+    }
+    out.push_str(&sql[copied..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlite3x::wrapper::Database;
+
+    #[test]
+    fn test_rebuild_table() {
+        let db = Database::open(":memory:").unwrap();
+        db.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, age TEXT, legacy TEXT);
+             CREATE TABLE posts (id INTEGER PRIMARY KEY, user_id INTEGER REFERENCES users(id));
+             CREATE TABLE signups (user_id INTEGER);
+             CREATE INDEX idx_users_name ON users (name);
+             CREATE INDEX idx_users_legacy ON users (legacy);
+             CREATE INDEX idx_users_legacy_lower ON users (lower(legacy));
+             CREATE VIEW names AS SELECT name FROM users;
+             CREATE VIEW adults AS SELECT * FROM users WHERE age >= 18;
+             CREATE VIEW adult_ids AS SELECT id FROM adults;
+             CREATE TRIGGER users_touch AFTER UPDATE ON users BEGIN SELECT 1; END;
+             CREATE TRIGGER users_post AFTER INSERT ON users WHEN NEW.name IS NOT NULL
+             BEGIN INSERT INTO signups VALUES (NEW.id); END;
+             INSERT INTO users VALUES (1, 'ann', '30', 'x'), (2, 'bob', '12', 'y');
+             INSERT INTO posts VALUES (1, 1);",
+        )
+        .unwrap();
+
+        // Change a type, drop a column and rename another
+        let schema = db.get_schema().unwrap();
+        let mut definition = TableDefinition::from_schema(schema.tables.iter().find(|t| t.name == "users").unwrap()).unwrap();
+        definition.columns.retain(|c| c.name != "legacy");
+        definition.columns.iter_mut().find(|c| c.name == "age").unwrap().data_type = "INTEGER".to_string();
+        definition.columns.iter_mut().find(|c| c.name == "name").unwrap().name = "full_name".to_string();
+        let renames = HashMap::from([("full_name".to_string(), "name".to_string())]);
+
+        let script = rebuild_table(&schema, "users", &definition, &renames).unwrap();
+        assert_eq!(script.warnings.len(), 2);
+        assert_eq!(script.check_tables, vec!["users", "posts"]);
+
+        db.execute_batch("PRAGMA foreign_keys = OFF; BEGIN").unwrap();
+        for statement in &script.statements {
+            db.execute(statement).unwrap_or_else(|e| panic!("{}: {}", statement, e));
+        }
+        check_compiles(&db, &script.views, &script.triggers).unwrap();
+        db.execute_batch("COMMIT; PRAGMA foreign_keys = ON").unwrap();
+
+        let rows = db.query_values("SELECT full_name, typeof(age) FROM users ORDER BY id", &[]).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0][1], crate::sqlite3x::types::CellValue::Text("integer".to_string()));
+
+        let schema = db.get_schema().unwrap();
+        let index = schema.indexes.iter().find(|i| i.name == "idx_users_name").unwrap();
+        assert_eq!(index.columns, vec!["full_name"]);
+        assert!(!schema.indexes.iter().any(|i| i.name.starts_with("idx_users_legacy")));
+        assert!(schema.triggers.iter().any(|t| t.name == "users_touch"));
+        assert_eq!(schema.views.len(), 3);
+        let adults = db.query_values("SELECT id FROM adult_ids", &[]).unwrap();
+        assert_eq!(adults.len(), 1);
+        assert_eq!(db.query_values("SELECT full_name FROM names", &[]).unwrap().len(), 2);
+
+        // The trigger fires with its column renamed
+        db.execute("INSERT INTO users (id, full_name, age) VALUES (3, 'cy', 40)").unwrap();
+        let signups = db.query_values("SELECT user_id FROM signups WHERE user_id = 3", &[]).unwrap();
+        assert_eq!(signups.len(), 1);
+    }
+
+    #[test]
+    fn test_check_compiles_dropped_column() {
+        let db = Database::open(":memory:").unwrap();
+        db.execute_batch(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, label TEXT, note TEXT);
+             CREATE TABLE log (item_id INTEGER);
+             CREATE VIEW labels AS SELECT label FROM items;
+             CREATE TRIGGER items_log AFTER UPDATE OF note ON items BEGIN INSERT INTO log VALUES (NEW.id); END;
+             CREATE TRIGGER items_note AFTER DELETE ON items BEGIN INSERT INTO log SELECT length(OLD.note); END;",
+        )
+        .unwrap();
+
+        let schema = db.get_schema().unwrap();
+        let table = schema.tables.iter().find(|t| t.name == "items").unwrap();
+        let mut definition = TableDefinition::from_schema(table).unwrap();
+        definition.columns.retain(|c| c.name != "note");
+
+        let script = rebuild_table(&schema, "items", &definition, &HashMap::new()).unwrap();
+        db.execute_batch("BEGIN").unwrap();
+        for statement in &script.statements {
+            db.execute(statement).unwrap();
+        }
+        let error = check_compiles(&db, &script.views, &script.triggers).unwrap_err();
+        assert!(error.to_string().contains("items_"), "{}", error);
+        db.execute_batch("ROLLBACK").unwrap();
+
+        // Without the broken triggers, the view still compiles
+        let views = vec!["labels".to_string()];
+        check_compiles(&db, &views, &[]).unwrap();
+    }
+}
@@ -0,0 +1,372 @@
+//! sqlite3x Change Sessions
+//!
+//! Records row changes through the SQLite session extension and decodes the
+//! resulting changesets.
+
+use super::errors::{Sqlite3xError, Sqlite3xResult};
+use rusqlite::ffi;
+use serde::Serialize;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A session recording every change made through one connection
+///
+/// The session holds the raw connection handle, so it must be dropped before
+/// the connection it was created on. `Database` keeps its sessions for that
+/// reason and clears them before closing.
+pub struct ChangeSession {
+    raw: *mut ffi::sqlite3_session,
+    /// Set once a `SessionLimit` stopped the session for using too much memory
+    overflowed: Arc<AtomicBool>,
+    limited: bool,
+}
+
+// The session is only ever used while the owning connection's lock is held.
+unsafe impl Send for ChangeSession {}
+
+impl ChangeSession {
+    /// Create a session on `schema` that records changes to all of its tables,
+    /// including rowid tables without an explicit primary key
+    ///
+    /// # Safety
+    /// `db` must be a valid connection handle that outlives the session.
+    pub(crate) unsafe fn create(db: *mut ffi::sqlite3, schema: &str) -> Sqlite3xResult<Self> {
+        let schema = CString::new(schema)
+            .map_err(|e| Sqlite3xError::Session(format!("Invalid schema name: {}", e)))?;
+
+        let mut raw = ptr::null_mut();
+        check(ffi::sqlite3session_create(db, schema.as_ptr(), &mut raw), "create session")?;
+        let session = Self { raw, overflowed: Arc::new(AtomicBool::new(false)), limited: false };
+
+        let mut rowid: c_int = 1;
+        check(
+            ffi::sqlite3session_object_config(
+                session.raw,
+                ffi::SQLITE_SESSION_OBJCONFIG_ROWID,
+                &mut rowid as *mut c_int as *mut c_void,
+            ),
+            "configure session",
+        )?;
+        check(ffi::sqlite3session_attach(session.raw, ptr::null()), "attach session")?;
+
+        Ok(session)
+    }
+
+    /// Limit the memory the session may use for its recorded changes
+    ///
+    /// The returned check is meant for the connection's progress handler, so
+    /// the limit is enforced while statements run rather than once the whole
+    /// changeset has been built.
+    ///
+    /// # Safety
+    /// The check must not be run after the session is dropped.
+    pub(crate) unsafe fn limit(&mut self, max_bytes: usize) -> SessionLimit {
+        self.limited = true;
+        SessionLimit {
+            raw: self.raw,
+            max_bytes: max_bytes as i64,
+            overflowed: Arc::clone(&self.overflowed),
+        }
+    }
+
+    /// Whether a progress handler enforces a limit on the session
+    pub fn is_limited(&self) -> bool {
+        self.limited
+    }
+
+    /// Serialize the changes recorded so far; the session keeps recording
+    ///
+    /// Fails once the session went over its limit and stopped recording,
+    /// since the changes it holds are then incomplete.
+    pub fn changeset(&self) -> Sqlite3xResult<Vec<u8>> {
+        if self.overflowed.load(Ordering::Relaxed) {
+            return Err(Sqlite3xError::Session("Recorded changes exceeded the memory limit".to_string()));
+        }
+
+        let mut n: c_int = 0;
+        let mut data: *mut c_void = ptr::null_mut();
+
+        unsafe {
+            check(ffi::sqlite3session_changeset(self.raw, &mut n, &mut data), "build changeset")?;
+
+            if data.is_null() {
+                return Ok(Vec::new());
+            }
+
+            let bytes = std::slice::from_raw_parts(data as *const u8, n as usize).to_vec();
+            ffi::sqlite3_free(data);
+            Ok(bytes)
+        }
+    }
+
+    /// Whether no changes were recorded
+    pub fn is_empty(&self) -> bool {
+        unsafe { ffi::sqlite3session_isempty(self.raw) != 0 }
+    }
+}
+
+impl Drop for ChangeSession {
+    fn drop(&mut self) {
+        unsafe { ffi::sqlite3session_delete(self.raw) };
+    }
+}
+
+/// Memory limit of a `ChangeSession`, checked from a progress handler
+pub(crate) struct SessionLimit {
+    raw: *mut ffi::sqlite3_session,
+    max_bytes: i64,
+    overflowed: Arc<AtomicBool>,
+}
+
+// Only run by the connection the session belongs to, while its lock is held.
+unsafe impl Send for SessionLimit {}
+
+impl SessionLimit {
+    /// Stop the session from recording once it is over the limit
+    pub fn check(&self) {
+        if self.overflowed.load(Ordering::Relaxed) {
+            return;
+        }
+
+        unsafe {
+            if ffi::sqlite3session_memory_used(self.raw) > self.max_bytes {
+                ffi::sqlite3session_enable(self.raw, 0);
+                self.overflowed.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Kind of row change in a changeset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A single row change decoded from a changeset
+///
+/// Values are keyed by column name. An insert carries all new values and a
+/// delete all old values. An update carries the primary key and the changed
+/// columns in `old_values`, and the changed columns in `new_values`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowChange {
+    pub operation: ChangeOperation,
+    pub primary_key: serde_json::Map<String, serde_json::Value>,
+    pub old_values: serde_json::Map<String, serde_json::Value>,
+    pub new_values: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Changes of one table decoded from a changeset
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableChanges {
+    pub table: String,
+    pub inserted: Vec<RowChange>,
+    pub updated: Vec<RowChange>,
+    pub deleted: Vec<RowChange>,
+}
+
+/// Decode a changeset into per-table row changes
+///
+/// `column_names` resolves the column names of a table from its name and the
+/// number of columns recorded in the changeset.
+pub fn decode_changeset<F>(changeset: &[u8], mut column_names: F) -> Sqlite3xResult<Vec<TableChanges>>
+where
+    F: FnMut(&str, usize) -> Vec<String>,
+{
+    let mut tables: Vec<TableChanges> = Vec::new();
+
+    if changeset.is_empty() {
+        return Ok(tables);
+    }
+
+    let iter = ChangesetCursor::start(changeset)?;
+
+    while iter.next()? {
+        let (table, column_count, op) = iter.op()?;
+        let pk_flags = iter.pk()?;
+        let columns = column_names(&table, column_count);
+
+        let operation = match op {
+            ffi::SQLITE_INSERT => ChangeOperation::Insert,
+            ffi::SQLITE_UPDATE => ChangeOperation::Update,
+            ffi::SQLITE_DELETE => ChangeOperation::Delete,
+            other => {
+                return Err(Sqlite3xError::Session(format!("Unknown changeset operation: {}", other)))
+            }
+        };
+
+        let mut change = RowChange {
+            operation,
+            primary_key: serde_json::Map::new(),
+            old_values: serde_json::Map::new(),
+            new_values: serde_json::Map::new(),
+        };
+
+        for (i, column) in columns.iter().enumerate().take(column_count) {
+            let old = if operation == ChangeOperation::Insert { None } else { iter.old_value(i)? };
+            let new = if operation == ChangeOperation::Delete { None } else { iter.new_value(i)? };
+
+            if pk_flags.get(i).copied().unwrap_or(false) {
+                if let Some(v) = old.as_ref().or(new.as_ref()) {
+                    change.primary_key.insert(column.clone(), v.clone());
+                }
+            }
+            if let Some(v) = old {
+                change.old_values.insert(column.clone(), v);
+            }
+            if let Some(v) = new {
+                change.new_values.insert(column.clone(), v);
+            }
+        }
+
+        let entry = match tables.iter().position(|t| t.table == table) {
+            Some(pos) => &mut tables[pos],
+            None => {
+                tables.push(TableChanges {
+                    table: table.clone(),
+                    inserted: Vec::new(),
+                    updated: Vec::new(),
+                    deleted: Vec::new(),
+                });
+                tables.last_mut().expect("just pushed")
+            }
+        };
+
+        match operation {
+            ChangeOperation::Insert => entry.inserted.push(change),
+            ChangeOperation::Update => entry.updated.push(change),
+            ChangeOperation::Delete => entry.deleted.push(change),
+        }
+    }
+
+    Ok(tables)
+}
+
+/// Thin cursor over `sqlite3_changeset_iter`
+///
+/// Unlike `rusqlite::session::ChangesetIter` this reports values that are
+/// absent from an UPDATE record (unchanged columns) as `None`.
+struct ChangesetCursor<'a> {
+    it: *mut ffi::sqlite3_changeset_iter,
+    _data: std::marker::PhantomData<&'a [u8]>,
+}
+
+impl<'a> ChangesetCursor<'a> {
+    fn start(changeset: &'a [u8]) -> Sqlite3xResult<Self> {
+        let mut it = ptr::null_mut();
+        unsafe {
+            check(
+                ffi::sqlite3changeset_start(
+                    &mut it,
+                    changeset.len() as c_int,
+                    changeset.as_ptr() as *mut c_void,
+                ),
+                "start changeset",
+            )?;
+        }
+        Ok(Self { it, _data: std::marker::PhantomData })
+    }
+
+    fn next(&self) -> Sqlite3xResult<bool> {
+        match unsafe { ffi::sqlite3changeset_next(self.it) } {
+            ffi::SQLITE_ROW => Ok(true),
+            ffi::SQLITE_DONE => Ok(false),
+            rc => Err(Sqlite3xError::Session(format!("Failed to read changeset: code {}", rc))),
+        }
+    }
+
+    fn op(&self) -> Sqlite3xResult<(String, usize, c_int)> {
+        let mut table: *const c_char = ptr::null();
+        let mut column_count: c_int = 0;
+        let mut op: c_int = 0;
+        let mut indirect: c_int = 0;
+
+        unsafe {
+            check(
+                ffi::sqlite3changeset_op(self.it, &mut table, &mut column_count, &mut op, &mut indirect),
+                "read changeset operation",
+            )?;
+            let table = CStr::from_ptr(table).to_string_lossy().into_owned();
+            Ok((table, column_count as usize, op))
+        }
+    }
+
+    fn pk(&self) -> Sqlite3xResult<Vec<bool>> {
+        let mut flags: *mut u8 = ptr::null_mut();
+        let mut column_count: c_int = 0;
+
+        unsafe {
+            check(ffi::sqlite3changeset_pk(self.it, &mut flags, &mut column_count), "read primary key")?;
+            let flags = std::slice::from_raw_parts(flags, column_count as usize);
+            Ok(flags.iter().map(|f| *f != 0).collect())
+        }
+    }
+
+    fn old_value(&self, column: usize) -> Sqlite3xResult<Option<serde_json::Value>> {
+        let mut value = ptr::null_mut();
+        unsafe {
+            check(ffi::sqlite3changeset_old(self.it, column as c_int, &mut value), "read old value")?;
+            Ok(sqlite_value_to_json(value))
+        }
+    }
+
+    fn new_value(&self, column: usize) -> Sqlite3xResult<Option<serde_json::Value>> {
+        let mut value = ptr::null_mut();
+        unsafe {
+            check(ffi::sqlite3changeset_new(self.it, column as c_int, &mut value), "read new value")?;
+            Ok(sqlite_value_to_json(value))
+        }
+    }
+}
+
+impl Drop for ChangesetCursor<'_> {
+    fn drop(&mut self) {
+        unsafe { ffi::sqlite3changeset_finalize(self.it) };
+    }
+}
+
+/// Convert a protected `sqlite3_value` to JSON, `None` for an absent value
+unsafe fn sqlite_value_to_json(value: *mut ffi::sqlite3_value) -> Option<serde_json::Value> {
+    if value.is_null() {
+        return None;
+    }
+
+    let json = match ffi::sqlite3_value_type(value) {
+        ffi::SQLITE_INTEGER => serde_json::Value::Number(ffi::sqlite3_value_int64(value).into()),
+        ffi::SQLITE_FLOAT => serde_json::Number::from_f64(ffi::sqlite3_value_double(value))
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        ffi::SQLITE_TEXT => {
+            let text = ffi::sqlite3_value_text(value);
+            let len = ffi::sqlite3_value_bytes(value) as usize;
+            if text.is_null() {
+                serde_json::Value::String(String::new())
+            } else {
+                let bytes = std::slice::from_raw_parts(text, len);
+                serde_json::Value::String(String::from_utf8_lossy(bytes).to_string())
+            }
+        }
+        ffi::SQLITE_BLOB => {
+            let len = ffi::sqlite3_value_bytes(value);
+            serde_json::Value::String(format!("<BLOB {} bytes>", len))
+        }
+        _ => serde_json::Value::Null,
+    };
+
+    Some(json)
+}
+
+fn check(rc: c_int, context: &str) -> Sqlite3xResult<()> {
+    if rc == ffi::SQLITE_OK {
+        Ok(())
+    } else {
+        Err(Sqlite3xError::Session(format!("Failed to {}: code {}", context, rc)))
+    }
+}
@@ -0,0 +1,673 @@
+//! sqlite3x Table Definitions
+//!
+//! A structured model of a CREATE TABLE statement. Definitions come from the
+//! frontend or from a loaded schema, and generate correctly quoted DDL.
+
+use super::ddl::{self, Token, TokenKind};
+use super::errors::{Sqlite3xError, Sqlite3xResult};
+use super::wrapper::{GeneratedKind, TableInfo, TableKind};
+use crate::utils::quote_identifier;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Column types a STRICT table accepts
+const STRICT_TYPES: &[&str] = &["INT", "INTEGER", "REAL", "TEXT", "BLOB", "ANY"];
+
+/// A table as CREATE TABLE describes it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableDefinition {
+    pub name: String,
+    pub columns: Vec<ColumnDefinition>,
+    #[serde(default)]
+    pub constraints: Vec<TableConstraint>,
+    #[serde(default)]
+    pub strict: bool,
+    #[serde(default)]
+    pub without_rowid: bool,
+}
+
+/// A column and its column constraints
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnDefinition {
+    pub name: String,
+    /// Declared type as written, e.g. `VARCHAR(20)`; may be empty unless the table is STRICT
+    #[serde(default)]
+    pub data_type: String,
+    #[serde(default)]
+    pub primary_key: bool,
+    #[serde(default)]
+    pub autoincrement: bool,
+    #[serde(default)]
+    pub not_null: bool,
+    #[serde(default)]
+    pub unique: bool,
+    /// Literal or expression, as `PRAGMA table_info` reports it
+    #[serde(default)]
+    pub default_value: Option<String>,
+    #[serde(default)]
+    pub collation: Option<String>,
+    #[serde(default)]
+    pub check: Option<String>,
+    #[serde(default)]
+    pub generated: Option<GeneratedColumn>,
+}
+
+/// Expression and storage of a generated column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedColumn {
+    pub expression: String,
+    pub kind: GeneratedKind,
+}
+
+/// A table constraint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TableConstraint {
+    #[serde(rename_all = "camelCase")]
+    PrimaryKey {
+        #[serde(default)]
+        name: Option<String>,
+        columns: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Unique {
+        #[serde(default)]
+        name: Option<String>,
+        columns: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Check {
+        #[serde(default)]
+        name: Option<String>,
+        expression: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ForeignKey {
+        #[serde(default)]
+        name: Option<String>,
+        columns: Vec<String>,
+        referenced_table: String,
+        /// Empty to reference the parent's primary key
+        #[serde(default)]
+        referenced_columns: Vec<String>,
+        #[serde(default)]
+        on_update: ForeignKeyAction,
+        #[serde(default)]
+        on_delete: ForeignKeyAction,
+        #[serde(default)]
+        deferrable: bool,
+        #[serde(default)]
+        initially_deferred: bool,
+    },
+}
+
+/// ON UPDATE / ON DELETE action of a foreign key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForeignKeyAction {
+    #[default]
+    #[serde(rename = "NO ACTION")]
+    NoAction,
+    #[serde(rename = "RESTRICT")]
+    Restrict,
+    #[serde(rename = "SET NULL")]
+    SetNull,
+    #[serde(rename = "SET DEFAULT")]
+    SetDefault,
+    #[serde(rename = "CASCADE")]
+    Cascade,
+}
+
+impl ForeignKeyAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ForeignKeyAction::NoAction => "NO ACTION",
+            ForeignKeyAction::Restrict => "RESTRICT",
+            ForeignKeyAction::SetNull => "SET NULL",
+            ForeignKeyAction::SetDefault => "SET DEFAULT",
+            ForeignKeyAction::Cascade => "CASCADE",
+        }
+    }
+
+    /// Parse an action as `PRAGMA foreign_key_list` reports it
+    pub fn from_pragma(action: &str) -> Self {
+        match action.to_uppercase().as_str() {
+            "RESTRICT" => ForeignKeyAction::Restrict,
+            "SET NULL" => ForeignKeyAction::SetNull,
+            "SET DEFAULT" => ForeignKeyAction::SetDefault,
+            "CASCADE" => ForeignKeyAction::Cascade,
+            _ => ForeignKeyAction::NoAction,
+        }
+    }
+}
+
+impl TableDefinition {
+    /// Check the definition for mistakes SQLite would reject, with clearer messages
+    pub fn validate(&self) -> Sqlite3xResult<()> {
+        let invalid = |message: String| Err(Sqlite3xError::Schema(message));
+
+        if self.name.trim().is_empty() {
+            return invalid("Table name is empty".to_string());
+        }
+        if self.columns.is_empty() {
+            return invalid("A table needs at least one column".to_string());
+        }
+
+        let mut names = HashSet::new();
+        for column in &self.columns {
+            if column.name.trim().is_empty() {
+                return invalid("Column name is empty".to_string());
+            }
+            if !names.insert(column.name.to_lowercase()) {
+                return invalid(format!("Duplicate column: {}", column.name));
+            }
+            if self.strict && !STRICT_TYPES.iter().any(|t| t.eq_ignore_ascii_case(column.data_type.trim())) {
+                return invalid(format!(
+                    "Column {} of a STRICT table must be one of {}",
+                    column.name,
+                    STRICT_TYPES.join(", ")
+                ));
+            }
+            if column.autoincrement
+                && (!column.primary_key || !column.data_type.trim().eq_ignore_ascii_case("INTEGER") || self.without_rowid)
+            {
+                return invalid(format!(
+                    "AUTOINCREMENT on {} needs an INTEGER PRIMARY KEY in a rowid table",
+                    column.name
+                ));
+            }
+            if column.generated.is_some() && (column.primary_key || column.default_value.is_some()) {
+                return invalid(format!(
+                    "Generated column {} cannot be part of the primary key or have a default",
+                    column.name
+                ));
+            }
+        }
+
+        let has_column = |name: &String| names.contains(&name.to_lowercase());
+        let mut primary_keys = self.columns.iter().filter(|c| c.primary_key).count();
+
+        for constraint in &self.constraints {
+            let columns = match constraint {
+                TableConstraint::PrimaryKey { columns, .. } => {
+                    primary_keys += 1;
+                    columns
+                }
+                TableConstraint::Unique { columns, .. } => columns,
+                TableConstraint::Check { expression, .. } => {
+                    if expression.trim().is_empty() {
+                        return invalid("CHECK expression is empty".to_string());
+                    }
+                    continue;
+                }
+                TableConstraint::ForeignKey { columns, referenced_table, referenced_columns, .. } => {
+                    if referenced_table.trim().is_empty() {
+                        return invalid("Foreign key has no referenced table".to_string());
+                    }
+                    if !referenced_columns.is_empty() && referenced_columns.len() != columns.len() {
+                        return invalid(format!(
+                            "Foreign key to {} has {} columns but references {}",
+                            referenced_table,
+                            columns.len(),
+                            referenced_columns.len()
+                        ));
+                    }
+                    columns
+                }
+            };
+
+            if columns.is_empty() {
+                return invalid("Constraint has no columns".to_string());
+            }
+            if let Some(unknown) = columns.iter().find(|c| !has_column(c)) {
+                return invalid(format!("Constraint refers to unknown column: {}", unknown));
+            }
+        }
+
+        if primary_keys > 1 {
+            return invalid("A table can have only one primary key".to_string());
+        }
+        if self.without_rowid && primary_keys == 0 {
+            return invalid("A WITHOUT ROWID table needs a primary key".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// The CREATE TABLE statement for this definition
+    pub fn to_sql(&self) -> Sqlite3xResult<String> {
+        self.validate()?;
+
+        let mut elements: Vec<String> = self.columns.iter().map(column_sql).collect();
+        elements.extend(self.constraints.iter().map(constraint_sql));
+
+        let mut sql = format!(
+            "CREATE TABLE {} (\n    {}\n)",
+            quote_identifier(&self.name),
+            elements.join(",\n    ")
+        );
+
+        let options: Vec<&str> = [(self.strict, "STRICT"), (self.without_rowid, "WITHOUT ROWID")]
+            .iter()
+            .filter(|(on, _)| *on)
+            .map(|(_, option)| *option)
+            .collect();
+        if !options.is_empty() {
+            sql.push(' ');
+            sql.push_str(&options.join(", "));
+        }
+
+        Ok(sql)
+    }
+
+    /// Definition of a table in a loaded schema
+    ///
+    /// Column details come from the pragmas; collations, CHECK constraints,
+    /// generated expressions and constraint names are read from the DDL.
+    pub fn from_schema(table: &TableInfo) -> Sqlite3xResult<Self> {
+        if table.kind != TableKind::Table {
+            return Err(Sqlite3xError::Schema(format!("{} is not an ordinary table", table.name)));
+        }
+
+        let mut columns: Vec<ColumnDefinition> = table.columns.iter()
+            .map(|c| ColumnDefinition {
+                name: c.name.clone(),
+                data_type: c.data_type.clone(),
+                primary_key: false,
+                autoincrement: false,
+                not_null: c.not_null,
+                unique: false,
+                default_value: c.default_value.clone(),
+                collation: None,
+                check: None,
+                generated: None,
+            })
+            .collect();
+        let mut constraints = Vec::new();
+
+        for element in table.sql.as_deref().map(ddl::table_elements).unwrap_or_default() {
+            let tokens = ddl::tokenize(&element);
+            let Some(first) = tokens.first() else { continue };
+
+            if is_table_constraint(first) {
+                if let Some(constraint) = parse_table_constraint(&element, &tokens) {
+                    constraints.push(constraint);
+                }
+                continue;
+            }
+
+            let Some(name) = first.identifier() else { continue };
+            if let Some(column) = columns.iter_mut().find(|c| c.name.eq_ignore_ascii_case(&name)) {
+                apply_column_constraints(column, &element, &tokens, table);
+            }
+        }
+
+        // Primary keys are known from the pragmas even when the DDL is unusual
+        let declared_key = columns.iter().any(|c| c.primary_key)
+            || constraints.iter().any(|c| matches!(c, TableConstraint::PrimaryKey { .. }));
+        let key_columns: Vec<&str> = table.columns.iter()
+            .filter(|c| c.primary_key)
+            .map(|c| c.name.as_str())
+            .collect();
+        if !declared_key && !key_columns.is_empty() {
+            constraints.insert(0, TableConstraint::PrimaryKey {
+                name: None,
+                columns: key_columns.iter().map(|c| c.to_string()).collect(),
+            });
+        }
+
+        for fk in &table.foreign_keys {
+            constraints.push(TableConstraint::ForeignKey {
+                name: None,
+                columns: fk.columns.iter().map(|c| c.from.clone()).collect(),
+                referenced_table: fk.referenced_table.clone(),
+                referenced_columns: fk.columns.iter().map(|c| c.to.clone()).collect(),
+                on_update: ForeignKeyAction::from_pragma(&fk.on_update),
+                on_delete: ForeignKeyAction::from_pragma(&fk.on_delete),
+                deferrable: fk.deferrable,
+                initially_deferred: fk.initially_deferred,
+            });
+        }
+
+        Ok(TableDefinition {
+            name: table.name.clone(),
+            columns,
+            constraints,
+            strict: table.strict,
+            without_rowid: table.without_rowid,
+        })
+    }
+
+    /// CHECK expressions of columns and of the table
+    pub fn checks(&self) -> impl Iterator<Item = &str> {
+        self.columns.iter()
+            .filter_map(|c| c.check.as_deref())
+            .chain(self.constraints.iter().filter_map(|c| match c {
+                TableConstraint::Check { expression, .. } => Some(expression.as_str()),
+                _ => None,
+            }))
+    }
+}
+
+/// A column definition as it appears in CREATE TABLE or ALTER TABLE ADD COLUMN
+pub fn column_sql(column: &ColumnDefinition) -> String {
+    let mut sql = quote_identifier(&column.name);
+
+    if !column.data_type.trim().is_empty() {
+        sql.push(' ');
+        sql.push_str(column.data_type.trim());
+    }
+    if column.primary_key {
+        sql.push_str(" PRIMARY KEY");
+        if column.autoincrement {
+            sql.push_str(" AUTOINCREMENT");
+        }
+    }
+    if column.not_null {
+        sql.push_str(" NOT NULL");
+    }
+    if column.unique {
+        sql.push_str(" UNIQUE");
+    }
+    if let Some(default) = &column.default_value {
+        sql.push_str(" DEFAULT ");
+        sql.push_str(&default_sql(default));
+    }
+    if let Some(collation) = &column.collation {
+        sql.push_str(" COLLATE ");
+        sql.push_str(&quote_identifier(collation));
+    }
+    if let Some(check) = &column.check {
+        sql.push_str(&format!(" CHECK ({})", check));
+    }
+    if let Some(generated) = &column.generated {
+        let storage = match generated.kind {
+            GeneratedKind::Virtual => "VIRTUAL",
+            GeneratedKind::Stored => "STORED",
+        };
+        sql.push_str(&format!(" GENERATED ALWAYS AS ({}) {}", generated.expression, storage));
+    }
+
+    sql
+}
+
+/// A table constraint as it appears in CREATE TABLE
+pub fn constraint_sql(constraint: &TableConstraint) -> String {
+    let quote_list = |columns: &[String]| {
+        columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", ")
+    };
+    let named = |name: &Option<String>, body: String| match name {
+        Some(name) => format!("CONSTRAINT {} {}", quote_identifier(name), body),
+        None => body,
+    };
+
+    match constraint {
+        TableConstraint::PrimaryKey { name, columns } => {
+            named(name, format!("PRIMARY KEY ({})", quote_list(columns)))
+        }
+        TableConstraint::Unique { name, columns } => named(name, format!("UNIQUE ({})", quote_list(columns))),
+        TableConstraint::Check { name, expression } => named(name, format!("CHECK ({})", expression)),
+        TableConstraint::ForeignKey {
+            name,
+            columns,
+            referenced_table,
+            referenced_columns,
+            on_update,
+            on_delete,
+            deferrable,
+            initially_deferred,
+        } => {
+            let mut sql = format!("FOREIGN KEY ({}) REFERENCES {}", quote_list(columns), quote_identifier(referenced_table));
+            if !referenced_columns.is_empty() {
+                sql.push_str(&format!(" ({})", quote_list(referenced_columns)));
+            }
+            if *on_update != ForeignKeyAction::NoAction {
+                sql.push_str(&format!(" ON UPDATE {}", on_update.as_str()));
+            }
+            if *on_delete != ForeignKeyAction::NoAction {
+                sql.push_str(&format!(" ON DELETE {}", on_delete.as_str()));
+            }
+            if *deferrable {
+                sql.push_str(if *initially_deferred {
+                    " DEFERRABLE INITIALLY DEFERRED"
+                } else {
+                    " DEFERRABLE INITIALLY IMMEDIATE"
+                });
+            }
+            named(name, sql)
+        }
+    }
+}
+
+/// A default as it must be written after DEFAULT
+///
+/// Literals stand alone; anything else is an expression and needs parentheses.
+fn default_sql(default: &str) -> String {
+    let tokens = ddl::tokenize(default);
+    let literal = match tokens.as_slice() {
+        [t] => matches!(t.kind, TokenKind::Number | TokenKind::String)
+            || ["NULL", "TRUE", "FALSE", "CURRENT_TIME", "CURRENT_DATE", "CURRENT_TIMESTAMP"]
+                .iter()
+                .any(|k| t.is_keyword(k)),
+        // Signed number or blob literal
+        [first, second] => {
+            ((first.is_punct('-') || first.is_punct('+')) && second.kind == TokenKind::Number)
+                || (first.is_keyword("X") && second.kind == TokenKind::String && first.end() == second.start)
+        }
+        _ => false,
+    };
+
+    if literal {
+        default.trim().to_string()
+    } else {
+        format!("({})", default.trim())
+    }
+}
+
+fn is_table_constraint(first: &Token<'_>) -> bool {
+    ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"]
+        .iter()
+        .any(|k| first.is_keyword(k))
+}
+
+/// Source text inside the parentheses opening at `open`, and the index of the closing one
+fn paren_text(element: &str, tokens: &[Token<'_>], open: usize) -> Option<(String, usize)> {
+    if !tokens.get(open)?.is_punct('(') {
+        return None;
+    }
+    let close = ddl::closing_paren(tokens, open)?;
+    Some((element[tokens[open].end()..tokens[close].start].trim().to_string(), close))
+}
+
+/// First identifier of each comma-separated term inside the parentheses at `open`
+fn paren_columns(tokens: &[Token<'_>], open: usize) -> Vec<String> {
+    let Some(close) = ddl::closing_paren(tokens, open) else {
+        return Vec::new();
+    };
+
+    let mut columns = Vec::new();
+    let mut expect_name = true;
+    let mut depth = 0;
+    for token in &tokens[open + 1..close] {
+        if token.is_punct('(') {
+            depth += 1;
+        } else if token.is_punct(')') {
+            depth -= 1;
+        } else if depth == 0 && token.is_punct(',') {
+            expect_name = true;
+        } else if expect_name {
+            if let Some(name) = token.identifier() {
+                columns.push(name);
+            }
+            expect_name = false;
+        }
+    }
+    columns
+}
+
+fn parse_table_constraint(element: &str, tokens: &[Token<'_>]) -> Option<TableConstraint> {
+    let (name, rest) = if tokens[0].is_keyword("CONSTRAINT") {
+        (tokens.get(1)?.identifier(), 2)
+    } else {
+        (None, 0)
+    };
+    let keyword = tokens.get(rest)?;
+
+    if keyword.is_keyword("PRIMARY") {
+        Some(TableConstraint::PrimaryKey { name, columns: paren_columns(tokens, rest + 2) })
+    } else if keyword.is_keyword("UNIQUE") {
+        Some(TableConstraint::Unique { name, columns: paren_columns(tokens, rest + 1) })
+    } else if keyword.is_keyword("CHECK") {
+        let (expression, _) = paren_text(element, tokens, rest + 1)?;
+        Some(TableConstraint::Check { name, expression })
+    } else {
+        // Foreign keys come from the pragmas
+        None
+    }
+}
+
+/// Fill in what only the DDL of a column definition says
+fn apply_column_constraints(column: &mut ColumnDefinition, element: &str, tokens: &[Token<'_>], table: &TableInfo) {
+    let mut i = 1;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        if token.is_punct('(') {
+            // Type arguments, e.g. VARCHAR(20)
+            i = ddl::closing_paren(tokens, i).unwrap_or(tokens.len());
+        } else if token.is_keyword("PRIMARY") {
+            column.primary_key = true;
+        } else if token.is_keyword("AUTOINCREMENT") {
+            column.autoincrement = true;
+        } else if token.is_keyword("UNIQUE") {
+            column.unique = true;
+        } else if token.is_keyword("COLLATE") {
+            column.collation = tokens.get(i + 1).and_then(|t| t.identifier());
+            i += 1;
+        } else if token.is_keyword("CHECK") {
+            if let Some((expression, close)) = paren_text(element, tokens, i + 1) {
+                column.check = Some(expression);
+                i = close;
+            }
+        } else if token.is_keyword("AS") {
+            if let Some((expression, close)) = paren_text(element, tokens, i + 1) {
+                let info = table.columns.iter().find(|c| c.name == column.name);
+                let kind = info.and_then(|c| c.generated).unwrap_or(GeneratedKind::Virtual);
+                column.generated = Some(GeneratedColumn { expression, kind });
+                i = close;
+            }
+        } else if (token.is_keyword("DEFAULT") && !tokens[i - 1].is_keyword("SET")) || token.is_keyword("REFERENCES") {
+            // Defaults and references come from the pragmas; skip their arguments
+            if tokens.get(i + 1).is_some_and(|t| t.is_punct('(')) {
+                i = ddl::closing_paren(tokens, i + 1).unwrap_or(tokens.len());
+            } else if tokens.get(i + 2).is_some_and(|t| t.is_punct('(')) {
+                i = ddl::closing_paren(tokens, i + 2).unwrap_or(tokens.len());
+            }
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlite3x::wrapper::Database;
+
+    #[test]
+    fn test_table_definition_round_trip() {
+        let definition = TableDefinition {
+            name: "order items".to_string(),
+            columns: vec![
+                ColumnDefinition {
+                    name: "id".to_string(),
+                    data_type: "INTEGER".to_string(),
+                    primary_key: true,
+                    autoincrement: true,
+                    ..column("id")
+                },
+                ColumnDefinition {
+                    data_type: "TEXT".to_string(),
+                    not_null: true,
+                    collation: Some("NOCASE".to_string()),
+                    default_value: Some("lower('X')".to_string()),
+                    ..column("sku")
+                },
+                ColumnDefinition {
+                    data_type: "INTEGER".to_string(),
+                    check: Some("qty > 0".to_string()),
+                    default_value: Some("1".to_string()),
+                    ..column("qty")
+                },
+                ColumnDefinition {
+                    data_type: "INTEGER".to_string(),
+                    generated: Some(GeneratedColumn {
+                        expression: "qty * 2".to_string(),
+                        kind: GeneratedKind::Stored,
+                    }),
+                    ..column("double")
+                },
+                ColumnDefinition { data_type: "INTEGER".to_string(), ..column("parent") },
+            ],
+            constraints: vec![
+                TableConstraint::Unique { name: Some("uq_sku".to_string()), columns: vec!["sku".to_string()] },
+                TableConstraint::ForeignKey {
+                    name: None,
+                    columns: vec!["parent".to_string()],
+                    referenced_table: "order items".to_string(),
+                    referenced_columns: vec!["id".to_string()],
+                    on_update: ForeignKeyAction::NoAction,
+                    on_delete: ForeignKeyAction::Cascade,
+                    deferrable: false,
+                    initially_deferred: false,
+                },
+            ],
+            strict: true,
+            without_rowid: false,
+        };
+
+        let db = Database::open(":memory:").unwrap();
+        db.execute(&definition.to_sql().unwrap()).expect("generated DDL should run");
+
+        let schema = db.get_schema().unwrap();
+        let table = schema.tables.iter().find(|t| t.name == "order items").unwrap();
+        let loaded = TableDefinition::from_schema(table).unwrap();
+        assert_eq!(loaded.columns, definition.columns);
+        assert_eq!(loaded.constraints, definition.constraints);
+        assert!(loaded.strict);
+    }
+
+    #[test]
+    fn test_table_definition_validation() {
+        let mut definition = TableDefinition {
+            name: "t".to_string(),
+            columns: vec![column("a"), column("A")],
+            constraints: Vec::new(),
+            strict: false,
+            without_rowid: false,
+        };
+        assert!(definition.validate().is_err());
+
+        definition.columns = vec![column("a")];
+        definition.without_rowid = true;
+        assert!(definition.validate().is_err());
+
+        definition.constraints = vec![TableConstraint::PrimaryKey { name: None, columns: vec!["a".to_string()] }];
+        assert!(definition.validate().is_ok());
+    }
+
+    fn column(name: &str) -> ColumnDefinition {
+        ColumnDefinition {
+            name: name.to_string(),
+            data_type: String::new(),
+            primary_key: false,
+            autoincrement: false,
+            not_null: false,
+            unique: false,
+            default_value: None,
+            collation: None,
+            check: None,
+            generated: None,
+        }
+    }
+}
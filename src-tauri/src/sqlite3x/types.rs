@@ -112,3 +112,68 @@ impl Default for OpenFlags {
         }
     }
 }
+
+/// A typed cell value exchanged with the frontend
+///
+/// Serialized as `{ "type": "integer", "value": 42 }`; blobs carry their bytes
+/// as an array of numbers.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum CellValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl CellValue {
+    /// The value as an SQL literal, e.g. for a generated script
+    pub fn to_sql_literal(&self) -> String {
+        match self {
+            CellValue::Null => "NULL".to_string(),
+            CellValue::Integer(i) => i.to_string(),
+            // Debug keeps a decimal point or exponent, so the value stays a REAL
+            CellValue::Real(f) if f.is_finite() => format!("{:?}", f),
+            CellValue::Real(f) if *f > 0.0 => "9e999".to_string(),
+            CellValue::Real(f) if *f < 0.0 => "-9e999".to_string(),
+            CellValue::Real(_) => "NULL".to_string(),
+            CellValue::Text(s) => format!("'{}'", s.replace('\'', "''")),
+            CellValue::Blob(b) => format!("X'{}'", b.iter().map(|byte| format!("{:02X}", byte)).collect::<String>()),
+        }
+    }
+}
+
+impl From<CellValue> for rusqlite::types::Value {
+    fn from(value: CellValue) -> Self {
+        match value {
+            CellValue::Null => rusqlite::types::Value::Null,
+            CellValue::Integer(i) => rusqlite::types::Value::Integer(i),
+            CellValue::Real(f) => rusqlite::types::Value::Real(f),
+            CellValue::Text(s) => rusqlite::types::Value::Text(s),
+            CellValue::Blob(b) => rusqlite::types::Value::Blob(b),
+        }
+    }
+}
+
+impl From<rusqlite::types::ValueRef<'_>> for CellValue {
+    fn from(value: rusqlite::types::ValueRef<'_>) -> Self {
+        match value {
+            rusqlite::types::ValueRef::Null => CellValue::Null,
+            rusqlite::types::ValueRef::Integer(i) => CellValue::Integer(i),
+            rusqlite::types::ValueRef::Real(f) => CellValue::Real(f),
+            rusqlite::types::ValueRef::Text(s) => CellValue::Text(String::from_utf8_lossy(s).to_string()),
+            rusqlite::types::ValueRef::Blob(b) => CellValue::Blob(b.to_vec()),
+        }
+    }
+}
+
+/// How rows of a table are addressed
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowKey {
+    /// Key columns; `rowid` for rowid tables without a declared primary key
+    pub columns: Vec<String>,
+    /// Whether the table has a rowid (false for WITHOUT ROWID tables)
+    pub has_rowid: bool,
+}
@@ -3,39 +3,74 @@
 //! High-level Rust wrapper using rusqlite for SQLite operations.
 
 use super::errors::{Sqlite3xError, Sqlite3xResult};
+use super::session::{ChangeSession, TableChanges};
+use super::ddl::TriggerDefinition;
+use super::types::{CellValue, OpenFlags, RowKey};
 use parking_lot::RwLock;
 use rusqlite::hooks::Action;
 use rusqlite::Connection;
 use std::sync::{Arc, Mutex};
 
+/// Virtual machine steps between checks of a limited session's memory use
+const SESSION_LIMIT_CHECK_OPS: i32 = 1000;
+
 /// Safe wrapper around a SQLite database connection
 pub struct Database {
+    /// Change sessions by name; declared first so they are dropped before the connection
+    sessions: Mutex<std::collections::HashMap<String, ChangeSession>>,
     connection: Mutex<Connection>,
     path: String,
     registered_udfs: Mutex<std::collections::HashSet<String>>,
     partition_manager: RwLock<Option<Arc<super::partition::PartitionManager>>>,
+    /// Last loaded schema and the `schema_version` it was loaded at
+    schema_cache: Mutex<Option<(i64, SchemaInfo)>>,
 }
 
 impl Database {
     /// Open a database connection (creates file if it doesn't exist)
     pub fn open(path: &str) -> Sqlite3xResult<Self> {
+        Self::open_with_flags(path, OpenFlags::default())
+    }
+
+    /// Open a database connection with the given flags
+    ///
+    /// A read-only connection leaves the file as it is: it is neither created
+    /// nor switched to WAL mode.
+    pub fn open_with_flags(path: &str, flags: OpenFlags) -> Sqlite3xResult<Self> {
         log::info!("Opening database at: {}", path);
 
-        let connection = Connection::open(path)
+        let mut open_flags = rusqlite::OpenFlags::SQLITE_OPEN_URI | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        if flags.read_only {
+            open_flags |= rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY;
+        } else {
+            open_flags |= rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE;
+            if flags.create {
+                open_flags |= rusqlite::OpenFlags::SQLITE_OPEN_CREATE;
+            }
+        }
+
+        let connection = Connection::open_with_flags(path, open_flags)
             .map_err(|e| Sqlite3xError::Connection(format!("Failed to open database: {}", e)))?;
 
         // Enable WAL mode for better concurrency
+        let pragmas = if flags.wal_mode && !flags.read_only {
+            "PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;"
+        } else {
+            "PRAGMA foreign_keys=ON;"
+        };
         connection
-            .execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")
+            .execute_batch(pragmas)
             .map_err(|e| Sqlite3xError::Connection(format!("Failed to set pragmas: {}", e)))?;
 
         log::info!("Database opened successfully: {}", path);
 
         Ok(Self {
+            sessions: Mutex::new(std::collections::HashMap::new()),
             connection: Mutex::new(connection),
             path: path.to_string(),
             registered_udfs: Mutex::new(std::collections::HashSet::new()),
             partition_manager: RwLock::new(None), // Initialize partition_manager to None
+            schema_cache: Mutex::new(None),
         })
     }
 
@@ -145,6 +180,65 @@ impl Database {
         Ok(affected)
     }
 
+    /// Execute a SQL statement with typed parameters
+    pub fn execute_values(&self, sql: &str, params: &[CellValue]) -> Sqlite3xResult<usize> {
+        log::debug!("Executing SQL with values: {}", sql);
+
+        let conn = self
+            .connection
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+
+        let values: Vec<rusqlite::types::Value> = params.iter().cloned().map(Into::into).collect();
+
+        conn.execute(sql, rusqlite::params_from_iter(values))
+            .map_err(|e| match e {
+                rusqlite::Error::SqliteFailure(ref failure, _)
+                    if failure.code == rusqlite::ErrorCode::ConstraintViolation =>
+                {
+                    Sqlite3xError::Constraint(e.to_string())
+                }
+                e => Sqlite3xError::Query(format!("Execute error: {}", e)),
+            })
+    }
+
+    /// Execute a query with typed parameters and return typed rows
+    pub fn query_values(&self, sql: &str, params: &[CellValue]) -> Sqlite3xResult<Vec<Vec<CellValue>>> {
+        log::debug!("Querying with values: {}", sql);
+
+        let conn = self
+            .connection
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+
+        let mut stmt = conn
+            .prepare(sql)
+            .map_err(|e| Sqlite3xError::Query(format!("Prepare error: {}", e)))?;
+        let column_count = stmt.column_count();
+
+        let values: Vec<rusqlite::types::Value> = params.iter().cloned().map(Into::into).collect();
+        let mut rows = stmt
+            .query(rusqlite::params_from_iter(values))
+            .map_err(|e| Sqlite3xError::Query(format!("Query error: {}", e)))?;
+
+        let mut result = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .map_err(|e| Sqlite3xError::Query(format!("Row error: {}", e)))?
+        {
+            let mut row_data = Vec::with_capacity(column_count);
+            for i in 0..column_count {
+                let value = row
+                    .get_ref(i)
+                    .map_err(|e| Sqlite3xError::Query(format!("Column error: {}", e)))?;
+                row_data.push(CellValue::from(value));
+            }
+            result.push(row_data);
+        }
+
+        Ok(result)
+    }
+
     /// Execute a query with parameters and return results
     pub fn query_with_params(
         &self,
@@ -322,173 +416,669 @@ fn json_params_to_sqlite(params: Vec<serde_json::Value>) -> Vec<rusqlite::types:
         .collect()
 }
 
+/// Run a schema query and map every row
+fn schema_rows<T, F>(conn: &Connection, sql: &str, f: F) -> Sqlite3xResult<Vec<T>>
+where
+    F: FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T>,
+{
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| Sqlite3xError::Query(format!("Schema query error: {}", e)))?;
+
+    let rows = stmt
+        .query_map([], f)
+        .map_err(|e| Sqlite3xError::Query(format!("Schema query error: {}", e)))?
+        .collect::<rusqlite::Result<Vec<T>>>()
+        .map_err(|e| Sqlite3xError::Query(e.to_string()))?;
+
+    Ok(rows)
+}
+
+/// Load the whole schema with a handful of set-based queries
+///
+/// Columns, foreign keys and index columns of all tables each come from one
+/// join of `sqlite_master` with the matching pragma table-valued function.
+/// `schema` is "main", "temp" or the name of an attached database.
+fn load_schema(conn: &Connection, schema: &str) -> Sqlite3xResult<SchemaInfo> {
+    use std::collections::HashMap;
+
+    let master = format!("{}.sqlite_master", crate::utils::quote_identifier(schema));
+    let schema_literal = format!("'{}'", schema.replace('\'', "''"));
+
+    let mut tables: Vec<TableInfo> = schema_rows(
+        conn,
+        &format!("SELECT m.name, m.sql, t.type, t.wr, t.strict
+         FROM {master} m
+         JOIN pragma_table_list t ON t.schema = {schema_literal} AND t.name = m.name
+         WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%'
+         ORDER BY m.name"),
+        |row| {
+            let sql: Option<String> = row.get(1)?;
+            let kind = match row.get::<_, String>(2)?.as_str() {
+                "virtual" => TableKind::Virtual,
+                "shadow" => TableKind::Shadow,
+                _ => TableKind::Table,
+            };
+            Ok(TableInfo {
+                name: row.get(0)?,
+                module: match kind {
+                    TableKind::Virtual => sql.as_deref().and_then(super::ddl::virtual_table_module),
+                    _ => None,
+                },
+                sql,
+                kind,
+                strict: row.get::<_, i64>(4)? != 0,
+                without_rowid: row.get::<_, i64>(3)? != 0,
+                shadow_tables: Vec::new(),
+                shadow_of: None,
+                columns: Vec::new(),
+                foreign_keys: Vec::new(),
+                referenced_by: Vec::new(),
+            })
+        },
+    )?;
+
+    // Shadow tables are named after their virtual table
+    let virtual_tables: Vec<String> = tables
+        .iter()
+        .filter(|t| t.kind == TableKind::Virtual)
+        .map(|t| t.name.clone())
+        .collect();
+    for table in tables.iter_mut().filter(|t| t.kind == TableKind::Shadow) {
+        table.shadow_of = virtual_tables
+            .iter()
+            .filter(|v| {
+                table.name.get(..v.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(v))
+                    && table.name.as_bytes().get(v.len()) == Some(&b'_')
+            })
+            .max_by_key(|v| v.len())
+            .cloned();
+    }
+    let shadows: Vec<(String, String)> = tables
+        .iter()
+        .filter_map(|t| t.shadow_of.clone().map(|parent| (parent, t.name.clone())))
+        .collect();
+    for (parent, shadow) in shadows {
+        if let Some(table) = tables.iter_mut().find(|t| t.name == parent) {
+            table.shadow_tables.push(shadow);
+        }
+    }
+
+    type ColumnRow = (String, String, String, bool, Option<String>, i64, i64);
+    let column_row = |row: &rusqlite::Row<'_>| -> rusqlite::Result<ColumnRow> {
+        Ok((
+            row.get(0)?,
+            row.get(1)?,
+            row.get(2)?,
+            row.get::<_, i64>(3)? != 0,
+            row.get(4)?,
+            row.get(5)?,
+            row.get(6)?,
+        ))
+    };
+
+    let mut columns = schema_rows(
+        conn,
+        &format!("SELECT m.name, c.name, c.type, c.\"notnull\", c.dflt_value, c.pk, c.hidden
+         FROM {master} m
+         JOIN pragma_table_list t ON t.schema = {schema_literal} AND t.name = m.name,
+              pragma_table_xinfo(m.name, {schema_literal}) c
+         WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%' AND t.type != 'virtual'
+         ORDER BY m.name, c.cid"),
+        column_row,
+    )?;
+
+    // Virtual tables one at a time: reading one whose module isn't loaded fails
+    for name in &virtual_tables {
+        let rows = conn
+            .prepare("SELECT ?1, name, type, \"notnull\", dflt_value, pk, hidden FROM pragma_table_xinfo(?1, ?2) ORDER BY cid")
+            .and_then(|mut stmt| stmt.query_map([name.as_str(), schema], column_row)?.collect::<rusqlite::Result<Vec<_>>>());
+        match rows {
+            Ok(rows) => columns.extend(rows),
+            Err(e) => log::warn!("Could not read columns of virtual table {}: {}", name, e),
+        }
+    }
+
+    let positions: HashMap<String, usize> = tables
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.name.to_lowercase(), i))
+        .collect();
+
+    // Primary key columns by table, in key order, to resolve implicit references
+    let mut primary_keys: HashMap<String, Vec<(i64, String)>> = HashMap::new();
+
+    for (table, name, data_type, not_null, default_value, pk, hidden) in columns {
+        let Some(&position) = positions.get(&table.to_lowercase()) else {
+            continue;
+        };
+        if pk > 0 {
+            primary_keys.entry(table.to_lowercase()).or_default().push((pk, name.clone()));
+        }
+        tables[position].columns.push(ColumnInfo {
+            name,
+            data_type,
+            not_null,
+            primary_key: pk > 0,
+            default_value,
+            hidden: hidden == 1,
+            generated: match hidden {
+                2 => Some(GeneratedKind::Virtual),
+                3 => Some(GeneratedKind::Stored),
+                _ => None,
+            },
+            foreign_key: None,
+        });
+    }
+    for key in primary_keys.values_mut() {
+        key.sort();
+    }
+
+    let foreign_key_rows = schema_rows(
+        conn,
+        &format!("SELECT m.name, f.id, f.\"table\", f.\"from\", f.\"to\", f.on_update, f.on_delete, f.\"match\"
+         FROM {master} m, pragma_foreign_key_list(m.name, {schema_literal}) f
+         WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%'
+         ORDER BY m.name, f.id, f.seq"),
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(7)?,
+            ))
+        },
+    )?;
+
+    for (table, id, parent, from, to, on_update, on_delete, match_type) in foreign_key_rows {
+        let Some(&position) = positions.get(&table.to_lowercase()) else {
+            continue;
+        };
+        let foreign_keys = &mut tables[position].foreign_keys;
+
+        if foreign_keys.last().map(|fk| fk.id) != Some(id) {
+            foreign_keys.push(ForeignKeyConstraint {
+                id,
+                table: table.clone(),
+                referenced_table: parent.clone(),
+                columns: Vec::new(),
+                on_update,
+                on_delete,
+                match_type,
+                deferrable: false,
+                initially_deferred: false,
+            });
+        }
+
+        let constraint = foreign_keys.last_mut().expect("just pushed");
+        let to = to.unwrap_or_else(|| {
+            primary_keys
+                .get(&parent.to_lowercase())
+                .and_then(|key| key.get(constraint.columns.len()))
+                .map(|(_, name)| name.clone())
+                .unwrap_or_default()
+        });
+        constraint.columns.push(ForeignKeyColumn { from, to });
+    }
+
+    for table in tables.iter_mut() {
+        // Deferral is only in the DDL; the pragma lists constraints in reverse declaration order
+        let clauses = table.sql.as_deref().map(super::ddl::foreign_key_clauses).unwrap_or_default();
+        if clauses.len() == table.foreign_keys.len() {
+            for (constraint, clause) in table.foreign_keys.iter_mut().zip(clauses.iter().rev()) {
+                if constraint.referenced_table.eq_ignore_ascii_case(&clause.referenced_table) {
+                    constraint.deferrable = clause.deferrable;
+                    constraint.initially_deferred = clause.initially_deferred;
+                }
+            }
+        }
+
+        // Columns point at the first constraint they take part in
+        for constraint in &table.foreign_keys {
+            for pair in &constraint.columns {
+                if let Some(column) = table.columns.iter_mut().find(|c| c.name == pair.from) {
+                    column.foreign_key.get_or_insert_with(|| ForeignKeyInfo {
+                        constraint_id: constraint.id,
+                        table: constraint.referenced_table.clone(),
+                        column: pair.to.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    let all_foreign_keys: Vec<ForeignKeyConstraint> = tables
+        .iter()
+        .flat_map(|t| t.foreign_keys.iter().cloned())
+        .collect();
+    for constraint in all_foreign_keys {
+        if let Some(&position) = positions.get(&constraint.referenced_table.to_lowercase()) {
+            tables[position].referenced_by.push(constraint);
+        }
+    }
+
+    let views = schema_rows(
+        conn,
+        &format!("SELECT name, sql FROM {master} WHERE type='view' ORDER BY name"),
+        |row| {
+            Ok(ViewInfo {
+                name: row.get(0)?,
+                sql: row.get(1)?,
+            })
+        },
+    )?;
+
+    // Every index of every table, including those created for constraints
+    let mut indexes = schema_rows(
+        conn,
+        &format!("SELECT il.name, m.name, s.sql, il.\"unique\", il.origin, il.partial
+         FROM {master} m
+         JOIN pragma_index_list(m.name, {schema_literal}) il
+         LEFT JOIN {master} s ON s.type = 'index' AND s.name = il.name
+         WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%'
+         ORDER BY il.name"),
+        |row| {
+            Ok(IndexInfo {
+                name: row.get(0)?,
+                table_name: row.get(1)?,
+                sql: row.get(2)?,
+                unique: row.get::<_, i64>(3)? != 0,
+                origin: match row.get::<_, String>(4)?.as_str() {
+                    "pk" => IndexOrigin::PrimaryKey,
+                    "u" => IndexOrigin::Unique,
+                    _ => IndexOrigin::CreateIndex,
+                },
+                partial: row.get::<_, i64>(5)? != 0,
+                where_clause: None,
+                columns: Vec::new(),
+                key_columns: Vec::new(),
+            })
+        },
+    )?;
+
+    // Key columns of every index, in key order
+    let mut key_columns: HashMap<String, Vec<(i64, IndexColumn)>> = HashMap::new();
+    for (index, cid, column) in schema_rows(
+        conn,
+        &format!("SELECT il.name, x.cid, x.name, x.\"desc\", x.coll
+         FROM {master} m, pragma_index_list(m.name, {schema_literal}) il, pragma_index_xinfo(il.name, {schema_literal}) x
+         WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%' AND x.key = 1
+         ORDER BY il.name, x.seqno"),
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                IndexColumn {
+                    name: row.get(2)?,
+                    expression: None,
+                    descending: row.get::<_, i64>(3)? != 0,
+                    collation: row.get::<_, Option<String>>(4)?.unwrap_or_else(|| "BINARY".to_string()),
+                },
+            ))
+        },
+    )? {
+        key_columns.entry(index).or_default().push((cid, column));
+    }
+
+    for index in indexes.iter_mut() {
+        // Expressions and the partial-index predicate are only in the DDL
+        let definition = index.sql.as_deref().and_then(super::ddl::parse_create_index);
+
+        for (position, (cid, mut column)) in key_columns.remove(&index.name).unwrap_or_default().into_iter().enumerate() {
+            match cid {
+                -2 => column.expression = definition.as_ref().and_then(|d| d.terms.get(position).cloned()),
+                -1 => column.name = Some("rowid".to_string()),
+                _ => {}
+            }
+            index.columns.push(column.name.clone().or_else(|| column.expression.clone()).unwrap_or_default());
+            index.key_columns.push(column);
+        }
+
+        if index.partial {
+            index.where_clause = definition.and_then(|d| d.where_clause);
+        }
+    }
+
+    let triggers = schema_rows(
+        conn,
+        &format!("SELECT name, tbl_name, sql FROM {master} WHERE type='trigger' ORDER BY name"),
+        |row| {
+            let sql: Option<String> = row.get(2)?;
+            Ok(TriggerInfo {
+                name: row.get(0)?,
+                table_name: row.get(1)?,
+                definition: sql.as_deref().and_then(super::ddl::parse_create_trigger),
+                sql,
+            })
+        },
+    )?;
+
+    Ok(SchemaInfo {
+        tables,
+        views,
+        indexes,
+        triggers,
+    })
+}
+
 impl Database {
     /// Get schema information
+    ///
+    /// Served from a cache until `PRAGMA schema_version` changes. Inside a
+    /// transaction the cache is bypassed: a rollback restores the previous
+    /// version number, which later DDL can reach again with another schema.
     pub fn get_schema(&self) -> Sqlite3xResult<SchemaInfo> {
         let conn = self
             .connection
             .lock()
             .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
 
-        // Get tables
-        let mut tables = Vec::new();
-        {
-            let mut stmt = conn.prepare(
-                "SELECT name, sql FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name"
-            ).map_err(|e| Sqlite3xError::Query(format!("Schema query error: {}", e)))?;
-
-            let mut rows = stmt
-                .query([])
-                .map_err(|e| Sqlite3xError::Query(format!("Schema query error: {}", e)))?;
-
-            while let Some(row) = rows
-                .next()
-                .map_err(|e| Sqlite3xError::Query(e.to_string()))?
-            {
-                let name: String = row.get(0).unwrap_or_default();
-                let sql: Option<String> = row.get(1).ok();
-
-                // Get columns for this table
-                let mut columns = Vec::new();
-                {
-                    let pragma_sql = format!("PRAGMA table_info({})", name);
-                    let mut col_stmt = conn
-                        .prepare(&pragma_sql)
-                        .map_err(|e| Sqlite3xError::Query(e.to_string()))?;
-                    let mut col_rows = col_stmt
-                        .query([])
-                        .map_err(|e| Sqlite3xError::Query(e.to_string()))?;
-
-                    while let Some(col_row) = col_rows
-                        .next()
-                        .map_err(|e| Sqlite3xError::Query(e.to_string()))?
-                    {
-                        let col_name: String = col_row.get(1).unwrap_or_default();
-
-                        // Check if this column is a foreign key
-                        let mut foreign_key = None;
-                        {
-                            let fk_sql = format!("PRAGMA foreign_key_list({})", name);
-                            let mut fk_stmt = conn
-                                .prepare(&fk_sql)
-                                .map_err(|e| Sqlite3xError::Query(e.to_string()))?;
-                            let mut fk_rows = fk_stmt
-                                .query([])
-                                .map_err(|e| Sqlite3xError::Query(e.to_string()))?;
-                            while let Some(fk_row) = fk_rows
-                                .next()
-                                .map_err(|e| Sqlite3xError::Query(e.to_string()))?
-                            {
-                                let from_col: String = fk_row.get(3).unwrap_or_default();
-                                if from_col == col_name {
-                                    foreign_key = Some(ForeignKeyInfo {
-                                        table: fk_row.get(2).unwrap_or_default(),
-                                        column: fk_row.get(4).unwrap_or_default(),
-                                    });
-                                    break;
-                                }
-                            }
-                        }
-
-                        columns.push(ColumnInfo {
-                            name: col_name,
-                            data_type: col_row.get(2).unwrap_or_default(),
-                            not_null: col_row.get(3).unwrap_or(0) != 0,
-                            primary_key: col_row.get(5).unwrap_or(0) != 0,
-                            default_value: col_row.get(4).ok(),
-                            foreign_key,
-                        });
-                    }
-                }
+        if !conn.is_autocommit() {
+            return load_schema(&conn, "main");
+        }
 
-                tables.push(TableInfo { name, sql, columns });
+        let version: i64 = conn
+            .query_row("PRAGMA schema_version", [], |row| row.get(0))
+            .map_err(|e| Sqlite3xError::Schema(format!("Schema version error: {}", e)))?;
+
+        let mut cache = self
+            .schema_cache
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+
+        if let Some((cached_version, schema)) = cache.as_ref() {
+            if *cached_version == version {
+                return Ok(schema.clone());
             }
         }
 
-        // Get views
-        let mut views = Vec::new();
-        {
-            let mut stmt = conn
-                .prepare("SELECT name, sql FROM sqlite_master WHERE type='view' ORDER BY name")
-                .map_err(|e| Sqlite3xError::Query(format!("View query error: {}", e)))?;
-
-            let mut rows = stmt
-                .query([])
-                .map_err(|e| Sqlite3xError::Query(format!("View query error: {}", e)))?;
-
-            while let Some(row) = rows
-                .next()
-                .map_err(|e| Sqlite3xError::Query(e.to_string()))?
-            {
-                let name: String = row.get(0).unwrap_or_default();
-                let sql: Option<String> = row.get(1).ok();
-                views.push(ViewInfo { name, sql });
-            }
+        let schema = load_schema(&conn, "main")?;
+        *cache = Some((version, schema.clone()));
+
+        Ok(schema)
+    }
+
+    /// Forget the cached schema, e.g. after rolling back schema changes
+    pub fn clear_schema_cache(&self) {
+        if let Ok(mut cache) = self.schema_cache.lock() {
+            *cache = None;
         }
+    }
 
-        // Get indexes
-        let mut indexes = Vec::new();
-        {
-            let mut stmt = conn.prepare(
-                "SELECT name, tbl_name, sql FROM sqlite_master WHERE type='index' AND name NOT LIKE 'sqlite_%' ORDER BY name"
-            ).map_err(|e| Sqlite3xError::Query(format!("Index query error: {}", e)))?;
-
-            let mut rows = stmt
-                .query([])
-                .map_err(|e| Sqlite3xError::Query(format!("Index query error: {}", e)))?;
-
-            while let Some(row) = rows
-                .next()
-                .map_err(|e| Sqlite3xError::Query(e.to_string()))?
-            {
-                let name: String = row.get(0).unwrap_or_default();
-                let table_name: String = row.get(1).unwrap_or_default();
-                let sql: Option<String> = row.get(2).ok();
-                let unique = sql.as_ref().map(|s| s.contains("UNIQUE")).unwrap_or(false);
-                indexes.push(IndexInfo {
-                    name,
-                    table_name,
-                    sql,
-                    unique,
-                    columns: Vec::new(),
-                });
+    /// Get schema information of "temp" or an attached database
+    ///
+    /// Not cached; `get_schema` covers the main database.
+    pub fn get_schema_of(&self, schema: &str) -> Sqlite3xResult<SchemaInfo> {
+        if schema.eq_ignore_ascii_case("main") {
+            return self.get_schema();
+        }
+
+        let conn = self
+            .connection
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+
+        let known: bool = conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM pragma_database_list WHERE name = ?1 COLLATE NOCASE)",
+                [schema],
+                |row| row.get(0),
+            )
+            .map_err(|e| Sqlite3xError::Schema(format!("Database list error: {}", e)))?;
+        if !known {
+            return Err(Sqlite3xError::Schema(format!("No attached database named {}", schema)));
+        }
+
+        load_schema(&conn, schema)
+    }
+
+    /// Start recording changes made through this connection under `name`
+    pub fn start_session(&self, name: &str) -> Sqlite3xResult<()> {
+        let conn = self
+            .connection
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+
+        // The session lives in `self.sessions`, which is dropped before the connection
+        let session = unsafe { ChangeSession::create(conn.handle(), "main")? };
+
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+        sessions.insert(name.to_string(), session);
+
+        Ok(())
+    }
+
+    /// Start recording changes under `name`, giving up once they take more
+    /// than `max_bytes`
+    ///
+    /// The limit is checked from the connection's progress handler, so only
+    /// one limited session can be active at a time. `session_changeset`
+    /// fails for a session that went over its limit.
+    pub fn start_limited_session(&self, name: &str, max_bytes: usize) -> Sqlite3xResult<()> {
+        self.start_session(name)?;
+
+        let conn = self
+            .connection
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+        let session = sessions
+            .get_mut(name)
+            .ok_or_else(|| Sqlite3xError::Session(format!("No change session: {}", name)))?;
+
+        // `end_session` removes the handler before the session is dropped
+        let limit = unsafe { session.limit(max_bytes) };
+        conn.progress_handler(SESSION_LIMIT_CHECK_OPS, Some(move || {
+            limit.check();
+            false
+        }));
+
+        Ok(())
+    }
+
+    /// Stop recording changes under `name`
+    pub fn end_session(&self, name: &str) {
+        let session = match self.sessions.lock() {
+            Ok(mut sessions) => sessions.remove(name),
+            Err(_) => None,
+        };
+
+        // The progress handler must not outlive the session it checks
+        if session.as_ref().is_some_and(ChangeSession::is_limited) {
+            if let Ok(conn) = self.connection.lock() {
+                conn.progress_handler(0, None::<fn() -> bool>);
             }
         }
+    }
 
-        // Get triggers
-        let mut triggers = Vec::new();
-        {
-            let mut stmt = conn.prepare(
-                "SELECT name, tbl_name, sql FROM sqlite_master WHERE type='trigger' ORDER BY name"
-            ).map_err(|e| Sqlite3xError::Query(format!("Trigger query error: {}", e)))?;
-
-            let mut rows = stmt
-                .query([])
-                .map_err(|e| Sqlite3xError::Query(format!("Trigger query error: {}", e)))?;
-
-            while let Some(row) = rows
-                .next()
-                .map_err(|e| Sqlite3xError::Query(e.to_string()))?
-            {
-                let name: String = row.get(0).unwrap_or_default();
-                let table_name: String = row.get(1).unwrap_or_default();
-                let sql: Option<String> = row.get(2).ok();
-                triggers.push(TriggerInfo {
-                    name,
-                    table_name,
-                    sql,
-                });
+    /// Serialized changeset recorded so far under `name`
+    pub fn session_changeset(&self, name: &str) -> Sqlite3xResult<Vec<u8>> {
+        let sessions = self
+            .sessions
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+
+        sessions
+            .get(name)
+            .ok_or_else(|| Sqlite3xError::Session(format!("No change session: {}", name)))?
+            .changeset()
+    }
+
+    /// Apply a changeset, or its inverse, atomically
+    ///
+    /// Any conflict (row changed or missing since the changeset was recorded,
+    /// duplicate key, constraint violation) aborts the whole application.
+    pub fn apply_changeset(&self, changeset: &[u8], invert: bool) -> Sqlite3xResult<()> {
+        use rusqlite::session::{ConflictAction, ConflictType};
+
+        let conn = self
+            .connection
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+
+        let mut data = Vec::new();
+        if invert {
+            rusqlite::session::invert_strm(&mut &changeset[..], &mut data)
+                .map_err(|e| Sqlite3xError::Session(format!("Failed to invert changeset: {}", e)))?;
+        } else {
+            data.extend_from_slice(changeset);
+        }
+
+        let conflicts = Arc::new(Mutex::new(Vec::<String>::new()));
+        let conflicts_handler = Arc::clone(&conflicts);
+
+        let result = conn.apply_strm(
+            &mut &data[..],
+            None::<fn(&str) -> bool>,
+            move |conflict, item| {
+                let reason = match conflict {
+                    ConflictType::SQLITE_CHANGESET_DATA => "row was modified since",
+                    ConflictType::SQLITE_CHANGESET_NOTFOUND => "row no longer exists",
+                    ConflictType::SQLITE_CHANGESET_CONFLICT => "row with the same key already exists",
+                    ConflictType::SQLITE_CHANGESET_CONSTRAINT => "constraint violation",
+                    ConflictType::SQLITE_CHANGESET_FOREIGN_KEY => "foreign key violation",
+                    _ => "unknown conflict",
+                };
+                // Foreign key conflicts are reported once at the end, without a row
+                let message = match conflict {
+                    ConflictType::SQLITE_CHANGESET_FOREIGN_KEY => reason.to_string(),
+                    _ => match item.op() {
+                        Ok(op) => format!("{}: {}", op.table_name(), reason),
+                        Err(_) => reason.to_string(),
+                    },
+                };
+                if let Ok(mut conflicts) = conflicts_handler.lock() {
+                    conflicts.push(message);
+                }
+                ConflictAction::SQLITE_CHANGESET_ABORT
+            },
+        );
+
+        let conflicts = conflicts.lock().map(|c| c.clone()).unwrap_or_default();
+        if !conflicts.is_empty() {
+            return Err(Sqlite3xError::ChangesetConflict(conflicts.join("; ")));
+        }
+
+        result.map_err(|e| Sqlite3xError::Session(format!("Failed to apply changeset: {}", e)))
+    }
+
+    /// Decode a changeset into per-table row changes using this connection's schema
+    pub fn decode_changeset(&self, changeset: &[u8]) -> Sqlite3xResult<Vec<TableChanges>> {
+        let conn = self
+            .connection
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+
+        super::session::decode_changeset(changeset, |table, column_count| {
+            let mut names: Vec<String> = conn
+                .prepare("SELECT name FROM pragma_table_info(?1)")
+                .and_then(|mut stmt| {
+                    stmt.query_map([table], |row| row.get(0))?
+                        .collect::<rusqlite::Result<Vec<String>>>()
+                })
+                .unwrap_or_default();
+
+            // Rowid tables without a declared primary key record the rowid first
+            if names.len() + 1 == column_count {
+                names.insert(0, "rowid".to_string());
+            }
+            if names.len() != column_count {
+                names = (0..column_count).map(|i| format!("column_{}", i)).collect();
             }
+            names
+        })
+    }
+
+    /// Columns that identify a row of `table`
+    ///
+    /// Declared primary key columns in key order, or `rowid` for rowid tables
+    /// without one. WITHOUT ROWID tables always have a declared primary key.
+    pub fn get_row_key(&self, table: &str) -> Sqlite3xResult<RowKey> {
+        let conn = self
+            .connection
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+
+        let without_rowid: Option<bool> = conn
+            .query_row(
+                "SELECT wr FROM pragma_table_list WHERE schema = 'main' AND name = ?1 AND type IN ('table', 'virtual')",
+                [table],
+                |row| row.get(0),
+            )
+            .map(Some)
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                e => Err(e),
+            })
+            .map_err(|e| Sqlite3xError::Schema(format!("Table lookup error: {}", e)))?;
+
+        let Some(without_rowid) = without_rowid else {
+            return Err(Sqlite3xError::Schema(format!("Table not found: {}", table)));
+        };
+
+        let mut columns: Vec<String> = conn
+            .prepare("SELECT name FROM pragma_table_info(?1) WHERE pk > 0 ORDER BY pk")
+            .and_then(|mut stmt| {
+                stmt.query_map([table], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()
+            })
+            .map_err(|e| Sqlite3xError::Schema(format!("Table info error: {}", e)))?;
+
+        if columns.is_empty() {
+            if without_rowid {
+                return Err(Sqlite3xError::Schema(format!("Table has no key: {}", table)));
+            }
+            columns.push("rowid".to_string());
         }
 
-        Ok(SchemaInfo {
-            tables,
-            views,
-            indexes,
-            triggers,
+        Ok(RowKey {
+            columns,
+            has_rowid: !without_rowid,
         })
     }
 
+    /// Names of the columns `SELECT *` returns for `table`, including generated columns
+    pub fn get_column_names(&self, table: &str) -> Sqlite3xResult<Vec<String>> {
+        let conn = self
+            .connection
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+
+        let names: Vec<String> = conn
+            .prepare("SELECT name FROM pragma_table_xinfo(?1) WHERE hidden != 1 ORDER BY cid")
+            .and_then(|mut stmt| {
+                stmt.query_map([table], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()
+            })
+            .map_err(|e| Sqlite3xError::Schema(format!("Table info error: {}", e)))?;
+
+        if names.is_empty() {
+            return Err(Sqlite3xError::Schema(format!("Table not found: {}", table)));
+        }
+
+        Ok(names)
+    }
+
+    /// Whether the connection is in autocommit mode (no transaction open)
+    pub fn is_autocommit(&self) -> Sqlite3xResult<bool> {
+        let conn = self
+            .connection
+            .lock()
+            .map_err(|e| Sqlite3xError::Connection(format!("Lock error: {}", e)))?;
+
+        Ok(conn.is_autocommit())
+    }
+
     /// Get database path
     pub fn get_path(&self) -> &str {
         &self.path
@@ -662,7 +1252,8 @@ pub struct QueryResult {
     pub rows: Vec<Vec<serde_json::Value>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SchemaInfo {
     pub tables: Vec<TableInfo>,
     pub views: Vec<ViewInfo>,
@@ -670,50 +1261,161 @@ pub struct SchemaInfo {
     pub triggers: Vec<TriggerInfo>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TableInfo {
     pub name: String,
     pub sql: Option<String>,
+    pub kind: TableKind,
+    pub strict: bool,
+    pub without_rowid: bool,
+    /// Module of a virtual table, e.g. `fts5` or `rtree`
+    pub module: Option<String>,
+    /// Shadow tables holding the data of a virtual table
+    pub shadow_tables: Vec<String>,
+    /// Virtual table a shadow table belongs to
+    pub shadow_of: Option<String>,
+    /// Includes hidden and generated columns
     pub columns: Vec<ColumnInfo>,
+    /// Foreign keys declared on this table
+    pub foreign_keys: Vec<ForeignKeyConstraint>,
+    /// Foreign keys of other tables (or this one) that reference this table
+    pub referenced_by: Vec<ForeignKeyConstraint>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ColumnInfo {
     pub name: String,
     pub data_type: String,
     pub not_null: bool,
     pub primary_key: bool,
     pub default_value: Option<String>,
+    /// Hidden column of a virtual table, e.g. an FTS5 `rank`
+    pub hidden: bool,
+    pub generated: Option<GeneratedKind>,
     pub foreign_key: Option<ForeignKeyInfo>,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
+impl ColumnInfo {
+    /// Whether values can be written to this column
+    pub fn is_writable(&self) -> bool {
+        !self.hidden && self.generated.is_none()
+    }
+}
+
+/// Kind of table from `PRAGMA table_list`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TableKind {
+    Table,
+    Virtual,
+    /// Backing table of a virtual table, e.g. `docs_content` for FTS5 table `docs`
+    Shadow,
+}
+
+/// How a generated column is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GeneratedKind {
+    /// Computed when read
+    Virtual,
+    /// Computed when written and stored in the row
+    Stored,
+}
+
+/// Foreign key a column takes part in
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ForeignKeyInfo {
+    /// Id of the constraint in the table's `foreign_keys`
+    pub constraint_id: i64,
     pub table: String,
     pub column: String,
 }
 
-#[derive(Debug, Clone)]
+/// A foreign key constraint
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForeignKeyConstraint {
+    /// Id from `PRAGMA foreign_key_list`, unique within the table
+    pub id: i64,
+    /// Table declaring the constraint
+    pub table: String,
+    pub referenced_table: String,
+    /// Column pairs in key order
+    pub columns: Vec<ForeignKeyColumn>,
+    pub on_update: String,
+    pub on_delete: String,
+    pub match_type: String,
+    pub deferrable: bool,
+    pub initially_deferred: bool,
+}
+
+/// A column of a foreign key and the parent column it references
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForeignKeyColumn {
+    pub from: String,
+    /// Referenced column; the parent's primary key column when not named
+    pub to: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ViewInfo {
     pub name: String,
     pub sql: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct IndexInfo {
     pub name: String,
     pub table_name: String,
+    /// `None` for indexes SQLite creates for constraints
     pub sql: Option<String>,
     pub unique: bool,
+    pub origin: IndexOrigin,
+    pub partial: bool,
+    /// Predicate of a partial index
+    pub where_clause: Option<String>,
+    /// Key column names, or the expression text for expression terms
     pub columns: Vec<String>,
+    pub key_columns: Vec<IndexColumn>,
 }
 
-#[derive(Debug, Clone)]
+/// What created an index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IndexOrigin {
+    /// A CREATE INDEX statement
+    CreateIndex,
+    /// A UNIQUE constraint
+    Unique,
+    /// A PRIMARY KEY constraint
+    PrimaryKey,
+}
+
+/// A key term of an index
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexColumn {
+    /// Column name; `None` for expressions
+    pub name: Option<String>,
+    pub expression: Option<String>,
+    pub descending: bool,
+    pub collation: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TriggerInfo {
     pub name: String,
     pub table_name: String,
     pub sql: Option<String>,
+    /// `None` when the DDL could not be parsed
+    pub definition: Option<TriggerDefinition>,
 }
 
 #[cfg(test)]
@@ -731,4 +1433,154 @@ mod tests {
         // Clear cache
         db.clear_cache().expect("Failed to clear cache");
     }
+
+    #[test]
+    fn test_get_schema_cached_by_version() {
+        let db = Database::open(":memory:").expect("Failed to open in-memory db");
+        db.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL);
+             CREATE TABLE posts (id INTEGER PRIMARY KEY, user_id INTEGER REFERENCES users(id), title TEXT);
+             CREATE UNIQUE INDEX idx_posts_user_title ON posts (user_id, title);",
+        )
+        .expect("Failed to create schema");
+
+        let schema = db.get_schema().expect("Failed to get schema");
+        let posts = schema.tables.iter().find(|t| t.name == "posts").unwrap();
+        let user_id = posts.columns.iter().find(|c| c.name == "user_id").unwrap();
+        assert_eq!(user_id.foreign_key.as_ref().map(|fk| fk.table.as_str()), Some("users"));
+        assert_eq!(schema.indexes[0].columns, vec!["user_id", "title"]);
+        assert!(schema.indexes[0].unique);
+
+        db.execute_batch(
+            "CREATE TABLE tags (name TEXT UNIQUE, weight INTEGER);
+             CREATE INDEX idx_tags_weight ON tags (weight DESC, lower(name) COLLATE NOCASE) WHERE weight > 0;",
+        )
+        .expect("Failed to create tags");
+        let schema = db.get_schema().expect("Failed to get schema");
+        let auto = schema.indexes.iter().find(|i| i.table_name == "tags" && i.sql.is_none()).unwrap();
+        assert_eq!(auto.origin, IndexOrigin::Unique);
+        assert_eq!(auto.columns, vec!["name"]);
+        let weight = schema.indexes.iter().find(|i| i.name == "idx_tags_weight").unwrap();
+        assert_eq!(weight.origin, IndexOrigin::CreateIndex);
+        assert_eq!(weight.columns, vec!["weight", "lower(name)"]);
+        assert!(weight.key_columns[0].descending);
+        assert_eq!(weight.key_columns[1].collation, "NOCASE");
+        assert_eq!(weight.where_clause.as_deref(), Some("weight > 0"));
+
+        // Schema changes invalidate the cache
+        db.execute("ALTER TABLE users ADD COLUMN name TEXT").expect("Failed to alter table");
+        let schema = db.get_schema().expect("Failed to get schema");
+        let users = schema.tables.iter().find(|t| t.name == "users").unwrap();
+        assert_eq!(users.columns.len(), 3);
+
+        // A rolled back schema is not served once its version number comes back
+        db.execute("BEGIN").unwrap();
+        db.execute("CREATE TABLE drafts (id INTEGER)").unwrap();
+        assert!(db.get_schema().unwrap().tables.iter().any(|t| t.name == "drafts"));
+        db.execute("ROLLBACK").unwrap();
+        db.execute("CREATE TABLE notes (id INTEGER)").unwrap();
+        let schema = db.get_schema().expect("Failed to get schema");
+        assert!(schema.tables.iter().all(|t| t.name != "drafts"));
+        assert!(schema.tables.iter().any(|t| t.name == "notes"));
+    }
+
+    #[test]
+    fn test_open_read_only() {
+        let dir = std::env::temp_dir().join("dbstudiox_test_open_read_only");
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        let read_only = OpenFlags { read_only: true, read_write: false, create: false, wal_mode: false };
+
+        // A missing file is not created
+        let missing = dir.join("missing.db");
+        assert!(Database::open_with_flags(missing.to_str().unwrap(), read_only).is_err());
+        assert!(!missing.exists());
+
+        let path = dir.join("rollback.db");
+        let path = path.to_str().unwrap();
+        let db = Database::open_with_flags(path, OpenFlags { wal_mode: false, ..Default::default() }).unwrap();
+        db.execute_batch("CREATE TABLE t (id INTEGER PRIMARY KEY)").unwrap();
+        drop(db);
+
+        // The journal mode is left alone and nothing can be written
+        let db = Database::open_with_flags(path, read_only).unwrap();
+        let mode = db.query_values("PRAGMA journal_mode", &[]).unwrap();
+        assert_eq!(mode[0][0], CellValue::Text("delete".to_string()));
+        assert_eq!(db.get_schema().unwrap().tables.len(), 1);
+        assert!(db.execute("INSERT INTO t VALUES (1)").is_err());
+
+        // Schemas round-trip through JSON, as snapshots
+        let schema = db.get_schema().unwrap();
+        let json = serde_json::to_string(&schema).unwrap();
+        let restored: SchemaInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.tables[0].name, "t");
+        assert_eq!(restored.tables[0].columns[0].name, "id");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_get_schema_composite_foreign_keys() {
+        let db = Database::open(":memory:").expect("Failed to open in-memory db");
+        db.execute_batch(
+            "CREATE TABLE parent (a INTEGER, b TEXT, PRIMARY KEY (a, b));
+             CREATE TABLE child (
+                 id INTEGER PRIMARY KEY,
+                 pa INTEGER,
+                 pb TEXT,
+                 owner INTEGER REFERENCES child ON DELETE SET NULL,
+                 FOREIGN KEY (pa, pb) REFERENCES parent ON UPDATE CASCADE DEFERRABLE INITIALLY DEFERRED
+             );",
+        )
+        .expect("Failed to create schema");
+
+        let schema = db.get_schema().expect("Failed to get schema");
+        let child = schema.tables.iter().find(|t| t.name == "child").unwrap();
+        assert_eq!(child.foreign_keys.len(), 2);
+
+        let composite = child.foreign_keys.iter().find(|fk| fk.referenced_table == "parent").unwrap();
+        let pairs: Vec<(&str, &str)> = composite.columns.iter().map(|c| (c.from.as_str(), c.to.as_str())).collect();
+        assert_eq!(pairs, vec![("pa", "a"), ("pb", "b")]);
+        assert_eq!(composite.on_update, "CASCADE");
+        assert!(composite.deferrable && composite.initially_deferred);
+
+        let owner = child.foreign_keys.iter().find(|fk| fk.referenced_table == "child").unwrap();
+        assert_eq!(owner.on_delete, "SET NULL");
+        assert_eq!(owner.columns[0].to, "id");
+        assert!(!owner.deferrable);
+
+        let parent = schema.tables.iter().find(|t| t.name == "parent").unwrap();
+        assert_eq!(parent.referenced_by.len(), 1);
+        assert_eq!(parent.referenced_by[0].table, "child");
+        assert_eq!(child.referenced_by.len(), 1);
+    }
+
+    #[test]
+    fn test_get_schema_table_flags() {
+        let db = Database::open(":memory:").expect("Failed to open in-memory db");
+        db.execute_batch(
+            "CREATE TABLE kv (k TEXT PRIMARY KEY, v INTEGER) STRICT, WITHOUT ROWID;
+             CREATE TABLE items (price REAL, qty INTEGER, total REAL AS (price * qty) STORED, label TEXT AS ('x'));
+             CREATE VIRTUAL TABLE docs USING fts5(body);",
+        )
+        .expect("Failed to create schema");
+
+        let schema = db.get_schema().expect("Failed to get schema");
+        let kv = schema.tables.iter().find(|t| t.name == "kv").unwrap();
+        assert!(kv.strict && kv.without_rowid);
+
+        let items = schema.tables.iter().find(|t| t.name == "items").unwrap();
+        let generated: Vec<Option<GeneratedKind>> = items.columns.iter().map(|c| c.generated).collect();
+        assert_eq!(generated, vec![None, None, Some(GeneratedKind::Stored), Some(GeneratedKind::Virtual)]);
+
+        let docs = schema.tables.iter().find(|t| t.name == "docs").unwrap();
+        assert_eq!(docs.kind, TableKind::Virtual);
+        assert_eq!(docs.module.as_deref(), Some("fts5"));
+        assert!(docs.shadow_tables.iter().any(|t| t == "docs_content"));
+        let content = schema.tables.iter().find(|t| t.name == "docs_content").unwrap();
+        assert_eq!(content.kind, TableKind::Shadow);
+        assert_eq!(content.shadow_of.as_deref(), Some("docs"));
+    }
 }
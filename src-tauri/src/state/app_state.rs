@@ -2,11 +2,14 @@
 //!
 //! Thread-safe application state management using parking_lot.
 
-use super::connection_pool::{QueryStats, TransactionInfo};
+use super::change_history::{ChangeEntry, ChangeHistory, MAX_CHANGESET_BYTES};
+use super::connection_pool::{
+    QueryStats, TransactionDrift, TransactionInfo, TransactionMode, TransactionTimeoutConfig,
+};
 use crate::commands::database::DatabaseConnection;
 use crate::sqlite3x::wrapper::Database;
 use parking_lot::{Mutex, RwLock};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 /// Application state that is shared across all Tauri commands
@@ -19,6 +22,12 @@ pub struct AppState {
     transactions: RwLock<HashMap<String, TransactionInfo>>,
     /// Query statistics per connection
     query_stats: RwLock<HashMap<String, QueryStats>>,
+    /// Limits enforced on open transactions
+    transaction_timeouts: RwLock<TransactionTimeoutConfig>,
+    /// Connections that opted out of transaction timeouts
+    timeout_exempt: RwLock<HashSet<String>>,
+    /// Undo/redo history per connection
+    change_history: RwLock<HashMap<String, ChangeHistory>>,
 }
 
 impl AppState {
@@ -29,6 +38,9 @@ impl AppState {
             db_handles: RwLock::new(HashMap::new()),
             transactions: RwLock::new(HashMap::new()),
             query_stats: RwLock::new(HashMap::new()),
+            transaction_timeouts: RwLock::new(TransactionTimeoutConfig::default()),
+            timeout_exempt: RwLock::new(HashSet::new()),
+            change_history: RwLock::new(HashMap::new()),
         }
     }
 
@@ -71,6 +83,8 @@ impl AppState {
 
         // Clean up related data
         self.query_stats.write().remove(connection_id);
+        self.timeout_exempt.write().remove(connection_id);
+        self.change_history.write().remove(connection_id);
 
         // Remove any transactions for this connection
         let mut transactions = self.transactions.write();
@@ -106,6 +120,7 @@ impl AppState {
         &self,
         connection_id: &str,
         transaction_id: &str,
+        mode: TransactionMode,
     ) -> Result<(), String> {
         if !self.has_connection(connection_id) {
             return Err(format!("Connection not found: {}", connection_id));
@@ -117,12 +132,16 @@ impl AppState {
             return Err(format!("Transaction already exists: {}", transaction_id));
         }
 
+        let now = chrono::Utc::now();
         transactions.insert(
             transaction_id.to_string(),
             TransactionInfo {
                 id: transaction_id.to_string(),
                 connection_id: connection_id.to_string(),
-                started_at: chrono::Utc::now(),
+                started_at: now,
+                mode: Some(mode),
+                last_activity_at: now,
+                warned: false,
             },
         );
 
@@ -145,11 +164,6 @@ impl AppState {
         Ok(())
     }
 
-    /// Get a specific transaction
-    pub fn get_transaction(&self, transaction_id: &str) -> Option<TransactionInfo> {
-        self.transactions.read().get(transaction_id).cloned()
-    }
-
     /// Get active transactions for a connection
     pub fn get_transactions(&self, connection_id: &str) -> Vec<TransactionInfo> {
         self.transactions
@@ -160,6 +174,144 @@ impl AppState {
             .collect()
     }
 
+    /// Get all active transactions across connections
+    pub fn get_all_transactions(&self) -> Vec<TransactionInfo> {
+        self.transactions.read().values().cloned().collect()
+    }
+
+    /// Record activity on the open transactions of a connection
+    pub fn touch_transactions(&self, connection_id: &str) {
+        let now = chrono::Utc::now();
+        for t in self.transactions.write().values_mut() {
+            if t.connection_id == connection_id {
+                t.last_activity_at = now;
+                t.warned = false;
+            }
+        }
+    }
+
+    /// Mark a transaction as warned about an upcoming timeout
+    pub fn mark_transaction_warned(&self, transaction_id: &str) {
+        if let Some(t) = self.transactions.write().get_mut(transaction_id) {
+            t.warned = true;
+        }
+    }
+
+    /// Reconcile tracked transactions with the connection's autocommit state
+    ///
+    /// A `COMMIT` typed in the editor or a statement failure that rolls back
+    /// leaves the tracked transaction behind, while a `BEGIN` typed in the
+    /// editor opens one that was never tracked. Returns the drift that was
+    /// corrected, if any.
+    ///
+    /// Changes still tagged with an ended transaction may have been rolled
+    /// back, so they are dropped from the undo history.
+    pub fn sync_transaction_state(&self, connection_id: &str) -> Option<TransactionDrift> {
+        // Lock the database before the transaction map, as every other path does
+        let db_handle = self.get_db_handle(connection_id)?;
+        let db = db_handle.lock();
+        let autocommit = db.is_autocommit().ok()?;
+
+        let tracked: Vec<String> = self
+            .transactions
+            .read()
+            .values()
+            .filter(|t| t.connection_id == connection_id)
+            .map(|t| t.id.clone())
+            .collect();
+
+        if autocommit && !tracked.is_empty() {
+            for id in &tracked {
+                db.end_session(id);
+            }
+            let mut transactions = self.transactions.write();
+            for id in &tracked {
+                transactions.remove(id);
+            }
+            drop(transactions);
+            self.with_change_history(connection_id, |history| {
+                for id in &tracked {
+                    history.end_transaction(id, false);
+                }
+            });
+            return Some(TransactionDrift::Ended(tracked));
+        }
+
+        if !autocommit && tracked.is_empty() {
+            let now = chrono::Utc::now();
+            let info = TransactionInfo {
+                id: uuid::Uuid::new_v4().to_string(),
+                connection_id: connection_id.to_string(),
+                started_at: now,
+                mode: None,
+                last_activity_at: now,
+                warned: false,
+            };
+            self.transactions.write().insert(info.id.clone(), info.clone());
+            return Some(TransactionDrift::Adopted(info));
+        }
+
+        None
+    }
+
+    // ==================== Transaction Timeouts ====================
+
+    /// Get the transaction timeout limits
+    pub fn get_transaction_timeouts(&self) -> TransactionTimeoutConfig {
+        self.transaction_timeouts.read().clone()
+    }
+
+    /// Replace the transaction timeout limits
+    pub fn set_transaction_timeouts(&self, config: TransactionTimeoutConfig) {
+        *self.transaction_timeouts.write() = config;
+    }
+
+    /// Enable or disable transaction timeouts for a connection
+    pub fn set_transaction_timeout_enabled(&self, connection_id: &str, enabled: bool) {
+        let mut exempt = self.timeout_exempt.write();
+        if enabled {
+            exempt.remove(connection_id);
+        } else {
+            exempt.insert(connection_id.to_string());
+        }
+    }
+
+    /// Check whether transaction timeouts apply to a connection
+    pub fn is_transaction_timeout_enabled(&self, connection_id: &str) -> bool {
+        !self.timeout_exempt.read().contains(connection_id)
+    }
+
+    // ==================== Change History ====================
+
+    /// Record an undoable change for a connection
+    pub fn record_change(&self, connection_id: &str, entry: ChangeEntry) {
+        if entry.changeset.is_empty() {
+            return;
+        }
+
+        if entry.changeset.len() > MAX_CHANGESET_BYTES {
+            log::warn!(
+                "Change '{}' on {} is too large to undo ({} bytes)",
+                entry.description,
+                connection_id,
+                entry.changeset.len()
+            );
+            return;
+        }
+
+        self.with_change_history(connection_id, |history| history.record(entry));
+    }
+
+    /// Access the undo/redo history of a connection
+    pub fn with_change_history<R>(
+        &self,
+        connection_id: &str,
+        f: impl FnOnce(&mut ChangeHistory) -> R,
+    ) -> R {
+        let mut histories = self.change_history.write();
+        f(histories.entry(connection_id.to_string()).or_default())
+    }
+
     // ==================== Query Statistics ====================
 
     /// Get query statistics for a connection
@@ -0,0 +1,122 @@
+//! Change History
+//!
+//! Per-connection undo and redo stacks of recorded changesets.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Maximum number of undo entries kept per connection
+const MAX_UNDO_ENTRIES: usize = 100;
+
+/// Changesets larger than this are not kept, to bound memory use of bulk imports
+pub const MAX_CHANGESET_BYTES: usize = 64 * 1024 * 1024;
+
+/// A recorded data change that can be undone or redone
+#[derive(Debug, Clone)]
+pub struct ChangeEntry {
+    pub id: String,
+    /// What produced the change, e.g. the executed SQL
+    pub description: String,
+    pub affected_rows: usize,
+    pub created_at: DateTime<Utc>,
+    /// Changeset that replays the change; its inverse undoes it
+    pub changeset: Vec<u8>,
+    /// Transaction the change was made in, while it is still open
+    pub transaction_id: Option<String>,
+}
+
+impl ChangeEntry {
+    pub fn new(description: String, affected_rows: usize, changeset: Vec<u8>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            description,
+            affected_rows,
+            created_at: Utc::now(),
+            changeset,
+            transaction_id: None,
+        }
+    }
+
+    pub fn summary(&self) -> ChangeEntrySummary {
+        ChangeEntrySummary {
+            id: self.id.clone(),
+            description: self.description.clone(),
+            affected_rows: self.affected_rows,
+            created_at: self.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Change entry metadata for frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEntrySummary {
+    pub id: String,
+    pub description: String,
+    pub affected_rows: usize,
+    pub created_at: String,
+}
+
+/// Undo and redo stacks of one connection
+#[derive(Debug, Default)]
+pub struct ChangeHistory {
+    undo: VecDeque<ChangeEntry>,
+    redo: Vec<ChangeEntry>,
+}
+
+impl ChangeHistory {
+    /// Record a new change; invalidates the redo stack
+    pub fn record(&mut self, entry: ChangeEntry) {
+        self.redo.clear();
+        self.push_undo(entry);
+    }
+
+    /// Push an entry that can be undone, keeping the redo stack
+    pub fn push_undo(&mut self, entry: ChangeEntry) {
+        self.undo.push_back(entry);
+        while self.undo.len() > MAX_UNDO_ENTRIES {
+            self.undo.pop_front();
+        }
+    }
+
+    pub fn pop_undo(&mut self) -> Option<ChangeEntry> {
+        self.undo.pop_back()
+    }
+
+    pub fn push_redo(&mut self, entry: ChangeEntry) {
+        self.redo.push(entry);
+    }
+
+    pub fn pop_redo(&mut self) -> Option<ChangeEntry> {
+        self.redo.pop()
+    }
+
+    /// Settle the changes made in a transaction that ended
+    ///
+    /// Committed changes stay undoable. Rolled back changes are gone from the
+    /// database, so their entries are dropped from both stacks.
+    pub fn end_transaction(&mut self, transaction_id: &str, committed: bool) {
+        let in_transaction = |e: &ChangeEntry| e.transaction_id.as_deref() == Some(transaction_id);
+        if committed {
+            for entry in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+                if in_transaction(entry) {
+                    entry.transaction_id = None;
+                }
+            }
+        } else {
+            self.undo.retain(|e| !in_transaction(e));
+            self.redo.retain(|e| !in_transaction(e));
+        }
+    }
+
+    /// Undo entries, most recent first
+    pub fn undo_summaries(&self) -> Vec<ChangeEntrySummary> {
+        self.undo.iter().rev().map(ChangeEntry::summary).collect()
+    }
+
+    /// Redo entries, most recent first
+    pub fn redo_summaries(&self) -> Vec<ChangeEntrySummary> {
+        self.redo.iter().rev().map(ChangeEntry::summary).collect()
+    }
+}
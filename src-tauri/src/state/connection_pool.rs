@@ -5,12 +5,133 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// SQLite transaction locking mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionMode {
+    #[default]
+    Deferred,
+    Immediate,
+    Exclusive,
+}
+
+impl TransactionMode {
+    /// SQL statement that opens a transaction in this mode
+    pub fn begin_sql(&self) -> &'static str {
+        match self {
+            TransactionMode::Deferred => "BEGIN DEFERRED TRANSACTION",
+            TransactionMode::Immediate => "BEGIN IMMEDIATE TRANSACTION",
+            TransactionMode::Exclusive => "BEGIN EXCLUSIVE TRANSACTION",
+        }
+    }
+}
+
+impl std::str::FromStr for TransactionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "deferred" => Ok(TransactionMode::Deferred),
+            "immediate" => Ok(TransactionMode::Immediate),
+            "exclusive" => Ok(TransactionMode::Exclusive),
+            _ => Err(format!("Unsupported transaction mode: {}", s)),
+        }
+    }
+}
+
 /// Transaction information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionInfo {
     pub id: String,
     pub connection_id: String,
     pub started_at: DateTime<Utc>,
+    /// Locking mode, or `None` for transactions opened outside `begin_transaction`
+    pub mode: Option<TransactionMode>,
+    /// Last statement executed while the transaction was open
+    pub last_activity_at: DateTime<Utc>,
+    /// Whether a timeout warning was emitted since the last activity
+    #[serde(default)]
+    pub warned: bool,
+}
+
+/// Divergence between tracked transactions and the connection's autocommit state
+#[derive(Debug, Clone)]
+pub enum TransactionDrift {
+    /// Tracked transactions that ended without going through the transaction commands
+    Ended(Vec<String>),
+    /// A transaction opened outside `begin_transaction` that is now tracked
+    Adopted(TransactionInfo),
+}
+
+/// Why a transaction is about to be, or was, rolled back automatically
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionTimeoutReason {
+    Idle,
+    MaxAge,
+}
+
+/// Timeout state of an open transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionTimeoutStatus {
+    /// The transaction will be rolled back in `seconds_remaining`
+    Warning {
+        reason: TransactionTimeoutReason,
+        seconds_remaining: i64,
+    },
+    /// The transaction exceeded a limit and must be rolled back
+    Expired { reason: TransactionTimeoutReason },
+}
+
+/// Limits for open transactions, enforced by the transaction watchdog
+///
+/// On by default, so a transaction forgotten in a tab doesn't hold the
+/// write lock for long; connections that need long transactions opt out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionTimeoutConfig {
+    /// Seconds without activity before rollback, `None` to disable
+    pub idle_timeout_secs: Option<u64>,
+    /// Seconds since `BEGIN` before rollback, `None` to disable
+    pub max_age_secs: Option<u64>,
+    /// Seconds before rollback at which a warning is emitted
+    pub warning_secs: u64,
+}
+
+impl Default for TransactionTimeoutConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout_secs: Some(600),
+            max_age_secs: Some(3600),
+            warning_secs: 30,
+        }
+    }
+}
+
+impl TransactionTimeoutConfig {
+    /// Evaluate a transaction against the configured limits
+    pub fn check(&self, transaction: &TransactionInfo, now: DateTime<Utc>) -> Option<TransactionTimeoutStatus> {
+        let idle = (now - transaction.last_activity_at).num_seconds();
+        let age = (now - transaction.started_at).num_seconds();
+
+        let remaining = [
+            self.idle_timeout_secs.map(|limit| (TransactionTimeoutReason::Idle, limit as i64 - idle)),
+            self.max_age_secs.map(|limit| (TransactionTimeoutReason::MaxAge, limit as i64 - age)),
+        ];
+
+        let (reason, seconds_remaining) = remaining
+            .into_iter()
+            .flatten()
+            .min_by_key(|(_, secs)| *secs)?;
+
+        if seconds_remaining <= 0 {
+            Some(TransactionTimeoutStatus::Expired { reason })
+        } else if seconds_remaining <= self.warning_secs as i64 {
+            Some(TransactionTimeoutStatus::Warning { reason, seconds_remaining })
+        } else {
+            None
+        }
+    }
 }
 
 /// Query statistics for a connection
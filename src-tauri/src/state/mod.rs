@@ -3,7 +3,12 @@
 //! Provides thread-safe state management for the application.
 
 mod app_state;
+mod change_history;
 mod connection_pool;
 
 pub use app_state::AppState;
-pub use connection_pool::{QueryStats, TransactionInfo};
+pub use change_history::{ChangeEntry, ChangeEntrySummary, ChangeHistory, MAX_CHANGESET_BYTES};
+pub use connection_pool::{
+    QueryStats, TransactionDrift, TransactionInfo, TransactionMode, TransactionTimeoutConfig,
+    TransactionTimeoutReason, TransactionTimeoutStatus,
+};
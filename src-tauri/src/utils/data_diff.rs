@@ -0,0 +1,516 @@
+//! Table Data Diff
+//!
+//! Compares the rows of two tables that share a key, in the same or in
+//! different databases, and plans the INSERT, UPDATE and DELETE statements
+//! that make the target's rows match the source's. Both tables are read in
+//! key order a batch at a time, and statements are handed on as they are
+//! planned, so neither the tables nor the sync are held whole.
+
+use super::{quote_identifier, AppError, AppResult};
+use crate::sqlite3x::ddl::tokenize;
+use crate::sqlite3x::types::CellValue;
+use crate::sqlite3x::wrapper::Database;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+
+/// Rows read from each table per query
+const BATCH_SIZE: usize = 1000;
+
+/// Row differences, and script statements, reported by default; the counts
+/// and the sync cover all rows
+pub const DEFAULT_ROW_LIMIT: usize = 1000;
+
+/// Column values keyed by column name
+pub type RowValues = BTreeMap<String, CellValue>;
+
+/// Options of `diff_table_data`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDiffOptions {
+    /// Columns to match rows by; the tables' common primary key by default
+    #[serde(default)]
+    pub key_columns: Option<Vec<String>>,
+    /// Most differences, and script statements, to report; `DEFAULT_ROW_LIMIT` by default
+    #[serde(default)]
+    pub row_limit: Option<usize>,
+    /// Run the sync on the target as well
+    #[serde(default)]
+    pub apply: bool,
+}
+
+/// How a row of the source differs from the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RowChangeKind {
+    /// Only in the source; inserted into the target
+    Inserted,
+    /// Only in the target; deleted from it
+    Deleted,
+    /// In both with different values
+    Changed,
+}
+
+/// A column whose value differs between the two copies of a row
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnDifference {
+    pub column: String,
+    pub source: CellValue,
+    pub target: CellValue,
+}
+
+/// One row that differs
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowDifference {
+    pub kind: RowChangeKind,
+    pub key: RowValues,
+    /// The row's values: the source's when inserted, the target's when deleted
+    pub values: Option<RowValues>,
+    /// Differing columns of a changed row
+    pub changes: Vec<ColumnDifference>,
+}
+
+/// Outcome of `diff_table_data`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableDataDiff {
+    pub key_columns: Vec<String>,
+    /// Columns compared and synced: the ordinary columns both tables have
+    pub columns: Vec<String>,
+    pub inserted: usize,
+    pub deleted: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+    /// The first differences, up to the requested limit
+    pub rows: Vec<RowDifference>,
+    /// Whether there were more differences than `rows` holds
+    pub truncated: bool,
+    /// Columns that are not compared, and rows that can't be
+    pub warnings: Vec<String>,
+    /// The statements that sync the target, as one script; cut at the row
+    /// limit like `rows`
+    pub script: String,
+    /// Number of rows changed in the target when the sync was applied
+    pub applied: Option<usize>,
+}
+
+/// A statement of the sync, with its parameters
+#[derive(Debug, Clone)]
+pub struct SyncStatement {
+    pub sql: String,
+    pub params: Vec<CellValue>,
+}
+
+impl SyncStatement {
+    /// The statement with its parameters written as literals
+    pub fn to_sql(&self) -> String {
+        let mut params = self.params.iter();
+        let mut sql = String::with_capacity(self.sql.len());
+        let mut copied = 0;
+        for token in tokenize(&self.sql).iter().filter(|t| t.is_punct('?')) {
+            let Some(value) = params.next() else { break };
+            sql.push_str(&self.sql[copied..token.start]);
+            sql.push_str(&value.to_sql_literal());
+            copied = token.end();
+        }
+        sql.push_str(&self.sql[copied..]);
+        sql
+    }
+}
+
+/// Compare `source_table` in `source` with `target_table` in `target`
+///
+/// Rows are matched by `key_columns`, or by the primary key (or rowid) the
+/// tables have in common. Each statement that makes the target match the
+/// source goes to `on_statement` as soon as it is planned.
+///
+/// `on_statement` may run the statement on the target: it only touches rows
+/// the diff has passed, or inserts after the target's last row once all of
+/// them were read.
+pub fn diff_table_data(
+    source: &Database,
+    source_table: &str,
+    target: &Database,
+    target_table: &str,
+    key_columns: Option<&[String]>,
+    row_limit: usize,
+    mut on_statement: impl FnMut(&SyncStatement) -> AppResult<()>,
+) -> AppResult<TableDataDiff> {
+    let key = match key_columns {
+        Some(columns) if !columns.is_empty() => columns.to_vec(),
+        _ => common_key(source, source_table, target, target_table)?,
+    };
+
+    let source_columns = ordinary_columns(source, source_table)?;
+    let target_columns = ordinary_columns(target, target_table)?;
+    let is_key = |name: &str| key.iter().any(|k| k.eq_ignore_ascii_case(name));
+    let has = |columns: &[String], name: &str| columns.iter().any(|c| c.eq_ignore_ascii_case(name));
+
+    for column in &key {
+        let missing = [(source_table, &source_columns), (target_table, &target_columns)]
+            .into_iter()
+            .find(|(_, columns)| !column.eq_ignore_ascii_case("rowid") && !has(columns, column));
+        if let Some((table, _)) = missing {
+            return Err(AppError::BadRequest(format!("{} has no key column {}", table, column)));
+        }
+    }
+
+    let columns: Vec<String> = source_columns.iter()
+        .filter(|c| !is_key(c) && has(&target_columns, c))
+        .cloned()
+        .collect();
+    let mut warnings = Vec::new();
+    for (table, only, other) in [(source_table, &source_columns, &target_columns), (target_table, &target_columns, &source_columns)] {
+        let extra: Vec<&str> = only.iter().filter(|c| !is_key(c) && !has(other, c)).map(String::as_str).collect();
+        if !extra.is_empty() {
+            warnings.push(format!("Only {} has columns {}; they are not compared", table, extra.join(", ")));
+        }
+    }
+    for (db, table) in [(source, source_table), (target, target_table)] {
+        let null_keys = count_null_keys(db, table, &key)?;
+        if null_keys > 0 {
+            warnings.push(format!("{} rows of {} have a NULL key and are skipped", null_keys, table));
+        }
+    }
+
+    let mut diff = TableDataDiff {
+        key_columns: key.clone(),
+        columns: columns.clone(),
+        inserted: 0,
+        deleted: 0,
+        changed: 0,
+        unchanged: 0,
+        rows: Vec::new(),
+        truncated: false,
+        warnings,
+        script: String::new(),
+        applied: None,
+    };
+    let mut script = vec!["BEGIN;".to_string()];
+    let mut planned = 0;
+    let mut plan = |statement: SyncStatement| -> AppResult<()> {
+        if planned < row_limit {
+            script.push(format!("{};", statement.to_sql()));
+        }
+        planned += 1;
+        on_statement(&statement)
+    };
+    let report = |diff: &mut TableDataDiff, row: RowDifference| {
+        if diff.rows.len() < row_limit {
+            diff.rows.push(row);
+        } else {
+            diff.truncated = true;
+        }
+    };
+
+    let mut source_rows = RowStream::new(source, source_table, &key, &columns);
+    let mut target_rows = RowStream::new(target, target_table, &key, &columns);
+    let values = |row: &[CellValue]| -> RowValues {
+        key.iter().chain(&columns).cloned().zip(row.iter().cloned()).collect()
+    };
+
+    loop {
+        let order = match (source_rows.peek()?, target_rows.peek()?) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(a), Some(b)) => compare_rows(&a[..key.len()], &b[..key.len()]),
+        };
+
+        match order {
+            Ordering::Less => {
+                let row = source_rows.next()?.unwrap_or_default();
+                plan(insert_statement(target_table, &key, &columns, &row))?;
+                diff.inserted += 1;
+                report(&mut diff, RowDifference {
+                    kind: RowChangeKind::Inserted,
+                    key: values(&row[..key.len()]),
+                    values: Some(values(&row)),
+                    changes: Vec::new(),
+                });
+            }
+            Ordering::Greater => {
+                let row = target_rows.next()?.unwrap_or_default();
+                plan(delete_statement(target_table, &key, &row[..key.len()]))?;
+                diff.deleted += 1;
+                report(&mut diff, RowDifference {
+                    kind: RowChangeKind::Deleted,
+                    key: values(&row[..key.len()]),
+                    values: Some(values(&row)),
+                    changes: Vec::new(),
+                });
+            }
+            Ordering::Equal => {
+                let new = source_rows.next()?.unwrap_or_default();
+                let old = target_rows.next()?.unwrap_or_default();
+                let changes: Vec<ColumnDifference> = columns.iter().enumerate()
+                    .map(|(i, column)| (column, &new[key.len() + i], &old[key.len() + i]))
+                    .filter(|(_, a, b)| a != b)
+                    .map(|(column, a, b)| ColumnDifference { column: column.clone(), source: a.clone(), target: b.clone() })
+                    .collect();
+                if changes.is_empty() {
+                    diff.unchanged += 1;
+                    continue;
+                }
+                plan(update_statement(target_table, &key, &new[..key.len()], &changes))?;
+                diff.changed += 1;
+                report(&mut diff, RowDifference {
+                    kind: RowChangeKind::Changed,
+                    key: values(&new[..key.len()]),
+                    values: None,
+                    changes,
+                });
+            }
+        }
+    }
+
+    // Only a script with statements in it; a row limit of 0 leaves none
+    if planned.min(row_limit) > 0 {
+        if planned > row_limit {
+            script.push(format!("-- {} more statements are left out; raise the row limit to see them", planned - row_limit));
+        }
+        script.push("COMMIT;".to_string());
+        diff.script = script.join("\n");
+    }
+
+    Ok(diff)
+}
+
+/// The primary key both tables have, or their rowid
+fn common_key(source: &Database, source_table: &str, target: &Database, target_table: &str) -> AppResult<Vec<String>> {
+    let row_key = |db: &Database, table: &str| db.get_row_key(table).map_err(|e| AppError::BadRequest(e.to_string()));
+    let source_key = row_key(source, source_table)?.columns;
+    let target_key = row_key(target, target_table)?.columns;
+
+    let same = source_key.len() == target_key.len()
+        && source_key.iter().zip(&target_key).all(|(a, b)| a.eq_ignore_ascii_case(b));
+    if !same {
+        return Err(AppError::BadRequest(format!(
+            "{} is keyed by ({}) but {} by ({}); choose the key columns to match rows by",
+            source_table,
+            source_key.join(", "),
+            target_table,
+            target_key.join(", ")
+        )));
+    }
+    Ok(source_key)
+}
+
+/// Columns of `table` that hold stored values, i.e. not hidden or generated
+fn ordinary_columns(db: &Database, table: &str) -> AppResult<Vec<String>> {
+    let rows = db
+        .query_values(
+            "SELECT name FROM pragma_table_xinfo(?1) WHERE hidden = 0 ORDER BY cid",
+            &[CellValue::Text(table.to_string())],
+        )
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    if rows.is_empty() {
+        return Err(AppError::NotFound(format!("Table not found: {}", table)));
+    }
+    Ok(rows.into_iter()
+        .filter_map(|row| match row.into_iter().next() {
+            Some(CellValue::Text(name)) => Some(name),
+            _ => None,
+        })
+        .collect())
+}
+
+fn count_null_keys(db: &Database, table: &str, key: &[String]) -> AppResult<i64> {
+    let condition = key.iter().map(|k| format!("{} IS NULL", quote_identifier(k))).collect::<Vec<_>>().join(" OR ");
+    let rows = db
+        .query_values(&format!("SELECT COUNT(*) FROM {} WHERE {}", quote_identifier(table), condition), &[])
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+    Ok(match rows.first().and_then(|row| row.first()) {
+        Some(CellValue::Integer(n)) => *n,
+        _ => 0,
+    })
+}
+
+/// Rows of a table in key order, read in batches after the last key seen
+struct RowStream<'a> {
+    db: &'a Database,
+    first_sql: String,
+    next_sql: String,
+    key_len: usize,
+    buffer: VecDeque<Vec<CellValue>>,
+    last_key: Option<Vec<CellValue>>,
+    done: bool,
+}
+
+impl<'a> RowStream<'a> {
+    /// Keys are ordered with BINARY collation on both sides, whatever the
+    /// columns' own collations, so that the two streams line up
+    fn new(db: &'a Database, table: &str, key: &[String], columns: &[String]) -> Self {
+        let quoted = |names: &[String]| names.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>();
+        let select = quoted(key).into_iter().chain(quoted(columns)).collect::<Vec<_>>().join(", ");
+        let binary: Vec<String> = quoted(key).into_iter().map(|k| format!("{} COLLATE BINARY", k)).collect();
+        let not_null = quoted(key).into_iter().map(|k| format!("{} IS NOT NULL", k)).collect::<Vec<_>>().join(" AND ");
+        let placeholders = vec!["?"; key.len()].join(", ");
+
+        let first_sql = format!(
+            "SELECT {} FROM {} WHERE {} ORDER BY {} LIMIT {}",
+            select, quote_identifier(table), not_null, binary.join(", "), BATCH_SIZE
+        );
+        let next_sql = format!(
+            "SELECT {} FROM {} WHERE {} AND ({}) > ({}) ORDER BY {} LIMIT {}",
+            select, quote_identifier(table), not_null, binary.join(", "), placeholders, binary.join(", "), BATCH_SIZE
+        );
+
+        RowStream { db, first_sql, next_sql, key_len: key.len(), buffer: VecDeque::new(), last_key: None, done: false }
+    }
+
+    fn fill(&mut self) -> AppResult<()> {
+        if !self.buffer.is_empty() || self.done {
+            return Ok(());
+        }
+        let rows = match &self.last_key {
+            None => self.db.query_values(&self.first_sql, &[]),
+            Some(key) => self.db.query_values(&self.next_sql, key),
+        }
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+        self.done = rows.len() < BATCH_SIZE;
+        if let Some(last) = rows.last() {
+            self.last_key = Some(last[..self.key_len].to_vec());
+        }
+        self.buffer.extend(rows);
+        Ok(())
+    }
+
+    fn peek(&mut self) -> AppResult<Option<&Vec<CellValue>>> {
+        self.fill()?;
+        Ok(self.buffer.front())
+    }
+
+    fn next(&mut self) -> AppResult<Option<Vec<CellValue>>> {
+        self.fill()?;
+        Ok(self.buffer.pop_front())
+    }
+}
+
+/// Order of two keys as SQLite sorts them with BINARY collation
+fn compare_rows(a: &[CellValue], b: &[CellValue]) -> Ordering {
+    a.iter().zip(b).map(|(a, b)| compare_values(a, b)).find(|o| o.is_ne()).unwrap_or(Ordering::Equal)
+}
+
+/// NULL, then numbers, then text, then blobs, as SQLite sorts values
+fn compare_values(a: &CellValue, b: &CellValue) -> Ordering {
+    let rank = |v: &CellValue| match v {
+        CellValue::Null => 0,
+        CellValue::Integer(_) | CellValue::Real(_) => 1,
+        CellValue::Text(_) => 2,
+        CellValue::Blob(_) => 3,
+    };
+    match (a, b) {
+        (CellValue::Integer(x), CellValue::Integer(y)) => x.cmp(y),
+        (CellValue::Integer(x), CellValue::Real(y)) => (*x as f64).partial_cmp(y).unwrap_or(Ordering::Equal),
+        (CellValue::Real(x), CellValue::Integer(y)) => x.partial_cmp(&(*y as f64)).unwrap_or(Ordering::Equal),
+        (CellValue::Real(x), CellValue::Real(y)) => x.partial_cmp(y).unwrap_or(Ordering::Equal),
+        (CellValue::Text(x), CellValue::Text(y)) => x.as_bytes().cmp(y.as_bytes()),
+        (CellValue::Blob(x), CellValue::Blob(y)) => x.cmp(y),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn key_condition(key: &[String]) -> String {
+    key.iter().map(|c| format!("{} IS ?", quote_identifier(c))).collect::<Vec<_>>().join(" AND ")
+}
+
+fn insert_statement(table: &str, key: &[String], columns: &[String], row: &[CellValue]) -> SyncStatement {
+    let names: Vec<String> = key.iter().chain(columns).map(|c| quote_identifier(c)).collect();
+    SyncStatement {
+        sql: format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_identifier(table),
+            names.join(", "),
+            vec!["?"; names.len()].join(", ")
+        ),
+        params: row.to_vec(),
+    }
+}
+
+fn update_statement(table: &str, key: &[String], key_values: &[CellValue], changes: &[ColumnDifference]) -> SyncStatement {
+    let set: Vec<String> = changes.iter().map(|c| format!("{} = ?", quote_identifier(&c.column))).collect();
+    SyncStatement {
+        sql: format!("UPDATE {} SET {} WHERE {}", quote_identifier(table), set.join(", "), key_condition(key)),
+        params: changes.iter().map(|c| c.source.clone()).chain(key_values.iter().cloned()).collect(),
+    }
+}
+
+fn delete_statement(table: &str, key: &[String], key_values: &[CellValue]) -> SyncStatement {
+    SyncStatement {
+        sql: format!("DELETE FROM {} WHERE {}", quote_identifier(table), key_condition(key)),
+        params: key_values.to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_table_data() {
+        let source = Database::open(":memory:").unwrap();
+        let target = Database::open(":memory:").unwrap();
+        source.execute_batch(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, price REAL, note TEXT);
+             INSERT INTO items VALUES (1, 'a', 1.5, NULL), (2, 'b?', 2.0, 'x'), (4, 'd', 4.0, NULL);",
+        )
+        .unwrap();
+        target.execute_batch(
+            "CREATE TABLE stock (id INTEGER PRIMARY KEY, name TEXT, price REAL, qty INTEGER);
+             INSERT INTO stock VALUES (1, 'a', 1.5, 10), (2, 'B', 2.0, 5), (3, 'c', 3.0, 1);",
+        )
+        .unwrap();
+
+        let mut statements = Vec::new();
+        let diff = diff_table_data(&source, "items", &target, "stock", None, 2, |s| {
+            statements.push(s.clone());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(diff.key_columns, vec!["id"]);
+        assert_eq!(diff.columns, vec!["name", "price"]);
+        assert_eq!((diff.inserted, diff.deleted, diff.changed, diff.unchanged), (1, 1, 1, 1));
+        assert_eq!(diff.warnings.len(), 2);
+        assert!(diff.truncated);
+        assert_eq!(diff.rows[0].kind, RowChangeKind::Changed);
+        assert_eq!(diff.rows[0].changes[0].source, CellValue::Text("b?".to_string()));
+        assert!(diff.script.contains("UPDATE \"stock\" SET \"name\" = 'b?' WHERE \"id\" IS 2;"));
+        assert_eq!(statements.len(), 3);
+        assert!(diff.script.contains("-- 1 more statements are left out"));
+
+        for statement in &statements {
+            target.execute_values(&statement.sql, &statement.params).unwrap();
+        }
+        let diff = diff_table_data(&source, "items", &target, "stock", None, 10, |_| panic!("nothing to sync")).unwrap();
+        assert!(diff.script.is_empty());
+        assert_eq!(diff.unchanged, 3);
+
+        // Batches line up across text keys of mixed collations
+        source.execute_batch("CREATE TABLE k (code TEXT PRIMARY KEY COLLATE NOCASE, v INTEGER)").unwrap();
+        target.execute_batch("CREATE TABLE k (code TEXT PRIMARY KEY, v INTEGER)").unwrap();
+        for i in 0..2500 {
+            source.execute(&format!("INSERT INTO k VALUES ('{}{}', {})", if i % 2 == 0 { "a" } else { "B" }, i, i)).unwrap();
+            if i % 10 != 0 {
+                target.execute(&format!("INSERT INTO k VALUES ('{}{}', {})", if i % 2 == 0 { "a" } else { "B" }, i, i % 7)).unwrap();
+            }
+        }
+        let diff = diff_table_data(&source, "k", &target, "k", None, 0, |_| Ok(())).unwrap();
+        assert_eq!(diff.inserted, 250);
+        assert_eq!(diff.deleted, 0);
+        assert_eq!(diff.changed + diff.unchanged, 2250);
+        assert!(diff.script.is_empty());
+
+        // Applied while diffing, across batches
+        let diff = diff_table_data(&source, "k", &target, "k", None, 0, |s| {
+            target.execute_values(&s.sql, &s.params).map(|_| ()).map_err(|e| AppError::QueryError(e.to_string()))
+        })
+        .unwrap();
+        assert_eq!(diff.inserted, 250);
+        let diff = diff_table_data(&source, "k", &target, "k", None, 0, |_| panic!("nothing to sync")).unwrap();
+        assert_eq!(diff.unchanged, 2500);
+    }
+}
@@ -0,0 +1,472 @@
+//! Test Data Generation
+//!
+//! Seeded generators for realistic column values, and inference of a
+//! generator from a column's name and declared type.
+
+use crate::sqlite3x::types::CellValue;
+use chrono::{NaiveDate, NaiveDateTime};
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+use regex_syntax::hir::{Class, ClassUnicode, ClassUnicodeRange, Hir, HirKind};
+use serde::{Deserialize, Serialize};
+
+/// Random number generator used for test data; reproducible from its seed
+pub type DataRng = ChaCha8Rng;
+
+/// Extra repetitions generated for unbounded regex repetitions (`*`, `+`, `{n,}`)
+const MAX_EXTRA_REPEAT: u32 = 8;
+
+/// Longest random text a `Text` generator may produce
+const MAX_TEXT_LENGTH: usize = 1_000_000;
+
+const FIRST_NAMES: &[&str] = &[
+    "James", "Mary", "Robert", "Patricia", "John", "Jennifer", "Michael", "Linda", "David", "Elizabeth",
+    "William", "Barbara", "Richard", "Susan", "Joseph", "Jessica", "Thomas", "Sarah", "Charles", "Karen",
+    "Min-jun", "Seo-yeon", "Hiroshi", "Yuki", "Wei", "Fang", "Lucas", "Emma", "Noah", "Olivia",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Smith", "Johnson", "Williams", "Brown", "Jones", "Garcia", "Miller", "Davis", "Rodriguez", "Martinez",
+    "Kim", "Lee", "Park", "Tanaka", "Suzuki", "Wang", "Li", "Zhang", "Muller", "Schmidt",
+    "Rossi", "Dubois", "Silva", "Novak", "Nielsen", "Cohen", "Khan", "Singh", "Okafor", "Ivanova",
+];
+
+const CITIES: &[&str] = &[
+    "Seoul", "Tokyo", "New York", "London", "Paris", "Berlin", "Madrid", "Rome", "Toronto", "Sydney",
+    "Busan", "Osaka", "Chicago", "Amsterdam", "Vienna", "Prague", "Lisbon", "Dublin", "Oslo", "Austin",
+];
+
+const COUNTRIES: &[&str] = &[
+    "South Korea", "Japan", "United States", "United Kingdom", "France", "Germany", "Spain", "Italy",
+    "Canada", "Australia", "Netherlands", "Austria", "Czechia", "Portugal", "Ireland", "Norway",
+];
+
+const COMPANY_PARTS: &[&str] = &[
+    "Acme", "Globex", "Initech", "Umbrella", "Stark", "Wayne", "Hooli", "Vandelay", "Cyberdyne", "Tyrell",
+];
+
+const COMPANY_SUFFIXES: &[&str] = &["Inc.", "LLC", "Ltd.", "Group", "Systems", "Labs", "Partners"];
+
+const WORDS: &[&str] = &[
+    "alpha", "bravo", "cloud", "data", "engine", "field", "graph", "harbor", "index", "join",
+    "kernel", "ledger", "matrix", "node", "orbit", "pixel", "query", "river", "signal", "table",
+    "update", "vector", "window", "yield", "zone", "batch", "cache", "delta", "event", "frame",
+];
+
+/// Domains reserved for documentation, so generated addresses never reach anyone
+const EMAIL_DOMAINS: &[&str] = &["example.com", "example.org", "example.net"];
+
+/// How values of one column are generated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ColumnGenerator {
+    FirstName,
+    LastName,
+    FullName,
+    Email,
+    Phone,
+    Company,
+    City,
+    Country,
+    Word,
+    Sentence,
+    Uuid,
+    Boolean,
+    Integer { min: i64, max: i64 },
+    Real { min: f64, max: f64 },
+    /// Random letters of a length within the bounds
+    Text {
+        #[serde(rename = "minLength")]
+        min_length: usize,
+        #[serde(rename = "maxLength")]
+        max_length: usize,
+    },
+    /// Dates formatted as `YYYY-MM-DD`, bounds inclusive
+    Date { start: String, end: String },
+    /// Timestamps formatted as `YYYY-MM-DD HH:MM:SS`, bounds inclusive
+    DateTime { start: String, end: String },
+    /// One of the given values
+    Enum { values: Vec<CellValue> },
+    /// Strings matching a regular expression
+    Pattern { regex: String },
+    /// Consecutive integers
+    Sequence { start: i64, step: i64 },
+    Constant { value: CellValue },
+    Null,
+    /// Leave the column out so the database fills in its default
+    Default,
+}
+
+/// A generator ready to produce values
+pub struct ValueGenerator {
+    kind: ColumnGenerator,
+    pattern: Option<Hir>,
+    range: Option<(NaiveDateTime, NaiveDateTime)>,
+    /// Values produced so far, used for sequences and unique suffixes
+    counter: i64,
+}
+
+impl ValueGenerator {
+    /// Validate a generator's parameters
+    pub fn new(kind: ColumnGenerator) -> Result<Self, String> {
+        let mut pattern = None;
+        let mut range = None;
+
+        match &kind {
+            ColumnGenerator::Integer { min, max } if min > max => {
+                return Err(format!("Integer range is empty: {}..{}", min, max));
+            }
+            // The width must be finite too, or sampling overflows
+            ColumnGenerator::Real { min, max } if min > max || !(max - min).is_finite() => {
+                return Err(format!("Real range is invalid: {}..{}", min, max));
+            }
+            ColumnGenerator::Text { min_length, max_length } if min_length > max_length => {
+                return Err(format!("Text length range is empty: {}..{}", min_length, max_length));
+            }
+            ColumnGenerator::Text { max_length, .. } if *max_length > MAX_TEXT_LENGTH => {
+                return Err(format!("Text can be at most {} characters long", MAX_TEXT_LENGTH));
+            }
+            ColumnGenerator::Enum { values } if values.is_empty() => {
+                return Err("Enum generator needs at least one value".to_string());
+            }
+            ColumnGenerator::Date { start, end } | ColumnGenerator::DateTime { start, end } => {
+                let start = parse_datetime(start)?;
+                let end = parse_datetime(end)?;
+                if start > end {
+                    return Err("Date range is empty".to_string());
+                }
+                range = Some((start, end));
+            }
+            ColumnGenerator::Pattern { regex } => {
+                let hir = regex_syntax::Parser::new()
+                    .parse(regex)
+                    .map_err(|e| format!("Invalid pattern: {}", e))?;
+                pattern = Some(hir);
+            }
+            _ => {}
+        }
+
+        Ok(Self { kind, pattern, range, counter: 0 })
+    }
+
+    /// Whether the column should be left to its default
+    pub fn is_default(&self) -> bool {
+        matches!(self.kind, ColumnGenerator::Default)
+    }
+
+    /// Whether every value produced is distinct without any help
+    pub fn is_distinct(&self) -> bool {
+        matches!(self.kind, ColumnGenerator::Sequence { .. } | ColumnGenerator::Uuid)
+    }
+
+    /// Whether `next` keeps values from repeating when asked to
+    pub fn can_be_unique(&self) -> bool {
+        matches!(
+            self.kind,
+            ColumnGenerator::FirstName
+                | ColumnGenerator::LastName
+                | ColumnGenerator::FullName
+                | ColumnGenerator::Email
+                | ColumnGenerator::Phone
+                | ColumnGenerator::Company
+                | ColumnGenerator::City
+                | ColumnGenerator::Country
+                | ColumnGenerator::Word
+                | ColumnGenerator::Sentence
+                | ColumnGenerator::Text { .. }
+        ) || self.is_distinct()
+    }
+
+    /// Produce the next value
+    ///
+    /// With `unique`, text values get a running suffix so they don't repeat.
+    pub fn next(&mut self, rng: &mut DataRng, unique: bool) -> CellValue {
+        let n = self.counter;
+        self.counter += 1;
+
+        let text = |s: String| {
+            if unique {
+                CellValue::Text(format!("{}-{}", s, n + 1))
+            } else {
+                CellValue::Text(s)
+            }
+        };
+
+        match &self.kind {
+            ColumnGenerator::FirstName => text(pick(rng, FIRST_NAMES).to_string()),
+            ColumnGenerator::LastName => text(pick(rng, LAST_NAMES).to_string()),
+            ColumnGenerator::FullName => {
+                text(format!("{} {}", pick(rng, FIRST_NAMES), pick(rng, LAST_NAMES)))
+            }
+            ColumnGenerator::Email => {
+                let local = format!(
+                    "{}.{}",
+                    pick(rng, FIRST_NAMES).to_lowercase(),
+                    pick(rng, LAST_NAMES).to_lowercase()
+                );
+                let local = if unique { format!("{}{}", local, n + 1) } else { local };
+                CellValue::Text(format!("{}@{}", local, pick(rng, EMAIL_DOMAINS)))
+            }
+            ColumnGenerator::Phone => text(format!(
+                "+1-555-{:03}-{:04}",
+                rng.gen_range(100..1000),
+                rng.gen_range(0..10000)
+            )),
+            ColumnGenerator::Company => {
+                text(format!("{} {}", pick(rng, COMPANY_PARTS), pick(rng, COMPANY_SUFFIXES)))
+            }
+            ColumnGenerator::City => text(pick(rng, CITIES).to_string()),
+            ColumnGenerator::Country => text(pick(rng, COUNTRIES).to_string()),
+            ColumnGenerator::Word => text(pick(rng, WORDS).to_string()),
+            ColumnGenerator::Sentence => {
+                let count = rng.gen_range(4..10);
+                let words: Vec<&str> = (0..count).map(|_| pick(rng, WORDS)).collect();
+                let mut sentence = words.join(" ");
+                if let Some(first) = sentence.get_mut(0..1) {
+                    first.make_ascii_uppercase();
+                }
+                sentence.push('.');
+                text(sentence)
+            }
+            ColumnGenerator::Uuid => {
+                let bytes: [u8; 16] = rng.gen();
+                CellValue::Text(uuid::Builder::from_random_bytes(bytes).into_uuid().to_string())
+            }
+            ColumnGenerator::Boolean => CellValue::Integer(rng.gen_range(0..=1)),
+            ColumnGenerator::Integer { min, max } => CellValue::Integer(rng.gen_range(*min..=*max)),
+            ColumnGenerator::Real { min, max } => {
+                let value = if min == max { *min } else { rng.gen_range(*min..*max) };
+                CellValue::Real((value * 100.0).round() / 100.0)
+            }
+            ColumnGenerator::Text { min_length, max_length } => {
+                let length = rng.gen_range(*min_length..=*max_length);
+                text((0..length).map(|_| rng.gen_range(b'a'..=b'z') as char).collect())
+            }
+            ColumnGenerator::Date { .. } | ColumnGenerator::DateTime { .. } => {
+                let (start, end) = self.range.expect("validated in new");
+                let span = (end - start).num_seconds();
+                let at = start + chrono::Duration::seconds(rng.gen_range(0..=span));
+                if matches!(self.kind, ColumnGenerator::Date { .. }) {
+                    CellValue::Text(at.format("%Y-%m-%d").to_string())
+                } else {
+                    CellValue::Text(at.format("%Y-%m-%d %H:%M:%S").to_string())
+                }
+            }
+            ColumnGenerator::Enum { values } => values[rng.gen_range(0..values.len())].clone(),
+            ColumnGenerator::Pattern { .. } => {
+                let mut out = String::new();
+                generate_from_hir(self.pattern.as_ref().expect("validated in new"), rng, &mut out);
+                CellValue::Text(out)
+            }
+            ColumnGenerator::Sequence { start, step } => CellValue::Integer(start + step * n),
+            ColumnGenerator::Constant { value } => value.clone(),
+            ColumnGenerator::Null | ColumnGenerator::Default => CellValue::Null,
+        }
+    }
+}
+
+/// Pick a generator for a column from its name and declared type
+pub fn infer_generator(column: &str, declared_type: &str) -> ColumnGenerator {
+    let name = column.to_lowercase();
+    let declared = declared_type.to_uppercase();
+    let has = |words: &[&str]| words.iter().any(|w| name.contains(w));
+
+    // Declared types that pin the value format come first
+    if declared.contains("BOOL") {
+        return ColumnGenerator::Boolean;
+    }
+    if declared.contains("DATETIME") || declared.contains("TIMESTAMP") {
+        return recent_datetimes();
+    }
+    if declared.contains("DATE") {
+        return ColumnGenerator::Date { start: "2020-01-01".to_string(), end: "2025-12-31".to_string() };
+    }
+
+    match affinity(&declared) {
+        Affinity::Integer => {
+            if has(&["age"]) {
+                ColumnGenerator::Integer { min: 18, max: 90 }
+            } else if has(&["year"]) {
+                ColumnGenerator::Integer { min: 1990, max: 2030 }
+            } else if name.starts_with("is_") || name.starts_with("has_") {
+                ColumnGenerator::Boolean
+            } else {
+                ColumnGenerator::Integer { min: 0, max: 10_000 }
+            }
+        }
+        Affinity::Real | Affinity::Numeric => {
+            if has(&["price", "amount", "cost", "total", "balance"]) {
+                ColumnGenerator::Real { min: 1.0, max: 1000.0 }
+            } else if has(&["lat"]) {
+                ColumnGenerator::Real { min: -90.0, max: 90.0 }
+            } else if has(&["lon", "lng"]) {
+                ColumnGenerator::Real { min: -180.0, max: 180.0 }
+            } else {
+                ColumnGenerator::Real { min: 0.0, max: 1000.0 }
+            }
+        }
+        Affinity::Text | Affinity::Blob => {
+            if has(&["email", "mail"]) {
+                ColumnGenerator::Email
+            } else if has(&["first_name", "firstname", "given_name"]) {
+                ColumnGenerator::FirstName
+            } else if has(&["last_name", "lastname", "surname", "family_name"]) {
+                ColumnGenerator::LastName
+            } else if has(&["company", "organization", "organisation"]) {
+                ColumnGenerator::Company
+            } else if has(&["name", "author", "owner"]) {
+                ColumnGenerator::FullName
+            } else if has(&["phone", "mobile", "tel"]) {
+                ColumnGenerator::Phone
+            } else if has(&["city", "town"]) {
+                ColumnGenerator::City
+            } else if has(&["country"]) {
+                ColumnGenerator::Country
+            } else if has(&["uuid", "guid"]) {
+                ColumnGenerator::Uuid
+            } else if name.ends_with("_at") || has(&["time"]) {
+                recent_datetimes()
+            } else if has(&["date", "birthday"]) {
+                ColumnGenerator::Date { start: "1970-01-01".to_string(), end: "2025-12-31".to_string() }
+            } else if has(&["url", "website", "link"]) {
+                ColumnGenerator::Pattern { regex: "https://www\\.example\\.com/[a-z]{4,10}".to_string() }
+            } else if has(&["description", "comment", "note", "body", "content", "message", "text"]) {
+                ColumnGenerator::Sentence
+            } else {
+                ColumnGenerator::Word
+            }
+        }
+    }
+}
+
+fn recent_datetimes() -> ColumnGenerator {
+    ColumnGenerator::DateTime {
+        start: "2020-01-01 00:00:00".to_string(),
+        end: "2025-12-31 23:59:59".to_string(),
+    }
+}
+
+/// SQLite column affinity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affinity {
+    Integer,
+    Text,
+    Blob,
+    Real,
+    Numeric,
+}
+
+/// Affinity of a declared column type, following SQLite's rules
+pub fn affinity(declared_type: &str) -> Affinity {
+    let declared = declared_type.to_uppercase();
+
+    if declared.contains("INT") {
+        Affinity::Integer
+    } else if declared.contains("CHAR") || declared.contains("CLOB") || declared.contains("TEXT") {
+        Affinity::Text
+    } else if declared.is_empty() || declared.contains("BLOB") {
+        Affinity::Blob
+    } else if declared.contains("REAL") || declared.contains("FLOA") || declared.contains("DOUB") {
+        Affinity::Real
+    } else {
+        Affinity::Numeric
+    }
+}
+
+fn pick<'a>(rng: &mut DataRng, items: &[&'a str]) -> &'a str {
+    items[rng.gen_range(0..items.len())]
+}
+
+fn parse_datetime(s: &str) -> Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .map(|d| d.and_hms_opt(0, 0, 0).expect("midnight is valid"))
+        })
+        .map_err(|_| format!("Invalid date: {} (expected YYYY-MM-DD or YYYY-MM-DD HH:MM:SS)", s))
+}
+
+/// Append a random string matching `hir` to `out`
+fn generate_from_hir(hir: &Hir, rng: &mut DataRng, out: &mut String) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => out.push_str(&String::from_utf8_lossy(&literal.0)),
+        HirKind::Class(Class::Unicode(class)) => {
+            // Prefer printable ASCII, so `.` and `\w` give readable text
+            let mut printable = ClassUnicode::new([ClassUnicodeRange::new(' ', '~')]);
+            printable.intersect(class);
+            let class = if printable.ranges().is_empty() { class } else { &printable };
+
+            if let Some(c) = pick_from_ranges(rng, class.ranges().iter().map(|r| (r.start() as u32, r.end() as u32))) {
+                out.push(c);
+            }
+        }
+        HirKind::Class(Class::Bytes(class)) => {
+            let ranges = class.ranges().iter().map(|r| (r.start() as u32, r.end().min(0x7f) as u32));
+            if let Some(c) = pick_from_ranges(rng, ranges.filter(|(start, end)| start <= end)) {
+                out.push(c);
+            }
+        }
+        HirKind::Repetition(repetition) => {
+            let max = repetition
+                .max
+                .unwrap_or(repetition.min + MAX_EXTRA_REPEAT)
+                .min(repetition.min + MAX_EXTRA_REPEAT);
+            for _ in 0..rng.gen_range(repetition.min..=max) {
+                generate_from_hir(&repetition.sub, rng, out);
+            }
+        }
+        HirKind::Capture(capture) => generate_from_hir(&capture.sub, rng, out),
+        HirKind::Concat(parts) => {
+            for part in parts {
+                generate_from_hir(part, rng, out);
+            }
+        }
+        HirKind::Alternation(choices) => {
+            generate_from_hir(&choices[rng.gen_range(0..choices.len())], rng, out);
+        }
+    }
+}
+
+/// Pick a character uniformly from inclusive code point ranges
+fn pick_from_ranges(rng: &mut DataRng, ranges: impl Iterator<Item = (u32, u32)>) -> Option<char> {
+    let ranges: Vec<(u32, u32)> = ranges.collect();
+    let total: u64 = ranges.iter().map(|(start, end)| (end - start) as u64 + 1).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut index = rng.gen_range(0..total);
+    for (start, end) in ranges {
+        let size = (end - start) as u64 + 1;
+        if index < size {
+            return char::from_u32(start + index as u32);
+        }
+        index -= size;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_rejects_unsampleable_ranges() {
+        let real = |min, max| ValueGenerator::new(ColumnGenerator::Real { min, max });
+        assert!(real(-1e308, 1e308).is_err());
+        assert!(real(f64::NEG_INFINITY, 0.0).is_err());
+        assert!(real(0.0, f64::NAN).is_err());
+
+        let mut generator = real(-1e307, 1e307).unwrap();
+        let mut rng = DataRng::seed_from_u64(1);
+        assert!(matches!(generator.next(&mut rng, false), CellValue::Real(_)));
+
+        let text = |max_length| ValueGenerator::new(ColumnGenerator::Text { min_length: 0, max_length });
+        assert!(text(usize::MAX).is_err());
+        assert!(text(MAX_TEXT_LENGTH + 1).is_err());
+        assert!(text(MAX_TEXT_LENGTH).is_ok());
+    }
+}
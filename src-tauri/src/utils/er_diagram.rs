@@ -0,0 +1,383 @@
+//! ER Diagram Export
+//!
+//! Renders tables, their keys and foreign key relationships as Mermaid,
+//! Graphviz DOT or PlantUML text.
+
+use super::{AppError, AppResult};
+use crate::sqlite3x::wrapper::{ColumnInfo, ForeignKeyConstraint, SchemaInfo, TableInfo, TableKind};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Output syntax of `export_er_diagram`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagramFormat {
+    Mermaid,
+    Dot,
+    Plantuml,
+}
+
+/// Which part of the schema to draw
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErDiagramOptions {
+    /// Tables to start from; all tables when absent
+    pub tables: Option<Vec<String>>,
+    /// Also draw tables up to this many foreign key hops from `tables`
+    #[serde(default)]
+    pub depth: u32,
+    /// "main" when absent, or the name of an attached database
+    pub schema: Option<String>,
+}
+
+/// A foreign key between two drawn tables
+struct Relationship<'a> {
+    child: &'a TableInfo,
+    parent: &'a TableInfo,
+    constraint: &'a ForeignKeyConstraint,
+    /// Every child row has a parent: all key columns are NOT NULL
+    required: bool,
+    /// At most one child row per parent: the key columns are unique
+    unique: bool,
+    /// The key columns are part of the child's primary key
+    identifying: bool,
+}
+
+/// Render the tables selected by `options` in `format`
+pub fn render_er_diagram(schema: &SchemaInfo, format: DiagramFormat, options: &ErDiagramOptions) -> AppResult<String> {
+    let tables = select_tables(schema, options)?;
+    let relationships = relationships(schema, &tables);
+    let ids = diagram_ids(&tables);
+
+    Ok(match format {
+        DiagramFormat::Mermaid => mermaid(schema, &tables, &relationships, &ids),
+        DiagramFormat::Dot => dot(schema, &tables, &relationships),
+        DiagramFormat::Plantuml => plantuml(schema, &tables, &relationships, &ids),
+    })
+}
+
+/// The requested tables plus their neighbors within `depth` hops, in schema order
+fn select_tables<'a>(schema: &'a SchemaInfo, options: &ErDiagramOptions) -> AppResult<Vec<&'a TableInfo>> {
+    let candidates: Vec<&TableInfo> = schema.tables.iter().filter(|t| t.kind != TableKind::Shadow).collect();
+    let Some(requested) = &options.tables else {
+        return Ok(candidates);
+    };
+
+    let find = |name: &str| candidates.iter().copied().find(|t| t.name.eq_ignore_ascii_case(name));
+
+    let mut selected: HashSet<String> = HashSet::new();
+    let mut queue: VecDeque<(&TableInfo, u32)> = VecDeque::new();
+    for name in requested {
+        let table = find(name).ok_or_else(|| AppError::NotFound(format!("Table not found: {}", name)))?;
+        if selected.insert(table.name.to_lowercase()) {
+            queue.push_back((table, 0));
+        }
+    }
+
+    // Breadth-first over foreign keys in both directions
+    while let Some((table, hops)) = queue.pop_front() {
+        if hops == options.depth {
+            continue;
+        }
+        let neighbors = table.foreign_keys.iter().map(|fk| fk.referenced_table.as_str())
+            .chain(table.referenced_by.iter().map(|fk| fk.table.as_str()));
+        for neighbor in neighbors.filter_map(find) {
+            if selected.insert(neighbor.name.to_lowercase()) {
+                queue.push_back((neighbor, hops + 1));
+            }
+        }
+    }
+
+    Ok(candidates.into_iter().filter(|t| selected.contains(&t.name.to_lowercase())).collect())
+}
+
+fn relationships<'a>(schema: &'a SchemaInfo, tables: &[&'a TableInfo]) -> Vec<Relationship<'a>> {
+    let mut relationships = Vec::new();
+
+    for &child in tables {
+        for constraint in &child.foreign_keys {
+            let Some(&parent) = tables.iter().find(|t| t.name.eq_ignore_ascii_case(&constraint.referenced_table)) else {
+                continue;
+            };
+            let columns: Vec<&ColumnInfo> = constraint.columns.iter()
+                .filter_map(|pair| child.columns.iter().find(|c| c.name.eq_ignore_ascii_case(&pair.from)))
+                .collect();
+
+            relationships.push(Relationship {
+                child,
+                parent,
+                constraint,
+                required: !columns.is_empty() && columns.iter().all(|c| c.not_null || c.primary_key),
+                unique: is_unique(schema, child, constraint.columns.iter().map(|pair| pair.from.as_str()).collect()),
+                identifying: !columns.is_empty() && columns.iter().all(|c| c.primary_key),
+            });
+        }
+    }
+
+    relationships
+}
+
+/// Whether `columns` together are the primary key or a full unique index of `table`
+fn is_unique(schema: &SchemaInfo, table: &TableInfo, columns: Vec<&str>) -> bool {
+    let same = |keys: &[&str]| {
+        keys.len() == columns.len() && keys.iter().all(|k| columns.iter().any(|c| c.eq_ignore_ascii_case(k)))
+    };
+
+    let primary_key: Vec<&str> = table.columns.iter().filter(|c| c.primary_key).map(|c| c.name.as_str()).collect();
+    if same(&primary_key) {
+        return true;
+    }
+
+    schema.indexes.iter()
+        .filter(|i| i.unique && !i.partial && i.table_name.eq_ignore_ascii_case(&table.name))
+        .any(|i| {
+            let keys: Vec<&str> = i.key_columns.iter().filter_map(|c| c.name.as_deref()).collect();
+            keys.len() == i.key_columns.len() && same(&keys)
+        })
+}
+
+/// Key markers of a column: PK, FK and UK for single-column unique keys
+fn column_keys(schema: &SchemaInfo, table: &TableInfo, column: &ColumnInfo) -> Vec<&'static str> {
+    let mut keys = Vec::new();
+    if column.primary_key {
+        keys.push("PK");
+    }
+    if table.foreign_keys.iter().any(|fk| fk.columns.iter().any(|pair| pair.from.eq_ignore_ascii_case(&column.name))) {
+        keys.push("FK");
+    }
+    if !column.primary_key && is_unique(schema, table, vec![column.name.as_str()]) {
+        keys.push("UK");
+    }
+    keys
+}
+
+/// Identifiers for tables whose names the Mermaid and PlantUML grammars don't accept
+fn diagram_ids(tables: &[&TableInfo]) -> HashMap<String, String> {
+    let mut ids = HashMap::new();
+    let mut taken = HashSet::new();
+
+    for table in tables {
+        let mut id = bare_word(&table.name);
+        if id.is_empty() || id.starts_with(|c: char| c.is_ascii_digit()) {
+            id = format!("t_{}", id);
+        }
+        let base = id.clone();
+        let mut suffix = 1;
+        while !taken.insert(id.to_lowercase()) {
+            suffix += 1;
+            id = format!("{}_{}", base, suffix);
+        }
+        ids.insert(table.name.clone(), id);
+    }
+
+    ids
+}
+
+/// `text` with every character other than ASCII letters, digits and `_` replaced by `_`
+fn bare_word(text: &str) -> String {
+    text.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect()
+}
+
+fn visible_columns(table: &TableInfo) -> impl Iterator<Item = &ColumnInfo> {
+    table.columns.iter().filter(|c| !c.hidden)
+}
+
+fn display_type(column: &ColumnInfo) -> &str {
+    if column.data_type.is_empty() { "ANY" } else { &column.data_type }
+}
+
+fn foreign_key_label(constraint: &ForeignKeyConstraint) -> String {
+    constraint.columns.iter().map(|pair| pair.from.as_str()).collect::<Vec<_>>().join(", ")
+}
+
+/// Crow's foot ends shared by Mermaid and PlantUML: `parent ||--o{ child`
+fn crows_foot(relationship: &Relationship) -> String {
+    format!(
+        "{}{}{}",
+        if relationship.required { "||" } else { "|o" },
+        if relationship.identifying { "--" } else { ".." },
+        if relationship.unique { "o|" } else { "o{" },
+    )
+}
+
+fn mermaid(schema: &SchemaInfo, tables: &[&TableInfo], relationships: &[Relationship], ids: &HashMap<String, String>) -> String {
+    let mut lines = vec!["erDiagram".to_string()];
+
+    for table in tables {
+        let id = &ids[&table.name];
+        if *id == table.name {
+            lines.push(format!("    {} {{", id));
+        } else {
+            lines.push(format!("    {}[\"{}\"] {{", id, table.name.replace('"', "'")));
+        }
+        for column in visible_columns(table) {
+            let mut line = format!("        {} {}", bare_word(display_type(column)), bare_word(&column.name));
+            let keys = column_keys(schema, table, column);
+            if !keys.is_empty() {
+                line.push(' ');
+                line.push_str(&keys.join(", "));
+            }
+            if bare_word(&column.name) != column.name {
+                line.push_str(&format!(" \"{}\"", column.name.replace('"', "'")));
+            }
+            lines.push(line);
+        }
+        lines.push("    }".to_string());
+    }
+
+    for relationship in relationships {
+        lines.push(format!(
+            "    {} {} {} : \"{}\"",
+            ids[&relationship.parent.name],
+            crows_foot(relationship),
+            ids[&relationship.child.name],
+            foreign_key_label(relationship.constraint).replace('"', "'"),
+        ));
+    }
+
+    lines.join("\n")
+}
+
+fn dot(schema: &SchemaInfo, tables: &[&TableInfo], relationships: &[Relationship]) -> String {
+    let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    let html = |text: &str| text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;");
+    let port = |table: &TableInfo, column: &str| {
+        visible_columns(table).position(|c| c.name.eq_ignore_ascii_case(column)).map(|i| format!(":c{}", i)).unwrap_or_default()
+    };
+
+    let mut lines = vec![
+        "digraph schema {".to_string(),
+        "    graph [rankdir=LR];".to_string(),
+        "    node [shape=plaintext, fontname=\"Helvetica\"];".to_string(),
+        "    edge [dir=both, fontname=\"Helvetica\", fontsize=10];".to_string(),
+    ];
+
+    for table in tables {
+        let mut label = format!(
+            "<TABLE BORDER=\"0\" CELLBORDER=\"1\" CELLSPACING=\"0\" CELLPADDING=\"4\"><TR><TD BGCOLOR=\"lightgrey\"><B>{}</B></TD></TR>",
+            html(&table.name)
+        );
+        for (i, column) in visible_columns(table).enumerate() {
+            let keys = column_keys(schema, table, column);
+            let name = if column.primary_key { format!("<U>{}</U>", html(&column.name)) } else { html(&column.name) };
+            label.push_str(&format!(
+                "<TR><TD PORT=\"c{}\" ALIGN=\"LEFT\">{} : {}{}</TD></TR>",
+                i,
+                name,
+                html(display_type(column)),
+                if keys.is_empty() { String::new() } else { format!(" [{}]", keys.join(", ")) },
+            ));
+        }
+        label.push_str("</TABLE>");
+        lines.push(format!("    {} [label=<{}>];", quote(&table.name), label));
+    }
+
+    for relationship in relationships {
+        let first = relationship.constraint.columns.first();
+        lines.push(format!(
+            "    {}{} -> {}{} [arrowtail={}, arrowhead={}, style={}, label={}];",
+            quote(&relationship.child.name),
+            first.map(|pair| port(relationship.child, &pair.from)).unwrap_or_default(),
+            quote(&relationship.parent.name),
+            first.map(|pair| port(relationship.parent, &pair.to)).unwrap_or_default(),
+            if relationship.unique { "teeodot" } else { "crowodot" },
+            if relationship.required { "teetee" } else { "teeodot" },
+            if relationship.identifying { "solid" } else { "dashed" },
+            quote(&foreign_key_label(relationship.constraint)),
+        ));
+    }
+
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+fn plantuml(schema: &SchemaInfo, tables: &[&TableInfo], relationships: &[Relationship], ids: &HashMap<String, String>) -> String {
+    let mut lines = vec![
+        "@startuml".to_string(),
+        "hide circle".to_string(),
+        "skinparam linetype ortho".to_string(),
+    ];
+
+    for table in tables {
+        lines.push(String::new());
+        lines.push(format!("entity \"{}\" as {} {{", table.name.replace('"', "'"), ids[&table.name]));
+
+        // Primary key columns above the separator, the rest below
+        let (key, rest): (Vec<&ColumnInfo>, Vec<&ColumnInfo>) = visible_columns(table).partition(|c| c.primary_key);
+        let line = |column: &ColumnInfo| {
+            let keys = column_keys(schema, table, column);
+            format!(
+                "  {}{} : {}{}",
+                if column.not_null || column.primary_key { "* " } else { "" },
+                column.name,
+                display_type(column),
+                keys.iter().map(|k| format!(" <<{}>>", k)).collect::<String>(),
+            )
+        };
+        lines.extend(key.iter().map(|c| line(c)));
+        if !key.is_empty() && !rest.is_empty() {
+            lines.push("  --".to_string());
+        }
+        lines.extend(rest.iter().map(|c| line(c)));
+        lines.push("}".to_string());
+    }
+
+    if !relationships.is_empty() {
+        lines.push(String::new());
+    }
+    for relationship in relationships {
+        lines.push(format!(
+            "{} {} {} : {}",
+            ids[&relationship.parent.name],
+            crows_foot(relationship),
+            ids[&relationship.child.name],
+            foreign_key_label(relationship.constraint),
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push("@enduml".to_string());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlite3x::wrapper::Database;
+
+    #[test]
+    fn test_render_er_diagram() {
+        let db = Database::open(":memory:").unwrap();
+        db.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL UNIQUE);
+             CREATE TABLE profiles (user_id INTEGER PRIMARY KEY REFERENCES users(id), bio TEXT);
+             CREATE TABLE posts (id INTEGER PRIMARY KEY, author_id INTEGER NOT NULL REFERENCES users(id));
+             CREATE TABLE comments (id INTEGER PRIMARY KEY, post_id INTEGER REFERENCES posts(id));
+             CREATE TABLE \"audit log\" (id INTEGER PRIMARY KEY, message TEXT);",
+        )
+        .unwrap();
+        let schema = db.get_schema().unwrap();
+
+        let mermaid = render_er_diagram(&schema, DiagramFormat::Mermaid, &ErDiagramOptions::default()).unwrap();
+        assert!(mermaid.starts_with("erDiagram"));
+        assert!(mermaid.contains("audit_log[\"audit log\"] {"));
+        assert!(mermaid.contains("        TEXT email UK"));
+        assert!(mermaid.contains("        INTEGER user_id PK, FK"));
+        assert!(mermaid.contains("users ||--o| profiles : \"user_id\""));
+        assert!(mermaid.contains("users ||..o{ posts : \"author_id\""));
+        assert!(mermaid.contains("posts |o..o{ comments : \"post_id\""));
+
+        // One hop from posts reaches users and comments, but not profiles
+        let options = ErDiagramOptions { tables: Some(vec!["posts".to_string()]), depth: 1, schema: None };
+        let plantuml = render_er_diagram(&schema, DiagramFormat::Plantuml, &options).unwrap();
+        assert!(plantuml.contains("entity \"comments\" as comments {"));
+        assert!(plantuml.contains("entity \"users\" as users {"));
+        assert!(!plantuml.contains("profiles"));
+
+        let dot = render_er_diagram(&schema, DiagramFormat::Dot, &options).unwrap();
+        assert!(dot.contains("\"posts\":c1 -> \"users\":c0"));
+
+        let missing = ErDiagramOptions { tables: Some(vec!["nope".to_string()]), ..Default::default() };
+        assert!(render_er_diagram(&schema, DiagramFormat::Dot, &missing).is_err());
+    }
+}
@@ -29,6 +29,9 @@ pub enum AppError {
 
     #[error("Not found: {0}")]
     NotFound(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
 }
 
 // Implement Serialize so we can return this error from Tauri commands
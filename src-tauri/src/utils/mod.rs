@@ -4,7 +4,15 @@
 
 mod serialization;
 mod error;
+mod sql;
+pub mod data_diff;
+pub mod data_generator;
+pub mod er_diagram;
 pub mod schema_diff;
+pub mod schema_docs;
+pub mod schema_search;
+pub mod sidecar;
 
 pub use serialization::*;
 pub use error::*;
+pub use sql::*;
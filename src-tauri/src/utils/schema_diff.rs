@@ -1,5 +1,11 @@
 use serde::{Serialize, Deserialize};
-use crate::sqlite3x::wrapper::{SchemaInfo, TableInfo};
+use crate::sqlite3x::ddl::{self, TokenKind};
+use crate::sqlite3x::dependencies::{DependencyGraph, ObjectKind};
+use crate::sqlite3x::rebuild::rebuild_table;
+use crate::sqlite3x::table_definition::{column_sql, constraint_sql, ColumnDefinition, TableConstraint, TableDefinition};
+use crate::sqlite3x::wrapper::{Database, SchemaInfo, TableInfo, TableKind};
+use crate::utils::{quote_identifier, AppError, AppResult};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -7,93 +13,811 @@ pub struct SchemaDiffResult {
     pub tables_to_create: Vec<String>, // SQL statements
     pub tables_to_drop: Vec<String>,
     pub columns_to_add: Vec<String>,
-    pub columns_to_drop: Vec<String>, // Note: SQLite doesn't support DROP COLUMN in older versions easily, but 3.35.0+ does.
+    pub columns_to_drop: Vec<String>, // ALTER TABLE DROP COLUMN, or table rebuilds where that can't drop them
+    pub tables_to_rebuild: Vec<String>, // Rebuilds for changed columns or constraints; run with foreign keys off
     pub indexes_to_create: Vec<String>,
     pub indexes_to_drop: Vec<String>,
+    pub views_to_create: Vec<String>,
+    pub views_to_drop: Vec<String>,
+    pub triggers_to_create: Vec<String>,
+    pub triggers_to_drop: Vec<String>,
+    /// Objects in both schemas whose definitions differ
+    pub modified: Vec<ModifiedObject>,
+    /// ALTER TABLE ... RENAME statements for confirmed renames
+    pub renames_to_apply: Vec<String>,
+    /// Dropped and created tables and columns that look like renames
+    pub rename_candidates: Vec<RenameCandidate>,
+    /// All of the above in the order to run: drop dependents, alter, recreate
+    pub migration: Vec<String>,
     pub summary: String,
 }
 
+/// An object whose definition differs between the schemas
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifiedObject {
+    pub kind: ObjectKind,
+    pub name: String,
+    /// Definition in the target, i.e. now
+    pub before: Option<String>,
+    /// Definition in the source, i.e. after the migration
+    pub after: Option<String>,
+    /// What changed, e.g. "column age: type TEXT -> INTEGER"
+    pub changes: Vec<String>,
+}
+
+/// A table or column rename, from its name in the target to its name in the source
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Rename {
+    /// Target table of a column rename; `None` when the table itself is renamed
+    pub table: Option<String>,
+    pub from: String,
+    pub to: String,
+}
+
+/// A rename the diff suspects, for the user to confirm
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameCandidate {
+    pub table: Option<String>,
+    pub from: String,
+    pub to: String,
+    /// 0-100; how closely the columns, or the column's type, constraints and position, match
+    pub confidence: u32,
+}
+
+/// Least confidence for a rename to be suggested
+const MIN_RENAME_CONFIDENCE: u32 = 60;
+
+/// What has to happen to a table that exists in both schemas
+enum TableChange {
+    /// ALTER TABLE ADD COLUMN and DROP COLUMN are enough
+    Alter { added: Vec<ColumnDefinition>, dropped: Vec<String> },
+    Rebuild { definition: TableDefinition, drops_columns: bool },
+}
+
 pub fn compare_schemas(source: &SchemaInfo, target: &SchemaInfo) -> SchemaDiffResult {
     let mut diff = SchemaDiffResult {
         tables_to_create: Vec::new(),
         tables_to_drop: Vec::new(),
         columns_to_add: Vec::new(),
         columns_to_drop: Vec::new(),
+        tables_to_rebuild: Vec::new(),
         indexes_to_create: Vec::new(),
         indexes_to_drop: Vec::new(),
+        views_to_create: Vec::new(),
+        views_to_drop: Vec::new(),
+        triggers_to_create: Vec::new(),
+        triggers_to_drop: Vec::new(),
+        modified: Vec::new(),
+        renames_to_apply: Vec::new(),
+        rename_candidates: Vec::new(),
+        migration: Vec::new(),
         summary: String::new(),
     };
 
-    // 1. Tables
-    let source_tables: std::collections::HashMap<_, _> = source.tables.iter().map(|t| (&t.name, t)).collect();
-    let target_tables: std::collections::HashMap<_, _> = target.tables.iter().map(|t| (&t.name, t)).collect();
-
-    // Tables in source but not in target -> Create in Target (or vice-versa depending on direction)
     // We assume "source" is what we WANT, and "target" is what we HAVE.
     // So we generate SQL to update "target" to match "source".
+    // Names are looked up case-insensitively, as SQLite does.
+    let source_graph = DependencyGraph::build(source);
+    let target_graph = DependencyGraph::build(target);
+
+    // 1. Tables; shadow tables are created and dropped with their virtual table
+    let source_tables: HashMap<String, &TableInfo> = source.tables.iter()
+        .filter(|t| t.kind != TableKind::Shadow)
+        .map(|t| (t.name.to_lowercase(), t))
+        .collect();
+    let target_tables: HashMap<String, &TableInfo> = target.tables.iter()
+        .filter(|t| t.kind != TableKind::Shadow)
+        .map(|t| (t.name.to_lowercase(), t))
+        .collect();
 
-    for (name, table) in &source_tables {
-        if !target_tables.contains_key(name) {
+    let mut rebuilds: Vec<(&TableInfo, TableDefinition, bool)> = Vec::new();
+    let mut dropping_columns: Vec<&str> = Vec::new();
+    for table in source.tables.iter().filter(|t| t.kind != TableKind::Shadow) {
+        let Some(target_table) = target_tables.get(&table.name.to_lowercase()) else {
             if let Some(sql) = &table.sql {
                 diff.tables_to_create.push(format!("{};", sql));
             }
-        } else {
-            // Table exists in both, compare columns
-            let target_table = target_tables.get(name).unwrap();
-            compare_columns(table, target_table, &mut diff);
+            continue;
+        };
+
+        // Virtual tables cannot be altered
+        if table.kind != TableKind::Table || target_table.kind != TableKind::Table {
+            if !same_sql(table.sql.as_deref(), target_table.sql.as_deref()) {
+                diff.modified.push(ModifiedObject {
+                    kind: ObjectKind::Table,
+                    name: target_table.name.clone(),
+                    before: target_table.sql.clone(),
+                    after: table.sql.clone(),
+                    changes: vec!["Virtual table definition changed; drop and recreate it to apply".to_string()],
+                });
+            }
+            continue;
+        }
+
+        match compare_table(table, target_table) {
+            Ok(None) => {}
+            Ok(Some((change, changes))) => {
+                diff.modified.push(ModifiedObject {
+                    kind: ObjectKind::Table,
+                    name: target_table.name.clone(),
+                    before: target_table.sql.clone(),
+                    after: table.sql.clone(),
+                    changes,
+                });
+                match change {
+                    TableChange::Alter { added, dropped } => {
+                        for column in &added {
+                            diff.columns_to_add.push(format!(
+                                "ALTER TABLE {} ADD COLUMN {};",
+                                quote_identifier(&target_table.name),
+                                column_sql(column)
+                            ));
+                        }
+                        if !dropped.is_empty() {
+                            dropping_columns.push(&target_table.name);
+                        }
+                        for column in &dropped {
+                            diff.columns_to_drop.push(format!(
+                                "ALTER TABLE {} DROP COLUMN {};",
+                                quote_identifier(&target_table.name),
+                                quote_identifier(column)
+                            ));
+                        }
+                    }
+                    TableChange::Rebuild { definition, drops_columns } => {
+                        rebuilds.push((target_table, definition, drops_columns));
+                    }
+                }
+            }
+            Err(e) => diff.tables_to_rebuild.push(format!("-- Cannot compare {}: {}", target_table.name, e)),
         }
     }
 
-    for (name, _table) in &target_tables {
-        if !source_tables.contains_key(name) {
-            diff.tables_to_drop.push(format!("DROP TABLE IF EXISTS {};", name));
+    let mut dropped_tables = Vec::new();
+    for table in target.tables.iter().filter(|t| t.kind != TableKind::Shadow) {
+        if !source_tables.contains_key(&table.name.to_lowercase()) {
+            diff.tables_to_drop.push(format!("DROP TABLE IF EXISTS {};", quote_identifier(&table.name)));
+            dropped_tables.push(table.name.as_str());
         }
     }
 
-    // 2. Indexes
-    let source_indexes: std::collections::HashMap<_, _> = source.indexes.iter().map(|i| (&i.name, i)).collect();
-    let target_indexes: std::collections::HashMap<_, _> = target.indexes.iter().map(|i| (&i.name, i)).collect();
+    // 2. Indexes; those created for constraints go with their table
+    let source_indexes: HashMap<String, _> = source.indexes.iter().map(|i| (i.name.to_lowercase(), i)).collect();
+    let target_indexes: HashMap<String, _> = target.indexes.iter().map(|i| (i.name.to_lowercase(), i)).collect();
+    let mut dropped_indexes: HashSet<String> = HashSet::new();
 
-    for (name, index) in &source_indexes {
-        if !target_indexes.contains_key(name) {
-            if let Some(sql) = &index.sql {
+    for index in &source.indexes {
+        let Some(sql) = &index.sql else { continue };
+        match target_indexes.get(&index.name.to_lowercase()) {
+            None => diff.indexes_to_create.push(format!("{};", sql)),
+            Some(existing) if !same_sql(Some(sql), existing.sql.as_deref()) => {
+                diff.modified.push(ModifiedObject {
+                    kind: ObjectKind::Index,
+                    name: existing.name.clone(),
+                    before: existing.sql.clone(),
+                    after: Some(sql.clone()),
+                    changes: vec!["Index definition changed".to_string()],
+                });
+                if existing.sql.is_some() {
+                    diff.indexes_to_drop.push(format!("DROP INDEX IF EXISTS {};", quote_identifier(&existing.name)));
+                    dropped_indexes.insert(existing.name.to_lowercase());
+                }
                 diff.indexes_to_create.push(format!("{};", sql));
             }
+            Some(_) => {}
+        }
+    }
+
+    for index in &target.indexes {
+        if index.sql.is_some() && !source_indexes.contains_key(&index.name.to_lowercase()) {
+            diff.indexes_to_drop.push(format!("DROP INDEX IF EXISTS {};", quote_identifier(&index.name)));
+            dropped_indexes.insert(index.name.to_lowercase());
+        }
+    }
+
+    // 3. Views and triggers that are gone or changed
+    let source_views: HashMap<String, _> = source.views.iter().map(|v| (v.name.to_lowercase(), v)).collect();
+    let source_triggers: HashMap<String, _> = source.triggers.iter().map(|t| (t.name.to_lowercase(), t)).collect();
+    let mut dropped: HashSet<String> = HashSet::new();
+    let mut changed_views: Vec<&str> = Vec::new();
+
+    for view in &target.views {
+        match source_views.get(&view.name.to_lowercase()) {
+            None => {
+                dropped.insert(view.name.to_lowercase());
+                changed_views.push(&view.name);
+            }
+            Some(wanted) if !same_sql(wanted.sql.as_deref(), view.sql.as_deref()) => {
+                diff.modified.push(ModifiedObject {
+                    kind: ObjectKind::View,
+                    name: view.name.clone(),
+                    before: view.sql.clone(),
+                    after: wanted.sql.clone(),
+                    changes: vec!["View definition changed".to_string()],
+                });
+                dropped.insert(view.name.to_lowercase());
+                changed_views.push(&view.name);
+            }
+            Some(_) => {}
         }
     }
 
-    for (name, _index) in &target_indexes {
-        if !source_indexes.contains_key(name) {
-            diff.indexes_to_drop.push(format!("DROP INDEX IF EXISTS {};", name));
+    for trigger in &target.triggers {
+        match source_triggers.get(&trigger.name.to_lowercase()) {
+            None => {
+                dropped.insert(trigger.name.to_lowercase());
+            }
+            Some(wanted) if !same_sql(wanted.sql.as_deref(), trigger.sql.as_deref()) => {
+                diff.modified.push(ModifiedObject {
+                    kind: ObjectKind::Trigger,
+                    name: trigger.name.clone(),
+                    before: trigger.sql.clone(),
+                    after: wanted.sql.clone(),
+                    changes: vec!["Trigger definition changed".to_string()],
+                });
+                dropped.insert(trigger.name.to_lowercase());
+            }
+            Some(_) => {}
+        }
+    }
+
+    // Views and triggers that use a rebuilt, dropped or changed object go
+    // too; a table rebuild, or ALTER TABLE DROP COLUMN, fails while any view
+    // or trigger names a missing object
+    let seeds = rebuilds.iter().map(|(t, _, _)| t.name.as_str())
+        .chain(dropping_columns.iter().copied())
+        .chain(dropped_tables.iter().copied())
+        .chain(changed_views.iter().copied());
+    for seed in seeds {
+        for edge in target_graph.view_and_trigger_dependents(seed) {
+            if matches!(edge.dependent_kind, ObjectKind::View | ObjectKind::Trigger) {
+                dropped.insert(edge.dependent.to_lowercase());
+            }
         }
     }
 
-    diff.summary = format!(
-        "Found {} tables to create, {} tables to drop, {} indexes to create.",
+    for trigger in target.triggers.iter().filter(|t| dropped.contains(&t.name.to_lowercase())) {
+        diff.triggers_to_drop.push(format!("DROP TRIGGER IF EXISTS {};", quote_identifier(&trigger.name)));
+    }
+    let views: Vec<&str> = target.views.iter()
+        .filter(|v| dropped.contains(&v.name.to_lowercase()))
+        .map(|v| v.name.as_str())
+        .collect();
+    for view in ordered_views(&target_graph, &views).into_iter().rev() {
+        diff.views_to_drop.push(format!("DROP VIEW IF EXISTS {};", quote_identifier(view)));
+    }
+
+    // 4. Rebuilds, against the target without what was dropped above
+    let mut remaining = target.clone();
+    remaining.views.retain(|v| !dropped.contains(&v.name.to_lowercase()));
+    remaining.triggers.retain(|t| !dropped.contains(&t.name.to_lowercase()));
+    remaining.indexes.retain(|i| !dropped_indexes.contains(&i.name.to_lowercase()));
+    remaining.tables.retain(|t| !dropped_tables.iter().any(|d| d.eq_ignore_ascii_case(&t.name)));
+
+    for (table, definition, drops_columns) in &rebuilds {
+        let statements = if *drops_columns { &mut diff.columns_to_drop } else { &mut diff.tables_to_rebuild };
+        match rebuild_table(&remaining, &table.name, definition, &Default::default()) {
+            Ok(script) => {
+                statements.extend(script.warnings.iter().map(|w| format!("-- {}", w)));
+                statements.extend(script.statements.iter().map(|s| format!("{};", s)));
+            }
+            Err(e) => statements.push(format!("-- Cannot rebuild {}: {}", table.name, e)),
+        }
+    }
+
+    // 5. Views and triggers of the source that are new or were dropped above
+    let target_views: HashSet<String> = target.views.iter().map(|v| v.name.to_lowercase()).collect();
+    let target_triggers: HashSet<String> = target.triggers.iter().map(|t| t.name.to_lowercase()).collect();
+    let recreate = |name: &str, existing: &HashSet<String>| {
+        let name = name.to_lowercase();
+        !existing.contains(&name) || dropped.contains(&name)
+    };
+
+    let views: Vec<&str> = source.views.iter()
+        .filter(|v| recreate(&v.name, &target_views))
+        .map(|v| v.name.as_str())
+        .collect();
+    for name in ordered_views(&source_graph, &views) {
+        if let Some(sql) = source_views.get(&name.to_lowercase()).and_then(|v| v.sql.as_ref()) {
+            diff.views_to_create.push(format!("{};", sql));
+        }
+    }
+    for trigger in source.triggers.iter().filter(|t| recreate(&t.name, &target_triggers)) {
+        if let Some(sql) = &trigger.sql {
+            diff.triggers_to_create.push(format!("{};", sql));
+        }
+    }
+
+    // Drop dependents, alter, recreate
+    let rebuilding = !diff.columns_to_drop.is_empty() || !diff.tables_to_rebuild.is_empty();
+    if rebuilding {
+        diff.migration.push("PRAGMA foreign_keys = OFF;".to_string());
+    }
+    for statements in [
+        &diff.triggers_to_drop,
+        &diff.views_to_drop,
+        &diff.indexes_to_drop,
+        &diff.tables_to_drop,
+        &diff.tables_to_create,
+        &diff.columns_to_add,
+        &diff.columns_to_drop,
+        &diff.tables_to_rebuild,
+        &diff.indexes_to_create,
+        &diff.views_to_create,
+        &diff.triggers_to_create,
+    ] {
+        diff.migration.extend(statements.iter().cloned());
+    }
+    if rebuilding {
+        diff.migration.push("PRAGMA foreign_keys = ON;".to_string());
+    }
+
+    diff.rename_candidates = rename_candidates(source, target);
+    diff.summary = summary(&diff);
+
+    diff
+}
+
+/// Like `compare_schemas`, with confirmed `renames` applied to the target first
+///
+/// The renames run on an in-memory copy of the target, so views, triggers,
+/// indexes and foreign keys are rewritten exactly as SQLite will rewrite
+/// them, and the migration starts with the same ALTER TABLE statements.
+pub fn compare_schemas_with_renames(source: &SchemaInfo, target: &SchemaInfo, renames: &[Rename]) -> AppResult<SchemaDiffResult> {
+    if renames.is_empty() {
+        return Ok(compare_schemas(source, target));
+    }
+
+    let statements = rename_statements(renames);
+    let copy = replay_schema(target)?;
+    for statement in &statements {
+        copy.execute(statement)
+            .map_err(|e| AppError::BadRequest(format!("Cannot apply rename: {}\n{}", e, statement)))?;
+    }
+    let renamed = copy.get_schema()
+        .map_err(|e| AppError::QueryError(format!("{:?}", e)))?;
+
+    let mut diff = compare_schemas(source, &renamed);
+    let renames_at = usize::from(diff.migration.first().is_some_and(|s| s.starts_with("PRAGMA")));
+    diff.migration.splice(renames_at..renames_at, statements.iter().map(|s| format!("{};", s)));
+    diff.renames_to_apply = statements.into_iter().map(|s| format!("{};", s)).collect();
+    diff.summary = summary(&diff);
+    Ok(diff)
+}
+
+fn summary(diff: &SchemaDiffResult) -> String {
+    format!(
+        "Found {} tables to create, {} tables to drop, {} indexes to create, {} views to create, {} triggers to create, {} modified objects and {} renames.",
         diff.tables_to_create.len(),
         diff.tables_to_drop.len(),
-        diff.indexes_to_create.len()
-    );
+        diff.indexes_to_create.len(),
+        diff.views_to_create.len(),
+        diff.triggers_to_create.len(),
+        diff.modified.len(),
+        diff.renames_to_apply.len()
+    )
+}
 
-    diff
+/// ALTER TABLE statements for `renames`: tables first, then columns under the tables' new names
+fn rename_statements(renames: &[Rename]) -> Vec<String> {
+    let table_renames: Vec<&Rename> = renames.iter().filter(|r| r.table.is_none()).collect();
+    let mut statements: Vec<String> = table_renames.iter()
+        .map(|r| format!("ALTER TABLE {} RENAME TO {}", quote_identifier(&r.from), quote_identifier(&r.to)))
+        .collect();
+    for rename in renames {
+        let Some(table) = &rename.table else { continue };
+        let table = table_renames.iter()
+            .find(|r| r.from.eq_ignore_ascii_case(table))
+            .map(|r| &r.to)
+            .unwrap_or(table);
+        statements.push(format!(
+            "ALTER TABLE {} RENAME COLUMN {} TO {}",
+            quote_identifier(table),
+            quote_identifier(&rename.from),
+            quote_identifier(&rename.to)
+        ));
+    }
+    statements
+}
+
+/// An in-memory database with the same schema as `schema`
+fn replay_schema(schema: &SchemaInfo) -> AppResult<Database> {
+    let db = Database::open(":memory:")
+        .map_err(|e| AppError::InternalError(format!("{:?}", e)))?;
+    let graph = DependencyGraph::build(schema);
+    let views: Vec<&str> = schema.views.iter().map(|v| v.name.as_str()).collect();
+    let views = ordered_views(&graph, &views);
+
+    let statements = schema.tables.iter()
+        .filter(|t| t.kind != TableKind::Shadow)
+        .filter_map(|t| t.sql.as_deref())
+        .chain(schema.indexes.iter().filter_map(|i| i.sql.as_deref()))
+        .chain(views.iter().filter_map(|name| schema.views.iter().find(|v| v.name == *name)?.sql.as_deref()))
+        .chain(schema.triggers.iter().filter_map(|t| t.sql.as_deref()));
+    for statement in statements {
+        db.execute(statement)
+            .map_err(|e| AppError::BadRequest(format!("Cannot copy the target schema: {}\n{}", e, statement)))?;
+    }
+    Ok(db)
 }
 
-fn compare_columns(source: &TableInfo, target: &TableInfo, diff: &mut SchemaDiffResult) {
-    let source_cols: std::collections::HashMap<_, _> = source.columns.iter().map(|c| (&c.name, c)).collect();
-    let target_cols: std::collections::HashMap<_, _> = target.columns.iter().map(|c| (&c.name, c)).collect();
+/// Dropped and created tables with similar columns, and dropped and added
+/// columns of the same table with the same type, best match first
+fn rename_candidates(source: &SchemaInfo, target: &SchemaInfo) -> Vec<RenameCandidate> {
+    fn ordinary(schema: &SchemaInfo) -> HashMap<String, &TableInfo> {
+        schema.tables.iter()
+            .filter(|t| t.kind == TableKind::Table)
+            .map(|t| (t.name.to_lowercase(), t))
+            .collect()
+    }
+    let source_tables = ordinary(source);
+    let target_tables = ordinary(target);
 
-    for (name, col) in &source_cols {
-        if !target_cols.contains_key(name) {
-            let mut sql = format!("ALTER TABLE {} ADD COLUMN {} {}", source.name, col.name, col.data_type);
-            if col.not_null { sql.push_str(" NOT NULL"); }
-            if let Some(def) = &col.default_value { sql.push_str(&format!(" DEFAULT {}", def)); }
-            diff.columns_to_add.push(format!("{};", sql));
+    let mut scored: Vec<RenameCandidate> = Vec::new();
+    for old in target_tables.values().filter(|t| !source_tables.contains_key(&t.name.to_lowercase())) {
+        for new in source_tables.values().filter(|t| !target_tables.contains_key(&t.name.to_lowercase())) {
+            let confidence = table_similarity(old, new);
+            if confidence >= MIN_RENAME_CONFIDENCE {
+                scored.push(RenameCandidate { table: None, from: old.name.clone(), to: new.name.clone(), confidence });
+            }
         }
     }
 
-    for (name, _col) in &target_cols {
-        if !source_cols.contains_key(name) {
-            diff.columns_to_drop.push(format!("-- SQLite 3.35.0+ supports: ALTER TABLE {} DROP COLUMN {};", source.name, name));
+    for (name, new) in &source_tables {
+        let Some(old) = target_tables.get(name) else { continue };
+        let has = |table: &TableInfo, column: &str| table.columns.iter().any(|c| c.name.eq_ignore_ascii_case(column));
+        for (old_position, old_column) in old.columns.iter().enumerate().filter(|(_, c)| !has(new, &c.name)) {
+            for (new_position, new_column) in new.columns.iter().enumerate().filter(|(_, c)| !has(old, &c.name)) {
+                if !old_column.data_type.eq_ignore_ascii_case(&new_column.data_type) {
+                    continue;
+                }
+                let mut confidence = 40;
+                if old_column.not_null == new_column.not_null { confidence += 10; }
+                if old_column.primary_key == new_column.primary_key { confidence += 10; }
+                if old_column.default_value == new_column.default_value { confidence += 10; }
+                if old_position == new_position { confidence += 30; }
+                if confidence >= MIN_RENAME_CONFIDENCE {
+                    scored.push(RenameCandidate {
+                        table: Some(old.name.clone()),
+                        from: old_column.name.clone(),
+                        to: new_column.name.clone(),
+                        confidence,
+                    });
+                }
+            }
+        }
+    }
+
+    // Each name is used by its best match only
+    scored.sort_by(|a, b| b.confidence.cmp(&a.confidence).then_with(|| a.from.cmp(&b.from)).then_with(|| a.to.cmp(&b.to)));
+    let mut used: HashSet<(Option<String>, String)> = HashSet::new();
+    scored.into_iter()
+        .filter(|c| {
+            let table = c.table.as_ref().map(|t| t.to_lowercase());
+            let from = (table.clone(), format!("from:{}", c.from.to_lowercase()));
+            let to = (table, format!("to:{}", c.to.to_lowercase()));
+            if used.contains(&from) || used.contains(&to) {
+                return false;
+            }
+            used.insert(from);
+            used.insert(to);
+            true
+        })
+        .collect()
+}
+
+/// 0-100 from shared column names and, position by position, matching types
+fn table_similarity(old: &TableInfo, new: &TableInfo) -> u32 {
+    let names = |table: &TableInfo| -> HashSet<String> { table.columns.iter().map(|c| c.name.to_lowercase()).collect() };
+    let (old_names, new_names) = (names(old), names(new));
+    let union = old_names.union(&new_names).count().max(1);
+    let shared = old_names.intersection(&new_names).count();
+
+    let longest = old.columns.len().max(new.columns.len()).max(1);
+    let same_types = old.columns.iter().zip(&new.columns)
+        .filter(|(a, b)| a.data_type.eq_ignore_ascii_case(&b.data_type) && a.not_null == b.not_null && a.primary_key == b.primary_key)
+        .count();
+
+    let score = 60.0 * shared as f64 / union as f64 + 40.0 * same_types as f64 / longest as f64;
+    // Identical columns are still only a guess
+    (score.round() as u32).min(95)
+}
+
+/// How `target` has to change to match `source`, with the changes described
+fn compare_table(
+    source: &TableInfo,
+    target: &TableInfo,
+) -> crate::sqlite3x::errors::Sqlite3xResult<Option<(TableChange, Vec<String>)>> {
+    let mut wanted = TableDefinition::from_schema(source)?;
+    let current = TableDefinition::from_schema(target)?;
+    wanted.name = current.name.clone();
+    if wanted == current {
+        return Ok(None);
+    }
+
+    let changes = describe_changes(&current, &wanted);
+    let has_column = |definition: &TableDefinition, name: &str| {
+        definition.columns.iter().any(|c| c.name.eq_ignore_ascii_case(name))
+    };
+
+    // Dropped columns, and new columns at the end, that ALTER TABLE can
+    // handle in place need no rebuild
+    let added: Vec<ColumnDefinition> = wanted.columns.iter()
+        .filter(|c| !has_column(&current, &c.name))
+        .cloned()
+        .collect();
+    let dropped: Vec<&ColumnDefinition> = current.columns.iter()
+        .filter(|c| !has_column(&wanted, &c.name))
+        .collect();
+    let mut altered = current.clone();
+    altered.columns.retain(|c| has_column(&wanted, &c.name));
+    altered.columns.extend(added.iter().cloned());
+    if altered == wanted
+        && added.iter().all(can_add_column)
+        && dropped.iter().all(|c| can_drop_column(&current, c))
+    {
+        let dropped = dropped.iter().map(|c| c.name.clone()).collect();
+        return Ok(Some((TableChange::Alter { added, dropped }, changes)));
+    }
+
+    let drops_columns = !dropped.is_empty();
+    Ok(Some((TableChange::Rebuild { definition: wanted, drops_columns }, changes)))
+}
+
+/// Whether ALTER TABLE ADD COLUMN can add `column`
+fn can_add_column(column: &ColumnDefinition) -> bool {
+    let stored = column.generated.as_ref()
+        .is_some_and(|g| g.kind == crate::sqlite3x::wrapper::GeneratedKind::Stored);
+    !column.primary_key
+        && !column.unique
+        && !stored
+        && (!column.not_null || column.default_value.is_some() || column.generated.is_some())
+}
+
+/// Whether ALTER TABLE DROP COLUMN can drop `column` from `table`
+///
+/// Indexes that use the column are dropped earlier in the migration, as they
+/// differ from the source, and so are the views and triggers on the table.
+fn can_drop_column(table: &TableDefinition, column: &ColumnDefinition) -> bool {
+    let names = |sql: &str| -> bool {
+        ddl::tokenize(sql).iter()
+            .filter(|t| matches!(t.kind, TokenKind::Word | TokenKind::QuotedIdent))
+            .filter_map(|t| t.identifier())
+            .any(|name| name.eq_ignore_ascii_case(&column.name))
+    };
+    let in_constraint = table.constraints.iter().any(|constraint| match constraint {
+        TableConstraint::PrimaryKey { columns, .. }
+        | TableConstraint::Unique { columns, .. }
+        | TableConstraint::ForeignKey { columns, .. } => columns.iter().any(|c| c.eq_ignore_ascii_case(&column.name)),
+        TableConstraint::Check { expression, .. } => names(expression),
+    });
+    // Other columns' CHECK and generated expressions; its own go with it
+    let in_expression = table.columns.iter()
+        .filter(|c| !c.name.eq_ignore_ascii_case(&column.name))
+        .any(|c| c.check.as_deref().is_some_and(names) || c.generated.as_ref().is_some_and(|g| names(&g.expression)));
+
+    !column.primary_key && !column.unique && !in_constraint && !in_expression
+}
+
+/// One line per difference between two definitions of a table
+fn describe_changes(before: &TableDefinition, after: &TableDefinition) -> Vec<String> {
+    let mut changes = Vec::new();
+    let find = |definition: &'_ TableDefinition, name: &str| {
+        definition.columns.iter().find(|c| c.name.eq_ignore_ascii_case(name)).cloned()
+    };
+
+    for column in &after.columns {
+        let Some(old) = find(before, &column.name) else {
+            changes.push(format!("column {} added", column.name));
+            continue;
+        };
+        if old == *column {
+            continue;
+        }
+        let mut specific = old.clone();
+        if !old.data_type.eq_ignore_ascii_case(&column.data_type) {
+            changes.push(format!("column {}: type {} -> {}", column.name, type_name(&old.data_type), type_name(&column.data_type)));
+        }
+        specific.data_type = column.data_type.clone();
+        if old.not_null != column.not_null {
+            changes.push(format!("column {}: NOT NULL {}", column.name, if column.not_null { "added" } else { "removed" }));
         }
+        specific.not_null = column.not_null;
+        if old.default_value != column.default_value {
+            changes.push(format!(
+                "column {}: default {} -> {}",
+                column.name,
+                old.default_value.as_deref().unwrap_or("none"),
+                column.default_value.as_deref().unwrap_or("none")
+            ));
+        }
+        specific.default_value = column.default_value.clone();
+        specific.name = column.name.clone();
+        if specific != *column {
+            changes.push(format!("column {}: {} -> {}", column.name, column_sql(&old), column_sql(column)));
+        }
+    }
+    for column in before.columns.iter().filter(|c| find(after, &c.name).is_none()) {
+        changes.push(format!("column {} dropped", column.name));
+    }
+
+    let before_order: Vec<String> = before.columns.iter().filter(|c| find(after, &c.name).is_some()).map(|c| c.name.to_lowercase()).collect();
+    let after_order: Vec<String> = after.columns.iter().filter(|c| find(before, &c.name).is_some()).map(|c| c.name.to_lowercase()).collect();
+    if before_order != after_order {
+        changes.push("column order changed".to_string());
+    }
+
+    for constraint in after.constraints.iter().filter(|c| !before.constraints.contains(c)) {
+        changes.push(format!("constraint added: {}", constraint_sql(constraint)));
+    }
+    for constraint in before.constraints.iter().filter(|c| !after.constraints.contains(c)) {
+        changes.push(format!("constraint removed: {}", constraint_sql(constraint)));
+    }
+    if before.strict != after.strict {
+        changes.push(format!("STRICT {}", if after.strict { "added" } else { "removed" }));
+    }
+    if before.without_rowid != after.without_rowid {
+        changes.push(format!("WITHOUT ROWID {}", if after.without_rowid { "added" } else { "removed" }));
+    }
+
+    changes
+}
+
+fn type_name(data_type: &str) -> &str {
+    if data_type.trim().is_empty() { "(none)" } else { data_type.trim() }
+}
+
+/// Whether two statements are the same apart from whitespace, comments,
+/// identifier quoting and the case of words
+fn same_sql(a: Option<&str>, b: Option<&str>) -> bool {
+    let normalize = |sql: &str| -> Vec<String> {
+        ddl::tokenize(sql).iter()
+            .filter(|t| !t.is_punct(';'))
+            .map(|t| match t.kind {
+                TokenKind::Word | TokenKind::QuotedIdent => t.identifier().unwrap_or_default().to_lowercase(),
+                _ => t.text.to_string(),
+            })
+            .collect()
+    };
+    match (a, b) {
+        (Some(a), Some(b)) => normalize(a) == normalize(b),
+        (a, b) => a == b,
+    }
+}
+
+/// `views` ordered so that each comes after the views of the list it selects from
+fn ordered_views<'a>(graph: &DependencyGraph, views: &[&'a str]) -> Vec<&'a str> {
+    let mut ordered: Vec<&str> = Vec::with_capacity(views.len());
+    let mut placed: HashSet<String> = HashSet::new();
+    while ordered.len() < views.len() {
+        let before = ordered.len();
+        for view in views {
+            if placed.contains(&view.to_lowercase()) {
+                continue;
+            }
+            let ready = graph.dependencies(view).iter().all(|d| {
+                placed.contains(&d.target.to_lowercase())
+                    || !views.iter().any(|v| v.eq_ignore_ascii_case(&d.target))
+                    || d.target.eq_ignore_ascii_case(view)
+            });
+            if ready {
+                placed.insert(view.to_lowercase());
+                ordered.push(view);
+            }
+        }
+        if ordered.len() == before {
+            ordered.extend(views.iter().filter(|v| !placed.contains(&v.to_lowercase())));
+            break;
+        }
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlite3x::wrapper::Database;
+
+    #[test]
+    fn test_compare_schemas() {
+        let current = Database::open(":memory:").unwrap();
+        current.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, age TEXT);
+             CREATE TABLE logs (id INTEGER PRIMARY KEY, message TEXT);
+             CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT UNIQUE, legacy TEXT);
+             CREATE INDEX idx_users_name ON users (name);
+             CREATE VIEW adults AS SELECT * FROM users WHERE age >= 18;
+             CREATE VIEW adult_names AS SELECT name FROM adults;
+             CREATE VIEW log_count AS SELECT count(*) FROM logs;
+             CREATE VIEW tag_names AS SELECT name FROM tags;
+             CREATE TRIGGER users_log AFTER INSERT ON users BEGIN INSERT INTO logs (message) VALUES (NEW.name); END;
+             INSERT INTO users VALUES (1, 'ann', '30'), (2, 'bob', '12');",
+        )
+        .unwrap();
+
+        let wanted = Database::open(":memory:").unwrap();
+        wanted.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL DEFAULT '', age INTEGER);
+             CREATE TABLE logs (id INTEGER PRIMARY KEY, message TEXT, level TEXT);
+             CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT UNIQUE);
+             CREATE INDEX idx_users_name ON users (name, age);
+             CREATE VIEW adults AS SELECT * FROM users WHERE age >= 18;
+             CREATE VIEW adult_names AS SELECT name FROM adults;
+             CREATE VIEW log_count AS SELECT count(*) AS total FROM logs;
+             CREATE VIEW tag_names AS SELECT name FROM tags;
+             CREATE TRIGGER users_log AFTER INSERT ON users BEGIN INSERT INTO logs (message) VALUES (NEW.name); END;",
+        )
+        .unwrap();
+
+        let diff = compare_schemas(&wanted.get_schema().unwrap(), &current.get_schema().unwrap());
+
+        let users = diff.modified.iter().find(|m| m.name == "users").unwrap();
+        assert!(users.changes.contains(&"column age: type TEXT -> INTEGER".to_string()));
+        assert!(users.changes.contains(&"column name: NOT NULL added".to_string()));
+        assert!(diff.modified.iter().any(|m| m.kind == ObjectKind::Index && m.name == "idx_users_name"));
+        assert!(diff.modified.iter().any(|m| m.kind == ObjectKind::View && m.name == "log_count"));
+        assert!(!diff.modified.iter().any(|m| m.name == "adults" || m.name == "users_log"));
+        assert_eq!(diff.columns_to_add, vec!["ALTER TABLE \"logs\" ADD COLUMN \"level\" TEXT;"]);
+        assert_eq!(diff.columns_to_drop, vec!["ALTER TABLE \"tags\" DROP COLUMN \"legacy\";"]);
+
+        // Dependents of users are dropped before the rebuild and recreated after it
+        let position = |prefix: &str| diff.migration.iter().position(|s| s.starts_with(prefix)).unwrap();
+        assert!(position("DROP VIEW IF EXISTS \"adult_names\"") < position("DROP VIEW IF EXISTS \"adults\""));
+        assert!(position("DROP TRIGGER IF EXISTS \"users_log\"") < position("CREATE TABLE \"users_new\""));
+        assert!(position("CREATE VIEW adults") < position("CREATE VIEW adult_names"));
+        assert!(position("CREATE TABLE \"users_new\"") < position("CREATE VIEW adults"));
+        // and so are those of a table losing a column
+        assert!(position("DROP VIEW IF EXISTS \"tag_names\"") < position("ALTER TABLE \"tags\" DROP COLUMN"));
+        assert!(position("ALTER TABLE \"tags\" DROP COLUMN") < position("CREATE VIEW tag_names"));
+
+        current.execute_batch(&diff.migration.join("\n")).unwrap();
+        let after = compare_schemas(&wanted.get_schema().unwrap(), &current.get_schema().unwrap());
+        assert!(after.migration.is_empty(), "{:?}", after.migration);
+        assert_eq!(current.query_values("SELECT * FROM adult_names", &[]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_renames() {
+        let current = Database::open(":memory:").unwrap();
+        current.execute_batch(
+            "CREATE TABLE people (id INTEGER PRIMARY KEY, name TEXT NOT NULL, born TEXT);
+             CREATE TABLE posts (id INTEGER PRIMARY KEY, author INTEGER REFERENCES people(id), body TEXT);
+             CREATE VIEW names AS SELECT name FROM people;
+             INSERT INTO people VALUES (1, 'ann', '1990');",
+        )
+        .unwrap();
+
+        let wanted = Database::open(":memory:").unwrap();
+        wanted.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, born TEXT);
+             CREATE TABLE posts (id INTEGER PRIMARY KEY, author INTEGER REFERENCES users(id), content TEXT);
+             CREATE VIEW names AS SELECT name FROM users;",
+        )
+        .unwrap();
+        let (source, target) = (wanted.get_schema().unwrap(), current.get_schema().unwrap());
+
+        let diff = compare_schemas(&source, &target);
+        let table = diff.rename_candidates.iter().find(|c| c.table.is_none()).unwrap();
+        assert_eq!((table.from.as_str(), table.to.as_str(), table.confidence), ("people", "users", 95));
+        let column = diff.rename_candidates.iter().find(|c| c.table.is_some()).unwrap();
+        assert_eq!((column.from.as_str(), column.to.as_str(), column.confidence), ("body", "content", 100));
+
+        // Confirmed, the renames replace the drops and keep the rows
+        let renames: Vec<Rename> = diff.rename_candidates.iter()
+            .map(|c| Rename { table: c.table.clone(), from: c.from.clone(), to: c.to.clone() })
+            .collect();
+        let diff = compare_schemas_with_renames(&source, &target, &renames).unwrap();
+        assert_eq!(diff.renames_to_apply, [
+            "ALTER TABLE \"people\" RENAME TO \"users\";",
+            "ALTER TABLE \"posts\" RENAME COLUMN \"body\" TO \"content\";",
+        ]);
+        assert!(diff.tables_to_drop.is_empty() && diff.rename_candidates.is_empty());
+
+        current.execute_batch(&diff.migration.join("\n")).unwrap();
+        assert!(compare_schemas(&source, &current.get_schema().unwrap()).migration.is_empty());
+        assert_eq!(current.query_values("SELECT * FROM names", &[]).unwrap().len(), 1);
     }
 }
@@ -0,0 +1,599 @@
+//! Schema Documentation
+//!
+//! Renders a data dictionary of a database as one Markdown file or as a
+//! static HTML site with a page per table and view.
+
+use super::sidecar::ObjectDescription;
+use crate::sqlite3x::dependencies::{DependencyGraph, DependencyKind};
+use crate::sqlite3x::table_definition::{ColumnDefinition, TableConstraint, TableDefinition};
+use crate::sqlite3x::wrapper::{ColumnInfo, GeneratedKind, IndexOrigin, SchemaInfo, TableInfo, TableKind, ViewInfo};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Output of `generate_schema_docs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocsFormat {
+    /// One Markdown file
+    Markdown,
+    /// A directory with `index.html` and a page per table and view
+    Html,
+}
+
+/// Everything a data dictionary shows
+pub struct SchemaDocs<'a> {
+    pub title: String,
+    pub schema: &'a SchemaInfo,
+    /// Row counts by table as (count, estimated)
+    pub row_counts: HashMap<String, (i64, bool)>,
+    /// Bytes used by tables and indexes, by name
+    pub sizes: HashMap<String, i64>,
+    pub descriptions: &'a BTreeMap<String, ObjectDescription>,
+}
+
+/// Inline content; links name the table or view they point at
+enum Inline {
+    Text(String),
+    Code(String),
+    Link(String, String),
+}
+
+enum Block {
+    /// Markdown heading level, the object it is the page title of, and its text
+    Heading(usize, Option<String>, String),
+    Paragraph(Vec<Inline>),
+    List(Vec<Vec<Inline>>),
+    Table(Vec<&'static str>, Vec<Vec<Vec<Inline>>>),
+    Code(String),
+}
+
+/// A table or view page
+struct Page {
+    name: String,
+    blocks: Vec<Block>,
+}
+
+fn text(value: impl Into<String>) -> Inline {
+    Inline::Text(value.into())
+}
+
+fn code(value: impl Into<String>) -> Inline {
+    Inline::Code(value.into())
+}
+
+impl<'a> SchemaDocs<'a> {
+    /// The whole dictionary as one Markdown document
+    pub fn to_markdown(&self) -> String {
+        let slugs = self.slugs();
+        let link = |name: &str| format!("#{}", slugs[&name.to_lowercase()]);
+
+        let mut out = render_markdown(&self.overview(), &slugs, &link);
+        for page in self.tables().iter().map(|t| self.table_page(t)).chain(self.views().map(|v| self.view_page(v))) {
+            out.push_str("\n---\n\n");
+            out.push_str(&render_markdown(&page.blocks, &slugs, &link));
+        }
+        out
+    }
+
+    /// The dictionary as (file name, content) pairs: `index.html`, `style.css` and a page per object
+    pub fn to_html_site(&self) -> Vec<(String, String)> {
+        let slugs = self.slugs();
+        let link = |name: &str| format!("{}.html", slugs[&name.to_lowercase()]);
+
+        let mut files = vec![
+            ("style.css".to_string(), STYLE.to_string()),
+            ("index.html".to_string(), html_page(&self.title, &render_html(&self.overview(), 0, &link), false)),
+        ];
+        for page in self.tables().iter().map(|t| self.table_page(t)).chain(self.views().map(|v| self.view_page(v))) {
+            files.push((
+                link(&page.name),
+                html_page(&format!("{} - {}", page.name, self.title), &render_html(&page.blocks, 1, &link), true),
+            ));
+        }
+        files
+    }
+
+    /// Tables to document; shadow tables belong to their virtual table
+    fn tables(&self) -> Vec<&'a TableInfo> {
+        self.schema.tables.iter().filter(|t| t.kind != TableKind::Shadow).collect()
+    }
+
+    fn views(&self) -> impl Iterator<Item = &'a ViewInfo> {
+        self.schema.views.iter()
+    }
+
+    /// File and anchor names by lowercase object name; tables and views share one namespace
+    fn slugs(&self) -> HashMap<String, String> {
+        let mut slugs = HashMap::new();
+        let mut taken = HashSet::new();
+        let names = self.tables().into_iter().map(|t| ("table", &t.name))
+            .chain(self.views().map(|v| ("view", &v.name)));
+
+        for (kind, name) in names {
+            let word: String = name.to_lowercase().chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                .collect();
+            let base = format!("{}-{}", kind, word);
+            let mut slug = base.clone();
+            let mut suffix = 1;
+            while !taken.insert(slug.clone()) {
+                suffix += 1;
+                slug = format!("{}-{}", base, suffix);
+            }
+            slugs.insert(name.to_lowercase(), slug);
+        }
+
+        slugs
+    }
+
+    fn description(&self, name: &str) -> Option<&str> {
+        self.descriptions.get(name).and_then(|d| d.description.as_deref())
+    }
+
+    fn column_description(&self, table: &str, column: &str) -> Option<&str> {
+        self.descriptions.get(table).and_then(|d| d.columns.get(column)).map(String::as_str)
+    }
+
+    fn link(&self, name: &str) -> Inline {
+        let known = self.tables().iter().any(|t| t.name.eq_ignore_ascii_case(name))
+            || self.views().any(|v| v.name.eq_ignore_ascii_case(name));
+        if known { Inline::Link(name.to_string(), name.to_string()) } else { code(name) }
+    }
+
+    fn overview(&self) -> Vec<Block> {
+        let tables = self.tables();
+        let views: Vec<&ViewInfo> = self.views().collect();
+        let mut blocks = vec![
+            Block::Heading(1, None, self.title.clone()),
+            Block::Paragraph(vec![text(format!(
+                "{} tables, {} views, {} indexes, {} triggers.",
+                tables.len(),
+                views.len(),
+                self.schema.indexes.len(),
+                self.schema.triggers.len()
+            ))]),
+        ];
+
+        if !tables.is_empty() {
+            blocks.push(Block::Heading(2, None, "Tables".to_string()));
+            let rows = tables.iter().map(|t| vec![
+                vec![self.link(&t.name)],
+                vec![text(self.rows_text(&t.name))],
+                vec![text(self.sizes.get(&t.name).map(|&n| human_size(n)).unwrap_or_default())],
+                vec![text(self.description(&t.name).unwrap_or_default())],
+            ]).collect();
+            blocks.push(Block::Table(vec!["Table", "Rows", "Size", "Description"], rows));
+        }
+
+        if !views.is_empty() {
+            blocks.push(Block::Heading(2, None, "Views".to_string()));
+            let rows = views.iter().map(|v| vec![
+                vec![self.link(&v.name)],
+                vec![text(self.description(&v.name).unwrap_or_default())],
+            ]).collect();
+            blocks.push(Block::Table(vec!["View", "Description"], rows));
+        }
+
+        blocks
+    }
+
+    fn rows_text(&self, table: &str) -> String {
+        match self.row_counts.get(table) {
+            Some((n, true)) => format!("~{}", n),
+            Some((n, false)) => n.to_string(),
+            None => String::new(),
+        }
+    }
+
+    fn table_page(&self, table: &TableInfo) -> Page {
+        let definition = TableDefinition::from_schema(table).ok();
+        let mut blocks = vec![Block::Heading(2, Some(table.name.clone()), format!("Table {}", table.name))];
+
+        if let Some(description) = self.description(&table.name) {
+            blocks.push(Block::Paragraph(vec![text(description)]));
+        }
+
+        let mut facts = Vec::new();
+        if let Some(count) = self.row_counts.get(&table.name) {
+            facts.push(format!("{} rows{}", count.0, if count.1 { " (estimated)" } else { "" }));
+        }
+        if let Some(&size) = self.sizes.get(&table.name) {
+            facts.push(human_size(size));
+        }
+        if let Some(module) = &table.module {
+            facts.push(format!("virtual table using {}", module));
+        }
+        if table.strict {
+            facts.push("STRICT".to_string());
+        }
+        if table.without_rowid {
+            facts.push("WITHOUT ROWID".to_string());
+        }
+        if !facts.is_empty() {
+            blocks.push(Block::Paragraph(vec![text(facts.join(" · "))]));
+        }
+        if !table.shadow_tables.is_empty() {
+            let mut inlines = vec![text("Shadow tables: ")];
+            for (i, shadow) in table.shadow_tables.iter().enumerate() {
+                if i > 0 {
+                    inlines.push(text(", "));
+                }
+                inlines.push(code(shadow));
+            }
+            blocks.push(Block::Paragraph(inlines));
+        }
+
+        // Columns
+        blocks.push(Block::Heading(3, None, "Columns".to_string()));
+        let rows = table.columns.iter().map(|column| {
+            let column_definition = definition.as_ref()
+                .and_then(|d| d.columns.iter().find(|c| c.name == column.name));
+            let mut references = Vec::new();
+            for fk in table.foreign_keys.iter() {
+                for pair in fk.columns.iter().filter(|p| p.from == column.name) {
+                    if !references.is_empty() {
+                        references.push(text(", "));
+                    }
+                    references.push(self.link(&fk.referenced_table));
+                    references.push(text(format!(".{}", pair.to)));
+                }
+            }
+            vec![
+                vec![code(&column.name)],
+                vec![text(&column.data_type)],
+                vec![text(if column.not_null || column.primary_key { "NOT NULL" } else { "NULL" })],
+                column.default_value.as_deref().map(code).into_iter().collect(),
+                column_constraints(column, column_definition).into_iter().map(code).collect(),
+                references,
+                vec![text(self.column_description(&table.name, &column.name).unwrap_or_default())],
+            ]
+        }).collect();
+        blocks.push(Block::Table(
+            vec!["Column", "Type", "Null", "Default", "Constraints", "References", "Description"],
+            rows,
+        ));
+
+        // Constraints over several columns, and CHECKs
+        let constraints: Vec<Vec<Inline>> = definition.iter()
+            .flat_map(|d| d.constraints.iter())
+            .filter_map(|constraint| {
+                let (name, sql) = match constraint {
+                    TableConstraint::PrimaryKey { name, columns } => (name, format!("PRIMARY KEY ({})", columns.join(", "))),
+                    TableConstraint::Unique { name, columns } => (name, format!("UNIQUE ({})", columns.join(", "))),
+                    TableConstraint::Check { name, expression } => (name, format!("CHECK ({})", expression)),
+                    TableConstraint::ForeignKey { .. } => return None,
+                };
+                let mut inlines = vec![code(sql)];
+                if let Some(name) = name {
+                    inlines.push(text(format!(" ({})", name)));
+                }
+                Some(inlines)
+            })
+            .collect();
+        if !constraints.is_empty() {
+            blocks.push(Block::Heading(3, None, "Constraints".to_string()));
+            blocks.push(Block::List(constraints));
+        }
+
+        if !table.foreign_keys.is_empty() {
+            blocks.push(Block::Heading(3, None, "Foreign keys".to_string()));
+            let rows = table.foreign_keys.iter().rev().map(|fk| vec![
+                vec![code(fk.columns.iter().map(|p| p.from.as_str()).collect::<Vec<_>>().join(", "))],
+                vec![self.link(&fk.referenced_table), text(format!(" ({})", fk.columns.iter().map(|p| p.to.as_str()).collect::<Vec<_>>().join(", ")))],
+                vec![text(&fk.on_update)],
+                vec![text(&fk.on_delete)],
+                vec![text(match (fk.deferrable, fk.initially_deferred) {
+                    (true, true) => "DEFERRABLE INITIALLY DEFERRED",
+                    (true, false) => "DEFERRABLE",
+                    _ => "",
+                })],
+            ]).collect();
+            blocks.push(Block::Table(vec!["Columns", "References", "On update", "On delete", "Deferral"], rows));
+        }
+
+        if !table.referenced_by.is_empty() {
+            blocks.push(Block::Heading(3, None, "Referenced by".to_string()));
+            let items = table.referenced_by.iter().map(|fk| vec![
+                self.link(&fk.table),
+                text(format!(" ({})", fk.columns.iter().map(|p| p.from.as_str()).collect::<Vec<_>>().join(", "))),
+            ]).collect();
+            blocks.push(Block::List(items));
+        }
+
+        let indexes: Vec<_> = self.schema.indexes.iter().filter(|i| i.table_name == table.name).collect();
+        if !indexes.is_empty() {
+            blocks.push(Block::Heading(3, None, "Indexes".to_string()));
+            let rows = indexes.iter().map(|index| vec![
+                vec![code(&index.name)],
+                vec![code(index.columns.join(", "))],
+                vec![text(if index.unique { "yes" } else { "no" })],
+                vec![text(match index.origin {
+                    IndexOrigin::CreateIndex => "CREATE INDEX",
+                    IndexOrigin::Unique => "UNIQUE constraint",
+                    IndexOrigin::PrimaryKey => "PRIMARY KEY",
+                })],
+                index.where_clause.as_deref().map(code).into_iter().collect(),
+                vec![text(self.sizes.get(&index.name).map(|&n| human_size(n)).unwrap_or_default())],
+                vec![text(self.description(&index.name).unwrap_or_default())],
+            ]).collect();
+            blocks.push(Block::Table(vec!["Index", "Columns", "Unique", "Origin", "Where", "Size", "Description"], rows));
+        }
+
+        self.triggers_and_dependents(&table.name, &mut blocks);
+
+        if let Some(sql) = &table.sql {
+            blocks.push(Block::Heading(3, None, "Definition".to_string()));
+            blocks.push(Block::Code(sql.clone()));
+        }
+
+        Page { name: table.name.clone(), blocks }
+    }
+
+    fn view_page(&self, view: &ViewInfo) -> Page {
+        let mut blocks = vec![Block::Heading(2, Some(view.name.clone()), format!("View {}", view.name))];
+        if let Some(description) = self.description(&view.name) {
+            blocks.push(Block::Paragraph(vec![text(description)]));
+        }
+
+        self.triggers_and_dependents(&view.name, &mut blocks);
+
+        if let Some(sql) = &view.sql {
+            blocks.push(Block::Heading(3, None, "Definition".to_string()));
+            blocks.push(Block::Code(sql.clone()));
+        }
+
+        Page { name: view.name.clone(), blocks }
+    }
+
+    /// Triggers on a table or view, and the views built on it
+    fn triggers_and_dependents(&self, name: &str, blocks: &mut Vec<Block>) {
+        let triggers: Vec<_> = self.schema.triggers.iter().filter(|t| t.table_name == name).collect();
+        if !triggers.is_empty() {
+            blocks.push(Block::Heading(3, None, "Triggers".to_string()));
+            let rows = triggers.iter().map(|trigger| {
+                let fires = trigger.definition.as_ref().map(|d| {
+                    let mut fires = format!("{} {}", d.timing.as_str(), d.event.as_str());
+                    if !d.update_columns.is_empty() {
+                        fires.push_str(&format!(" OF {}", d.update_columns.join(", ")));
+                    }
+                    fires
+                });
+                vec![
+                    vec![code(&trigger.name)],
+                    vec![text(fires.unwrap_or_default())],
+                    trigger.definition.as_ref().and_then(|d| d.when_clause.as_deref()).map(code).into_iter().collect(),
+                    vec![text(self.description(&trigger.name).unwrap_or_default())],
+                ]
+            }).collect();
+            blocks.push(Block::Table(vec!["Trigger", "Fires", "When", "Description"], rows));
+        }
+
+        let views: Vec<Vec<Inline>> = DependencyGraph::build(self.schema)
+            .view_and_trigger_dependents(name)
+            .into_iter()
+            .filter(|edge| edge.kind == DependencyKind::ViewReference)
+            .map(|edge| vec![self.link(&edge.dependent)])
+            .collect();
+        if !views.is_empty() {
+            blocks.push(Block::Heading(3, None, "Dependent views".to_string()));
+            blocks.push(Block::List(views));
+        }
+    }
+}
+
+/// Constraints declared on a column, as SQL fragments
+fn column_constraints(column: &ColumnInfo, definition: Option<&ColumnDefinition>) -> Vec<String> {
+    let mut constraints = Vec::new();
+    if column.primary_key {
+        let autoincrement = definition.is_some_and(|d| d.autoincrement);
+        constraints.push(if autoincrement { "PRIMARY KEY AUTOINCREMENT" } else { "PRIMARY KEY" }.to_string());
+    }
+    if let Some(definition) = definition {
+        if definition.unique {
+            constraints.push("UNIQUE".to_string());
+        }
+        if let Some(collation) = &definition.collation {
+            constraints.push(format!("COLLATE {}", collation));
+        }
+        if let Some(check) = &definition.check {
+            constraints.push(format!("CHECK ({})", check));
+        }
+        if let Some(generated) = &definition.generated {
+            let storage = if generated.kind == GeneratedKind::Stored { "STORED" } else { "VIRTUAL" };
+            constraints.push(format!("AS ({}) {}", generated.expression, storage));
+        }
+    } else if let Some(kind) = column.generated {
+        constraints.push(if kind == GeneratedKind::Stored { "GENERATED STORED" } else { "GENERATED VIRTUAL" }.to_string());
+    }
+    if column.hidden {
+        constraints.push("HIDDEN".to_string());
+    }
+    constraints
+}
+
+/// Bytes as B, KiB, MiB or GiB
+fn human_size(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 { format!("{} B", bytes) } else { format!("{:.1} {}", value, UNITS[unit]) }
+}
+
+fn render_markdown(blocks: &[Block], slugs: &HashMap<String, String>, link: &dyn Fn(&str) -> String) -> String {
+    let inlines = |inlines: &[Inline], in_table: bool| -> String {
+        inlines.iter().map(|inline| {
+            let escaped = |s: &str| {
+                let mut out = String::with_capacity(s.len());
+                for c in s.chars() {
+                    if "\\`*_[]<>#|".contains(c) {
+                        out.push('\\');
+                    }
+                    out.push(c);
+                }
+                if in_table { out.replace('\n', " ") } else { out }
+            };
+            match inline {
+                Inline::Text(s) => escaped(s),
+                Inline::Code(s) => {
+                    let s = if in_table { s.replace('|', "\\|").replace('\n', " ") } else { s.clone() };
+                    if s.contains('`') { format!("`` {} ``", s) } else { format!("`{}`", s) }
+                }
+                Inline::Link(label, target) => format!("[{}]({})", escaped(label), link(target)),
+            }
+        }).collect()
+    };
+
+    let mut out = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, target, title) => {
+                if let Some(slug) = target.as_ref().and_then(|t| slugs.get(&t.to_lowercase())) {
+                    out.push_str(&format!("<a id=\"{}\"></a>\n\n", slug));
+                }
+                out.push_str(&format!("{} {}\n\n", "#".repeat(*level), inlines(&[text(title)], false)));
+            }
+            Block::Paragraph(content) => out.push_str(&format!("{}\n\n", inlines(content, false))),
+            Block::List(items) => {
+                for item in items {
+                    out.push_str(&format!("- {}\n", inlines(item, false)));
+                }
+                out.push('\n');
+            }
+            Block::Table(headers, rows) => {
+                out.push_str(&format!("| {} |\n", headers.join(" | ")));
+                out.push_str(&format!("|{}\n", " --- |".repeat(headers.len())));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|cell| inlines(cell, true)).collect();
+                    out.push_str(&format!("| {} |\n", cells.join(" | ")));
+                }
+                out.push('\n');
+            }
+            Block::Code(sql) => {
+                let fence = if sql.contains("```") { "~~~" } else { "```" };
+                out.push_str(&format!("{}sql\n{}\n{}\n\n", fence, sql, fence));
+            }
+        }
+    }
+    out
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Blocks as HTML, with headings moved up by `shift` levels
+fn render_html(blocks: &[Block], shift: usize, link: &dyn Fn(&str) -> String) -> String {
+    let inlines = |inlines: &[Inline]| -> String {
+        inlines.iter().map(|inline| match inline {
+            Inline::Text(s) => escape_html(s),
+            Inline::Code(s) => format!("<code>{}</code>", escape_html(s)),
+            Inline::Link(label, target) => format!("<a href=\"{}\">{}</a>", escape_html(&link(target)), escape_html(label)),
+        }).collect()
+    };
+
+    let mut out = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, _, title) => {
+                let level = level.saturating_sub(shift).max(1);
+                out.push_str(&format!("<h{0}>{1}</h{0}>\n", level, escape_html(title)));
+            }
+            Block::Paragraph(content) => out.push_str(&format!("<p>{}</p>\n", inlines(content))),
+            Block::List(items) => {
+                out.push_str("<ul>\n");
+                for item in items {
+                    out.push_str(&format!("<li>{}</li>\n", inlines(item)));
+                }
+                out.push_str("</ul>\n");
+            }
+            Block::Table(headers, rows) => {
+                out.push_str("<table>\n<thead><tr>");
+                for header in headers {
+                    out.push_str(&format!("<th>{}</th>", header));
+                }
+                out.push_str("</tr></thead>\n<tbody>\n");
+                for row in rows {
+                    out.push_str("<tr>");
+                    for cell in row {
+                        out.push_str(&format!("<td>{}</td>", inlines(cell)));
+                    }
+                    out.push_str("</tr>\n");
+                }
+                out.push_str("</tbody>\n</table>\n");
+            }
+            Block::Code(sql) => out.push_str(&format!("<pre><code>{}</code></pre>\n", escape_html(sql))),
+        }
+    }
+    out
+}
+
+fn html_page(title: &str, body: &str, back_link: bool) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<link rel=\"stylesheet\" href=\"style.css\">\n</head>\n<body>\n{}{}</body>\n</html>\n",
+        escape_html(title),
+        if back_link { "<nav><a href=\"index.html\">&larr; All tables and views</a></nav>\n" } else { "" },
+        body
+    )
+}
+
+const STYLE: &str = "body { font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", sans-serif; max-width: 72rem; margin: 2rem auto; padding: 0 1rem; color: #1f2328; }
+table { border-collapse: collapse; margin: 1rem 0; width: 100%; }
+th, td { border: 1px solid #d0d7de; padding: 0.35rem 0.6rem; text-align: left; vertical-align: top; }
+th { background: #f6f8fa; }
+code, pre { font-family: ui-monospace, SFMono-Regular, Menlo, monospace; font-size: 0.9em; }
+pre { background: #f6f8fa; padding: 1rem; overflow-x: auto; }
+nav { margin-bottom: 1rem; }
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlite3x::wrapper::Database;
+
+    #[test]
+    fn test_schema_docs() {
+        let db = Database::open(":memory:").unwrap();
+        db.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT, email TEXT NOT NULL UNIQUE, age INTEGER CHECK (age >= 0));
+             CREATE TABLE posts (id INTEGER PRIMARY KEY, author_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE);
+             CREATE INDEX idx_posts_author ON posts (author_id);
+             CREATE VIEW authors AS SELECT * FROM users WHERE id IN (SELECT author_id FROM posts);",
+        )
+        .unwrap();
+        let schema = db.get_schema().unwrap();
+
+        let mut descriptions = BTreeMap::new();
+        descriptions.insert("users".to_string(), ObjectDescription {
+            description: Some("People who can sign in".to_string()),
+            columns: BTreeMap::from([("email".to_string(), "Login | contact address".to_string())]),
+        });
+
+        let docs = SchemaDocs {
+            title: "Data dictionary".to_string(),
+            schema: &schema,
+            row_counts: HashMap::from([("users".to_string(), (3, false))]),
+            sizes: HashMap::from([("users".to_string(), 4096)]),
+            descriptions: &descriptions,
+        };
+
+        let markdown = docs.to_markdown();
+        assert!(markdown.starts_with("# Data dictionary\n"));
+        assert!(markdown.contains("| [users](#table-users) | 3 | 4.0 KiB | People who can sign in |"));
+        assert!(markdown.contains("<a id=\"table-posts\"></a>"));
+        assert!(markdown.contains("| `email` | TEXT | NOT NULL |  | `UNIQUE` |  | Login \\| contact address |"));
+        assert!(markdown.contains("`CHECK (age >= 0)`"));
+        assert!(markdown.contains("| `author_id` | [users](#table-users) (id) | NO ACTION | CASCADE |  |"));
+        assert!(markdown.contains("### Dependent views\n\n- [authors](#view-authors)"));
+
+        let site = docs.to_html_site();
+        let names: Vec<&str> = site.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["style.css", "index.html", "table-posts.html", "table-users.html", "view-authors.html"]);
+        let users = &site.iter().find(|(name, _)| name == "table-users.html").unwrap().1;
+        assert!(users.contains("<h1>Table users</h1>"));
+        assert!(users.contains("<a href=\"table-posts.html\">posts</a>"));
+    }
+}
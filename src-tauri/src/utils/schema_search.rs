@@ -0,0 +1,181 @@
+//! Schema Search
+//!
+//! Finds a term in the names of tables, columns, views, indexes and triggers
+//! and in view and trigger SQL, ranked by how closely each match fits.
+
+use crate::sqlite3x::wrapper::{SchemaInfo, TableKind};
+use serde::Serialize;
+
+/// Characters of context on each side of a match in SQL
+const SNIPPET_CONTEXT: usize = 30;
+
+/// What a search hit matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchHitKind {
+    Table,
+    Column,
+    View,
+    Index,
+    Trigger,
+    /// The SQL of a view
+    ViewDefinition,
+    /// The SQL of a trigger
+    TriggerDefinition,
+}
+
+/// One match of `search_schema`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    /// "main" or the name of an attached database
+    pub schema: String,
+    pub kind: SearchHitKind,
+    /// Table, view, index or trigger name
+    pub object: String,
+    /// Column name for column hits
+    pub column: Option<String>,
+    /// The SQL around the match for definition hits
+    pub snippet: Option<String>,
+    /// Higher is better
+    pub score: u32,
+}
+
+/// All matches of `term` in `schema`, best first
+pub fn search_schema(schema_name: &str, schema: &SchemaInfo, term: &str) -> Vec<SearchHit> {
+    let needle = term.trim().to_lowercase();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+
+    let mut hits = Vec::new();
+    let mut hit = |kind: SearchHitKind, object: &str, column: Option<&str>, snippet: Option<String>, score: u32| {
+        hits.push(SearchHit {
+            schema: schema_name.to_string(),
+            kind,
+            object: object.to_string(),
+            column: column.map(str::to_string),
+            snippet,
+            score,
+        });
+    };
+
+    for table in schema.tables.iter().filter(|t| t.kind != TableKind::Shadow) {
+        if let Some(score) = name_score(&table.name, &needle) {
+            hit(SearchHitKind::Table, &table.name, None, None, score + 5);
+        }
+        for column in table.columns.iter().filter(|c| !c.hidden) {
+            if let Some(score) = name_score(&column.name, &needle) {
+                hit(SearchHitKind::Column, &table.name, Some(&column.name), None, score);
+            }
+        }
+    }
+
+    for view in &schema.views {
+        if let Some(score) = name_score(&view.name, &needle) {
+            hit(SearchHitKind::View, &view.name, None, None, score + 4);
+        }
+        if let Some(snippet) = view.sql.as_deref().and_then(|sql| snippet(body(sql), &needle)) {
+            hit(SearchHitKind::ViewDefinition, &view.name, None, Some(snippet), 20);
+        }
+    }
+
+    for index in &schema.indexes {
+        if let Some(score) = name_score(&index.name, &needle) {
+            hit(SearchHitKind::Index, &index.name, None, None, score);
+        }
+    }
+
+    for trigger in &schema.triggers {
+        if let Some(score) = name_score(&trigger.name, &needle) {
+            hit(SearchHitKind::Trigger, &trigger.name, None, None, score);
+        }
+        if let Some(snippet) = trigger.sql.as_deref().and_then(|sql| snippet(body(sql), &needle)) {
+            hit(SearchHitKind::TriggerDefinition, &trigger.name, None, Some(snippet), 20);
+        }
+    }
+
+    hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.object.len().cmp(&b.object.len())).then_with(|| a.object.cmp(&b.object)));
+    hits
+}
+
+/// How well `name` matches: exact, prefix, start of a word, or anywhere
+fn name_score(name: &str, needle: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    if name == needle {
+        return Some(100);
+    }
+    if name.starts_with(needle) {
+        return Some(80);
+    }
+    let position = name.find(needle)?;
+    let word_start = name[..position].ends_with(|c: char| !c.is_alphanumeric());
+    Some(if word_start { 60 } else { 40 })
+}
+
+/// The part of a CREATE VIEW or CREATE TRIGGER after its name, so the name itself isn't a definition hit
+fn body(sql: &str) -> &str {
+    let tokens = crate::sqlite3x::ddl::tokenize(sql);
+    let start = tokens.iter()
+        .position(|t| t.is_keyword("AS") || t.is_keyword("BEFORE") || t.is_keyword("AFTER") || t.is_keyword("INSTEAD") || t.is_keyword("ON"))
+        .map(|i| tokens[i].start)
+        .unwrap_or(0);
+    &sql[start..]
+}
+
+/// The text around the first match of `needle` in `sql`, on one line
+fn snippet(sql: &str, needle: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets, so the position is valid in `sql`
+    let position = sql.to_ascii_lowercase().find(&needle.to_ascii_lowercase())?;
+
+    let mut start = position.saturating_sub(SNIPPET_CONTEXT);
+    while !sql.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (position + needle.len() + SNIPPET_CONTEXT).min(sql.len());
+    while !sql.is_char_boundary(end) {
+        end += 1;
+    }
+
+    let text = sql[start..end].split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(format!(
+        "{}{}{}",
+        if start > 0 { "…" } else { "" },
+        text,
+        if end < sql.len() { "…" } else { "" }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlite3x::wrapper::Database;
+
+    #[test]
+    fn test_search_schema() {
+        let db = Database::open(":memory:").unwrap();
+        db.execute_batch(
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY, customer_id INTEGER, total REAL);
+             CREATE TABLE customer (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE INDEX idx_orders_customer ON orders (customer_id);
+             CREATE VIEW big_orders AS SELECT * FROM orders WHERE total > 100;
+             CREATE TRIGGER orders_check BEFORE INSERT ON orders BEGIN SELECT RAISE(ABORT, 'no customer') WHERE NEW.customer_id IS NULL; END;",
+        )
+        .unwrap();
+        let schema = db.get_schema().unwrap();
+
+        let hits = search_schema("main", &schema, "Customer");
+        assert_eq!((hits[0].kind, hits[0].object.as_str()), (SearchHitKind::Table, "customer"));
+        let column = hits.iter().find(|h| h.kind == SearchHitKind::Column).unwrap();
+        assert_eq!(column.column.as_deref(), Some("customer_id"));
+        assert!(hits.iter().any(|h| h.kind == SearchHitKind::Index && h.object == "idx_orders_customer"));
+        let trigger = hits.iter().find(|h| h.kind == SearchHitKind::TriggerDefinition).unwrap();
+        assert!(trigger.snippet.as_deref().unwrap().contains("'no customer'"));
+
+        // A view's own name is not a definition hit
+        let hits = search_schema("main", &schema, "big");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].kind, SearchHitKind::View);
+        assert!(search_schema("main", &schema, "  ").is_empty());
+    }
+}
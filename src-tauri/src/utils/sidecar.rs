@@ -0,0 +1,171 @@
+//! Database Sidecar File
+//!
+//! Metadata the app keeps about a database file in a JSON file next to it,
+//! so it travels with the database without changing its schema.
+
+use super::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use crate::sqlite3x::rebuild::{renamed_in_copy, rewrite_after};
+use crate::sqlite3x::wrapper::SchemaInfo;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+const SIDECAR_VERSION: u32 = 1;
+
+/// Contents of `<database>.dbstudiox.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sidecar {
+    pub version: u32,
+    /// Triggers dropped by `disable_trigger`, kept for re-creation
+    #[serde(default)]
+    pub disabled_triggers: Vec<DisabledTrigger>,
+    /// Free-text documentation by table, view, index or trigger name
+    #[serde(default)]
+    pub descriptions: BTreeMap<String, ObjectDescription>,
+}
+
+/// A dropped trigger and the DDL to recreate it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisabledTrigger {
+    pub name: String,
+    pub table_name: String,
+    pub sql: String,
+    /// Unix timestamp
+    pub disabled_at: u64,
+}
+
+/// Documentation of one schema object and, for tables, its columns
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectDescription {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub columns: BTreeMap<String, String>,
+}
+
+impl Sidecar {
+    /// Sidecar path of a database file
+    ///
+    /// In-memory and temporary databases have no file to keep metadata next to.
+    pub fn path_for(db_path: &str) -> AppResult<PathBuf> {
+        if db_path.is_empty() || db_path == ":memory:" || db_path.starts_with("file::memory:") {
+            return Err(AppError::BadRequest(
+                "In-memory databases have no sidecar file".to_string(),
+            ));
+        }
+        Ok(PathBuf::from(format!("{}.dbstudiox.json", db_path)))
+    }
+
+    /// Set or, with an empty text, remove the description of an object or one of its columns
+    pub fn set_description(&mut self, object: &str, column: Option<&str>, text: &str) {
+        let text = text.trim();
+        let entry = self.descriptions.entry(object.to_string()).or_default();
+        match (column, text.is_empty()) {
+            (Some(column), true) => {
+                entry.columns.remove(column);
+            }
+            (Some(column), false) => {
+                entry.columns.insert(column.to_string(), text.to_string());
+            }
+            (None, true) => entry.description = None,
+            (None, false) => entry.description = Some(text.to_string()),
+        }
+        if entry.description.is_none() && entry.columns.is_empty() {
+            self.descriptions.remove(object);
+        }
+    }
+
+    /// Follow a table rename in descriptions and disabled triggers; true if anything changed
+    pub fn rename_table(&mut self, old_name: &str, new_name: &str) -> bool {
+        let mut changed = false;
+        if let Some(key) = self.descriptions.keys().find(|k| k.eq_ignore_ascii_case(old_name)).cloned() {
+            let description = self.descriptions.remove(&key).unwrap_or_default();
+            self.descriptions.insert(new_name.to_string(), description);
+            changed = true;
+        }
+
+        let renames = HashMap::from([(old_name.to_lowercase(), new_name.to_string())]);
+        for trigger in self.disabled_triggers.iter_mut().filter(|t| t.table_name.eq_ignore_ascii_case(old_name)) {
+            trigger.table_name = new_name.to_string();
+            trigger.sql = rewrite_after(&trigger.sql, "ON", &renames);
+            changed = true;
+        }
+        changed
+    }
+
+    /// Follow a column rename in descriptions and disabled triggers; true if anything changed
+    ///
+    /// `schema` is the schema before the rename. Disabled triggers are renamed
+    /// by SQLite against a copy of it, so only references to `table` change.
+    pub fn rename_column(&mut self, schema: &SchemaInfo, table: &str, old_name: &str, new_name: &str) -> bool {
+        let mut changed = false;
+        if let Some(entry) = self.descriptions.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(table)).map(|(_, v)| v) {
+            if let Some(key) = entry.columns.keys().find(|k| k.eq_ignore_ascii_case(old_name)).cloned() {
+                let text = entry.columns.remove(&key).unwrap_or_default();
+                entry.columns.insert(new_name.to_string(), text);
+                changed = true;
+            }
+        }
+
+        let renames = HashMap::from([(new_name.to_string(), old_name.to_string())]);
+        for trigger in &mut self.disabled_triggers {
+            let mentions = crate::sqlite3x::ddl::tokenize(&trigger.sql).iter()
+                .filter_map(|t| t.identifier())
+                .any(|id| id.eq_ignore_ascii_case(old_name));
+            if !mentions {
+                continue;
+            }
+            match renamed_in_copy(schema, table, &[(&trigger.name, &trigger.sql)], &renames) {
+                Ok(renamed) => {
+                    if let Some(sql) = renamed.get(&trigger.name.to_lowercase()).filter(|sql| **sql != trigger.sql) {
+                        trigger.sql = sql.clone();
+                        changed = true;
+                    }
+                }
+                Err(e) => log::warn!("Could not rename {}.{} in disabled trigger {}: {}", table, old_name, trigger.name, e),
+            }
+        }
+        changed
+    }
+
+    /// Load the sidecar of a database, or an empty one if there is none yet
+    pub fn load(db_path: &str) -> AppResult<Self> {
+        let path = Self::path_for(db_path)?;
+        if !path.exists() {
+            return Ok(Self { version: SIDECAR_VERSION, ..Default::default() });
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| AppError::FsError(format!("Failed to read sidecar file: {}", e)))?;
+        let sidecar: Self = serde_json::from_str(&content)
+            .map_err(|e| AppError::SerializationError(format!("Invalid sidecar file {}: {}", path.display(), e)))?;
+
+        if sidecar.version > SIDECAR_VERSION {
+            return Err(AppError::BadRequest(format!(
+                "Sidecar file {} was written by a newer version (v{})",
+                path.display(),
+                sidecar.version
+            )));
+        }
+
+        Ok(sidecar)
+    }
+
+    /// Write the sidecar, replacing the previous file in one rename
+    pub fn save(&self, db_path: &str) -> AppResult<()> {
+        let path = Self::path_for(db_path)?;
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::SerializationError(e.to_string()))?;
+
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, json)
+            .map_err(|e| AppError::FsError(format!("Failed to write sidecar file: {}", e)))?;
+        std::fs::rename(&temp, &path)
+            .map_err(|e| AppError::FsError(format!("Failed to write sidecar file: {}", e)))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+//! SQL Text Utilities
+//!
+//! Helpers for building SQL statements from user-supplied names.
+
+/// Quote an identifier for use in SQL, escaping embedded double quotes
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
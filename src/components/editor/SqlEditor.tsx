@@ -101,7 +101,9 @@ const SqlEditor: React.FC<SqlEditorProps> = ({ value, onChange, onRun, schema })
                             label: table.name,
                             kind: monaco.languages.CompletionItemKind.Class,
                             insertText: table.name,
-                            detail: `Table (${table.rowCount} rows)`,
+                            detail: table.rowCount == null
+                                ? 'Table'
+                                : `Table (${table.rowCountEstimated ? '~' : ''}${table.rowCount} rows)`,
                             documentation: `Size: ${table.sizeBytes ? (table.sizeBytes / 1024).toFixed(1) + ' KB' : 'Unknown'}`,
                             range: range,
                         });
@@ -4,7 +4,7 @@ import { Modal } from '@/components/ui/Modal';
 import { Button } from '@/components/ui/Button';
 import { Input } from '@/components/ui/Input';
 import { useDatabaseStore } from '@/stores/databaseStore';
-import { dbService } from '@/services/dbService';
+import { dbService, SchemaRename, SchemaSource } from '@/services/dbService';
 import { DiffEditor } from '@monaco-editor/react';
 import { FileSearch, ChevronRight, CheckCircle2, History, AlertCircle, FileCode } from 'lucide-react';
 import FileDialog from '@/components/ui/FileDialog';
@@ -21,23 +21,51 @@ const SchemaDiffModal: React.FC = () => {
     const [diffResult, setDiffResult] = useState<any>(null);
     const [isProcessing, setIsProcessing] = useState(false);
     const [showSql, setShowSql] = useState(false);
+    const [renames, setRenames] = useState<SchemaRename[]>([]);
 
     // Custom file dialog
     const fileDialog = useFileDialog();
 
     const handleFileSelect = async () => {
         const file = await fileDialog.openFile({
-            filters: FILE_FILTERS.database,
-            title: 'Select Database to Compare'
+            filters: [...FILE_FILTERS.database, ...FILE_FILTERS.json],
+            title: 'Select Database or Schema Snapshot to Compare'
         });
-        if (file) setTargetFile(file);
+        if (file) {
+            setTargetFile(file);
+            setRenames([]);
+        }
     };
 
+    const handleSaveSnapshot = async () => {
+        if (!activeConnection) return;
+        const file = await fileDialog.saveFile({
+            filters: FILE_FILTERS.json,
+            title: 'Save Schema Snapshot'
+        });
+        if (!file) return;
+        try {
+            await dbService.saveSchemaSnapshot(activeConnection.id, file);
+            addNotification({ type: 'success', title: 'Snapshot Saved', message: file });
+        } catch (error: any) {
+            addNotification({ type: 'error', title: 'Snapshot Failed', message: error.toString() });
+        }
+    };
+
+    // Snapshots are JSON files; anything else is opened read-only as a database
+    const isSnapshot = !!targetFile?.toLowerCase().endsWith('.json');
+    const sources = (): [SchemaSource, SchemaSource] => [
+        { kind: 'connection', connectionId: activeConnection!.id },
+        isSnapshot
+            ? { kind: 'snapshot', path: targetFile! }
+            : { kind: 'file', path: targetFile! },
+    ];
+
     const handleNext = async () => {
         if (step === 2 && targetFile && activeConnection) {
             setIsProcessing(true);
             try {
-                const result = await dbService.diffSchemas(activeConnection.id, targetFile);
+                const result = await dbService.diffSchemas(...sources(), renames);
                 setDiffResult(result);
                 setStep(3);
             } catch (error: any) {
@@ -54,25 +82,57 @@ const SchemaDiffModal: React.FC = () => {
         }
     };
 
+    const handleApply = async (dryRun: boolean) => {
+        if (!targetFile || !activeConnection) return;
+        setIsProcessing(true);
+        try {
+            const result = await dbService.applySchemaDiff(...sources(), renames, dryRun);
+            addNotification({
+                type: result.converged ? 'success' : 'warning',
+                title: dryRun ? 'Dry Run Succeeded' : 'Migration Applied',
+                message: result.converged
+                    ? `${result.statements.length} statements ran${dryRun ? ' and were rolled back' : ''}.`
+                    : `${result.remainingStatements.length} statements still differ afterwards.`
+            });
+            if (!dryRun) {
+                setRenames([]);
+                setDiffResult(await dbService.diffSchemas(...sources()));
+            }
+        } catch (error: any) {
+            addNotification({
+                type: 'error',
+                title: dryRun ? 'Dry Run Failed' : 'Migration Failed',
+                message: error.toString()
+            });
+        } finally {
+            setIsProcessing(false);
+        }
+    };
+
+    // Confirming or dropping a rename diffs again with the new set
+    const toggleRename = async (rename: SchemaRename) => {
+        const same = (r: SchemaRename) => r.table === rename.table && r.from === rename.from && r.to === rename.to;
+        const next = renames.some(same)
+            ? renames.filter(r => !same(r))
+            : [...renames, { table: rename.table, from: rename.from, to: rename.to }];
+        setIsProcessing(true);
+        try {
+            setDiffResult(await dbService.diffSchemas(...sources(), next));
+            setRenames(next);
+        } catch (error: any) {
+            addNotification({ type: 'error', title: 'Rename Failed', message: error.toString() });
+        } finally {
+            setIsProcessing(false);
+        }
+    };
+
     const handleBack = () => {
         setStep(prev => prev - 1);
     };
 
     const getAllSql = () => {
         if (!diffResult) return "";
-        return [
-            "-- Tables to Create",
-            ...diffResult.tablesToCreate,
-            "",
-            "-- Columns to Add",
-            ...diffResult.columnsToAdd,
-            "",
-            "-- Indexes to Create",
-            ...diffResult.indexesToCreate,
-            "",
-            "-- Tables to Drop",
-            ...diffResult.tablesToDrop,
-        ].join("\n");
+        return diffResult.migration.join("\n");
     };
 
     if (!isOpen) return null;
@@ -96,9 +156,17 @@ const SchemaDiffModal: React.FC = () => {
                             Next <ChevronRight size={16} className="ml-2" />
                         </Button>
                     ) : (
-                        <Button variant="primary" onClick={closeModal}>
-                            Close <CheckCircle2 size={16} className="ml-2" />
-                        </Button>
+                        <>
+                            <Button variant="secondary" onClick={() => handleApply(true)} disabled={isSnapshot || !diffResult?.migration.length} isLoading={isProcessing}>
+                                Dry Run
+                            </Button>
+                            <Button variant="secondary" onClick={() => handleApply(false)} disabled={isSnapshot || !diffResult?.migration.length} isLoading={isProcessing}>
+                                Apply to Target
+                            </Button>
+                            <Button variant="primary" onClick={closeModal}>
+                                Close <CheckCircle2 size={16} className="ml-2" />
+                            </Button>
+                        </>
                     )}
                 </div>
             }
@@ -132,7 +200,8 @@ const SchemaDiffModal: React.FC = () => {
                                 <p>Analyze structural differences between your current database and another SQLite file.</p>
                                 <ul className={styles.featureList}>
                                     <li>Identify missing tables and columns</li>
-                                    <li>Detection of index discrepancies</li>
+                                    <li>Detection of index, view and trigger discrepancies</li>
+                                    <li>Changed column types, defaults and constraints</li>
                                     <li>Generate migration SQL scripts</li>
                                 </ul>
                             </div>
@@ -147,12 +216,15 @@ const SchemaDiffModal: React.FC = () => {
                                     <Input
                                         value={targetFile || ''}
                                         readOnly
-                                        placeholder="Select target SQLite file..."
+                                        placeholder="Select target SQLite file or schema snapshot..."
                                         className="flex-1"
                                     />
                                     <Button variant="secondary" onClick={handleFileSelect}>
                                         Browse
                                     </Button>
+                                    <Button variant="ghost" onClick={handleSaveSnapshot}>
+                                        Save Snapshot
+                                    </Button>
                                 </div>
                             </div>
                             <div className={styles.infoBox}>
@@ -177,8 +249,49 @@ const SchemaDiffModal: React.FC = () => {
                                     <span className={styles.count}>{diffResult?.indexesToCreate.length || 0}</span>
                                     <span className={styles.label}>Indexes to Create</span>
                                 </div>
+                                <div className={styles.summaryCard}>
+                                    <span className={styles.count}>{diffResult?.viewsToCreate.length || 0}</span>
+                                    <span className={styles.label}>Views to Create</span>
+                                </div>
+                                <div className={styles.summaryCard}>
+                                    <span className={styles.count}>{diffResult?.triggersToCreate.length || 0}</span>
+                                    <span className={styles.label}>Triggers to Create</span>
+                                </div>
+                                <div className={styles.summaryCard}>
+                                    <span className={styles.count}>{diffResult?.modified.length || 0}</span>
+                                    <span className={styles.label}>Modified Objects</span>
+                                </div>
                             </div>
 
+                            {(renames.length > 0 || diffResult?.renameCandidates.length > 0) && (
+                                <ul className={styles.featureList}>
+                                    {[...renames.map(r => ({ ...r, confirmed: true })), ...diffResult.renameCandidates].map((item: any) => (
+                                        <li key={`${item.table}-${item.from}-${item.to}`}>
+                                            <label>
+                                                <input
+                                                    type="checkbox"
+                                                    checked={!!item.confirmed}
+                                                    disabled={isProcessing}
+                                                    onChange={() => toggleRename(item)}
+                                                />{' '}
+                                                Rename {item.table ? `column ${item.table}.` : 'table '}{item.from} to {item.to}
+                                                {item.confidence !== undefined && ` (${item.confidence}% confidence)`}
+                                            </label>
+                                        </li>
+                                    ))}
+                                </ul>
+                            )}
+
+                            {diffResult?.modified.length > 0 && (
+                                <ul className={styles.featureList}>
+                                    {diffResult.modified.map((item: any) => (
+                                        <li key={`${item.kind}-${item.name}`}>
+                                            <b>{item.kind} {item.name}</b>: {item.changes.join('; ')}
+                                        </li>
+                                    ))}
+                                </ul>
+                            )}
+
                             <div className={styles.sqlPreview}>
                                 <div className={styles.previewHeader}>
                                     <span className={styles.previewTitle}><FileCode size={16} className="mr-2" /> Migration SQL Preview</span>
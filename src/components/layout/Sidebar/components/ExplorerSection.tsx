@@ -30,6 +30,9 @@ const ExplorerSection: React.FC<ExplorerSectionProps> = ({
     getTableIndexes,
     getTableTriggers
 }) => {
+    // Shadow tables are listed under their virtual table
+    const userTables = schema?.tables.filter(table => table.kind !== 'shadow') || [];
+
     return (
         <div className={styles.section}>
             {!sidebarCollapsed && <h3 className={styles.sectionTitle}>{t('common.explorer')}</h3>}
@@ -38,17 +41,18 @@ const ExplorerSection: React.FC<ExplorerSectionProps> = ({
                 <ExplorerItem
                     icon={TableProperties}
                     label={t('common.tables')}
-                    count={schema?.tables.length || 0}
+                    count={userTables.length}
                     collapsed={sidebarCollapsed}
                     onCollapsedClick={handleCollapsedItemClick}
                     onContextMenu={(e: React.MouseEvent) => handleContextMenu(e, 'tables')}
                 >
-                    {schema?.tables.map(table => (
+                    {userTables.map(table => (
                         <TableItem
                             key={table.name}
                             name={table.name}
                             indexes={getTableIndexes(table.name)}
                             triggers={getTableTriggers(table.name)}
+                            shadowTables={table.shadowTables}
                             onClick={() => handleTableClick(table.name)}
                             onContextMenu={(e: React.MouseEvent) => handleContextMenu(e, 'table', table.name)}
                             onIndexContextMenu={(e: React.MouseEvent, idxName: string) => handleContextMenu(e, 'index', idxName, table.name)}
@@ -7,6 +7,7 @@ interface TableItemProps {
     name: string;
     indexes: any[];
     triggers: any[];
+    shadowTables?: string[];
     onClick?: () => void;
     onContextMenu?: (e: React.MouseEvent) => void;
     onIndexContextMenu?: (e: React.MouseEvent, indexName: string) => void;
@@ -17,13 +18,14 @@ const TableItem: React.FC<TableItemProps> = ({
     name,
     indexes,
     triggers,
+    shadowTables = [],
     onClick,
     onContextMenu,
     onIndexContextMenu,
     onTriggerContextMenu
 }) => {
     const [isOpen, setIsOpen] = useState(false);
-    const hasChildren = indexes.length > 0 || triggers.length > 0;
+    const hasChildren = indexes.length > 0 || triggers.length > 0 || shadowTables.length > 0;
 
     return (
         <div className={styles.tableItem}>
@@ -83,6 +85,22 @@ const TableItem: React.FC<TableItemProps> = ({
                             ))}
                         </div>
                     )}
+                    {shadowTables.length > 0 && (
+                        <div className={styles.childGroup}>
+                            <span className={styles.childGroupLabel}>
+                                <span className="material-symbols-outlined" style={{ fontSize: 12 }}>layers</span>
+                                Shadow tables ({shadowTables.length})
+                            </span>
+                            {shadowTables.map(shadow => (
+                                <button
+                                    key={shadow}
+                                    className={styles.childItem}
+                                >
+                                    {shadow}
+                                </button>
+                            ))}
+                        </div>
+                    )}
                 </div>
             )}
         </div>
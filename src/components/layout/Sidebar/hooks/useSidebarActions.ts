@@ -4,7 +4,7 @@ import { useState, useCallback } from 'react';
 import { useDatabaseStore } from '@/stores/databaseStore';
 import { useUIStore } from '@/stores/uiStore';
 import { useQueryStore } from '@/stores/queryStore';
-import { executeQuery, SchemaInfo } from '@/lib/tauri';
+import { executeQuery, getDropImpact, SchemaInfo } from '@/lib/tauri';
 
 export const useSidebarActions = (schema: SchemaInfo | null, refreshSchema: () => void) => {
     const { activeConnection } = useDatabaseStore();
@@ -52,16 +52,7 @@ export const useSidebarActions = (schema: SchemaInfo | null, refreshSchema: () =
         } else if (type === 'view') {
             item = schema?.views.find(v => v.name === itemName);
         } else if (type === 'index') {
-            const idx = schema?.indexes.find(i => i.name === itemName);
-            if (idx) {
-                try {
-                    const result = await import('@/lib/tauri').then(m => m.executeQuery(activeConnection.id, `PRAGMA index_info("${itemName}")`, []));
-                    const columns = result.rows.map((row: any[]) => row[2]);
-                    item = { ...idx, columns };
-                } catch (e) {
-                    item = idx;
-                }
-            }
+            item = schema?.indexes.find(i => i.name === itemName);
         } else if (type === 'trigger') {
             item = schema?.triggers.find(t => t.name === itemName);
         }
@@ -149,31 +140,16 @@ export const useSidebarActions = (schema: SchemaInfo | null, refreshSchema: () =
     const handleDropTable = useCallback(async (tableName: string) => {
         if (!activeConnection || !schema) return;
 
-        const relatedIndexes = schema.indexes.filter(idx => idx.tableName === tableName);
-        const relatedTriggers = schema.triggers.filter(trg => trg.tableName === tableName);
-        const relatedViews = schema.views.filter(v =>
-            v.sql?.toLowerCase().includes(`from ${tableName.toLowerCase()}`) ||
-            v.sql?.toLowerCase().includes(`"${tableName.toLowerCase()}"`)
-        );
-
         let warningMessage = `Are you sure you want to drop table "${tableName}"?\n\n`;
-        if (relatedIndexes.length > 0 || relatedTriggers.length > 0 || relatedViews.length > 0) {
-            warningMessage += `⚠️ This will also affect:\n\n`;
-            if (relatedIndexes.length > 0) {
-                warningMessage += `📋 Indexes (${relatedIndexes.length}):\n`;
-                relatedIndexes.forEach(idx => { warningMessage += `   • ${idx.name}\n`; });
-                warningMessage += `\n`;
-            }
-            if (relatedTriggers.length > 0) {
-                warningMessage += `⚡ Triggers (${relatedTriggers.length}):\n`;
-                relatedTriggers.forEach(trg => { warningMessage += `   • ${trg.name}\n`; });
-                warningMessage += `\n`;
-            }
-            if (relatedViews.length > 0) {
-                warningMessage += `👁️ Views that may be affected (${relatedViews.length}):\n`;
-                relatedViews.forEach(v => { warningMessage += `   • ${v.name}\n`; });
+        try {
+            const impact = await getDropImpact(activeConnection.id, tableName);
+            if (impact.warnings.length > 0) {
+                warningMessage += `⚠️ This will also affect:\n\n`;
+                impact.warnings.forEach(w => { warningMessage += `   • ${w}\n`; });
                 warningMessage += `\n`;
             }
+        } catch (e) {
+            console.error('Failed to analyze dependencies:', e);
         }
         warningMessage += `This action cannot be undone.`;
 
@@ -192,18 +168,16 @@ export const useSidebarActions = (schema: SchemaInfo | null, refreshSchema: () =
     const handleDropView = useCallback(async (viewName: string) => {
         if (!activeConnection || !schema) return;
 
-        const dependentViews = schema.views.filter(v =>
-            v.name !== viewName && (
-                v.sql?.toLowerCase().includes(`from ${viewName.toLowerCase()}`) ||
-                v.sql?.toLowerCase().includes(`"${viewName.toLowerCase()}"`)
-            )
-        );
-
         let warningMessage = `Are you sure you want to drop view "${viewName}"?\n\n`;
-        if (dependentViews.length > 0) {
-            warningMessage += `⚠️ Views that may depend on this view:\n`;
-            dependentViews.forEach(v => { warningMessage += `   • ${v.name}\n`; });
-            warningMessage += `\n`;
+        try {
+            const impact = await getDropImpact(activeConnection.id, viewName);
+            if (impact.warnings.length > 0) {
+                warningMessage += `⚠️ This will also affect:\n`;
+                impact.warnings.forEach(w => { warningMessage += `   • ${w}\n`; });
+                warningMessage += `\n`;
+            }
+        } catch (e) {
+            console.error('Failed to analyze dependencies:', e);
         }
         warningMessage += `This action cannot be undone.`;
 
@@ -224,6 +198,10 @@ export const useSidebarActions = (schema: SchemaInfo | null, refreshSchema: () =
 
         const index = schema.indexes.find(idx => idx.name === indexName);
         const tableName = index?.tableName || 'unknown table';
+        if (index?.origin && index.origin !== 'createIndex') {
+            alert(`Index "${indexName}" enforces a ${index.origin === 'primaryKey' ? 'PRIMARY KEY' : 'UNIQUE'} constraint of ${tableName} and cannot be dropped on its own.`);
+            return;
+        }
         const warningMessage = `Are you sure you want to drop index "${indexName}"?\n\n` +
             `📋 This index belongs to table: ${tableName}\n\n` +
             `Note: Dropping this index may affect query performance.`;
@@ -3,7 +3,8 @@
 import React, { useState, useEffect } from 'react';
 import { X, Plus, Trash2, Key, Hash } from 'lucide-react';
 import { useDatabaseStore } from '@/stores/databaseStore';
-import { executeQuery } from '@/lib/tauri';
+import { executeQuery, createTable, alterTable } from '@/lib/tauri';
+import type { TableDefinition } from '@/schemas/query';
 import styles from './SchemaModal.module.css';
 
 interface Column {
@@ -41,6 +42,8 @@ const SchemaModal: React.FC<SchemaModalProps> = ({
         { name: 'id', type: 'INTEGER', notNull: true, primaryKey: true, defaultValue: '' }
     ]);
 
+    const [tablePreview, setTablePreview] = useState('');
+
     // View state
     const [viewName, setViewName] = useState('');
     const [viewSql, setViewSql] = useState('');
@@ -112,17 +115,32 @@ const SchemaModal: React.FC<SchemaModalProps> = ({
         setColumns(updated);
     };
 
-    const generateTableSql = () => {
-        const columnDefs = columns.map(col => {
-            let def = `"${col.name}" ${col.type}`;
-            if (col.primaryKey) def += ' PRIMARY KEY';
-            if (col.notNull && !col.primaryKey) def += ' NOT NULL';
-            if (col.defaultValue) def += ` DEFAULT ${col.defaultValue}`;
-            return def;
-        });
-        return `CREATE TABLE "${tableName}" (\n  ${columnDefs.join(',\n  ')}\n);`;
+    const tableDefinition = (): TableDefinition => {
+        const keyColumns = columns.filter(col => col.primaryKey).map(col => col.name);
+        return {
+            name: tableName,
+            columns: columns.map(col => ({
+                name: col.name,
+                dataType: col.type,
+                primaryKey: col.primaryKey && keyColumns.length === 1,
+                notNull: col.notNull && !col.primaryKey,
+                defaultValue: col.defaultValue || null,
+            })),
+            // Several key columns make a composite primary key
+            constraints: keyColumns.length > 1 ? [{ kind: 'primaryKey', columns: keyColumns }] : [],
+        };
     };
 
+    // Preview the DDL the backend will run; validation errors show as a comment
+    useEffect(() => {
+        if (type !== 'table' || !activeConnection) return;
+        let cancelled = false;
+        createTable(activeConnection.id, tableDefinition(), true)
+            .then(sql => { if (!cancelled) setTablePreview(sql); })
+            .catch(err => { if (!cancelled) setTablePreview(`-- ${err}`); });
+        return () => { cancelled = true; };
+    }, [type, activeConnection, tableName, columns]);
+
     const generateViewSql = () => {
         return `CREATE VIEW "${viewName}" AS\n${viewSql};`;
     };
@@ -145,6 +163,13 @@ const SchemaModal: React.FC<SchemaModalProps> = ({
         try {
             let sql = '';
 
+            // Tables are rebuilt in place so their rows are kept
+            if (type === 'table' && mode === 'edit' && item) {
+                await alterTable(activeConnection.id, item.name, tableDefinition());
+                onSave();
+                return;
+            }
+
             // For edit mode, we need to drop and recreate (SQLite limitation)
             if (mode === 'edit' && item) {
                 const dropSql = type === 'table'
@@ -160,8 +185,9 @@ const SchemaModal: React.FC<SchemaModalProps> = ({
 
             switch (type) {
                 case 'table':
-                    sql = generateTableSql();
-                    break;
+                    await createTable(activeConnection.id, tableDefinition());
+                    onSave();
+                    return;
                 case 'view':
                     sql = generateViewSql();
                     break;
@@ -306,7 +332,7 @@ const SchemaModal: React.FC<SchemaModalProps> = ({
 
                             <div className={styles.preview}>
                                 <h4>SQL Preview</h4>
-                                <pre>{generateTableSql()}</pre>
+                                <pre>{tablePreview}</pre>
                             </div>
                         </div>
                     )}
@@ -9,6 +9,7 @@ import type {
     QueryResult,
     DatabaseConnection,
     SchemaInfo,
+    TableDefinition,
     DatabaseStats,
     DataChangeEvent,
     PerformanceEvent,
@@ -82,6 +83,29 @@ export async function rollbackTransaction(transactionId: string): Promise<void>
     return invoke('rollback_transaction', { transactionId });
 }
 
+/** Limits after which open transactions are warned about, then rolled back */
+export interface TransactionTimeoutConfig {
+    /** Seconds without activity; null to disable */
+    idleTimeoutSecs: number | null;
+    /** Seconds since BEGIN; null to disable */
+    maxAgeSecs: number | null;
+    /** Seconds before the rollback that `db:transaction_warning` is emitted */
+    warningSecs: number;
+}
+
+export async function getTransactionTimeouts(): Promise<TransactionTimeoutConfig> {
+    return invoke<TransactionTimeoutConfig>('get_transaction_timeouts');
+}
+
+export async function setTransactionTimeouts(config: TransactionTimeoutConfig): Promise<void> {
+    return invoke('set_transaction_timeouts', { config });
+}
+
+/** Opt a connection out of, or back into, automatic rollback */
+export async function setTransactionTimeoutEnabled(connectionId: string, enabled: boolean): Promise<void> {
+    return invoke('set_transaction_timeout_enabled', { connectionId, enabled });
+}
+
 // ============================================
 // Schema Commands
 // ============================================
@@ -97,6 +121,186 @@ export async function getTableInfo(
     return invoke('get_table_info', { connectionId, tableName });
 }
 
+/** Render tables and their relationships as Mermaid, Graphviz DOT or PlantUML text */
+export async function exportErDiagram(
+    connectionId: string,
+    format: 'mermaid' | 'dot' | 'plantuml',
+    options?: { tables?: string[]; depth?: number; schema?: string }
+): Promise<string> {
+    return invoke<string>('export_er_diagram', { connectionId, format, options });
+}
+
+export interface SchemaSearchHit {
+    schema: string;
+    kind: 'table' | 'column' | 'view' | 'index' | 'trigger' | 'viewDefinition' | 'triggerDefinition';
+    object: string;
+    column?: string | null;
+    snippet?: string | null;
+    score: number;
+}
+
+/** Search object names and view and trigger SQL across main and attached databases */
+export async function searchSchema(connectionId: string, query: string, limit?: number): Promise<SchemaSearchHit[]> {
+    return invoke<SchemaSearchHit[]>('search_schema', { connectionId, query, limit });
+}
+
+/** A value as the backend sends it, tagged with its storage class */
+export type TaggedCellValue =
+    | { type: 'null' }
+    | { type: 'integer'; value: number }
+    | { type: 'real'; value: number }
+    | { type: 'text'; value: string }
+    | { type: 'blob'; value: number[] };
+
+export interface RowDifference {
+    kind: 'inserted' | 'deleted' | 'changed';
+    key: Record<string, TaggedCellValue>;
+    values?: Record<string, TaggedCellValue> | null;
+    changes: { column: string; source: TaggedCellValue; target: TaggedCellValue }[];
+}
+
+export interface TableDataDiff {
+    keyColumns: string[];
+    columns: string[];
+    inserted: number;
+    deleted: number;
+    changed: number;
+    unchanged: number;
+    rows: RowDifference[];
+    truncated: boolean;
+    warnings: string[];
+    script: string;
+    applied?: number | null;
+}
+
+/** Compare two tables by key; the sync makes the target match the source, and runs with `apply` */
+export async function diffTableData(
+    sourceConnectionId: string,
+    sourceTable: string,
+    targetConnectionId: string,
+    targetTable: string,
+    options?: { keyColumns?: string[]; rowLimit?: number; apply?: boolean }
+): Promise<TableDataDiff> {
+    return invoke<TableDataDiff>('diff_table_data', { sourceConnectionId, sourceTable, targetConnectionId, targetTable, options });
+}
+
+/** Write a data dictionary as one Markdown file or an HTML site; returns the files written */
+export async function generateSchemaDocs(
+    connectionId: string,
+    format: 'markdown' | 'html',
+    outputPath: string
+): Promise<string[]> {
+    return invoke<string[]>('generate_schema_docs', { connectionId, format, outputPath });
+}
+
+export interface Dependency {
+    dependent: string;
+    dependentKind: 'table' | 'view' | 'index' | 'trigger';
+    target: string;
+    targetKind: 'table' | 'view' | 'index' | 'trigger';
+    kind: 'viewReference' | 'triggerReference' | 'triggerTarget' | 'index' | 'foreignKey';
+    columns: string[];
+}
+
+export interface DropImpact {
+    object: string;
+    kind: 'table' | 'view' | 'index' | 'trigger';
+    column?: string | null;
+    droppedWith: string[];
+    broken: Dependency[];
+    foreignKeys: unknown[];
+    partitionPolicies: string[];
+    warnings: string[];
+}
+
+/** Objects that the named object uses */
+export async function getDependencies(connectionId: string, objectName: string): Promise<Dependency[]> {
+    return invoke<Dependency[]>('get_dependencies', { connectionId, objectName });
+}
+
+/** Objects that use the named object, optionally through other views */
+export async function getDependents(
+    connectionId: string,
+    objectName: string,
+    transitive = false
+): Promise<Dependency[]> {
+    return invoke<Dependency[]>('get_dependents', { connectionId, objectName, transitive });
+}
+
+/** What dropping an object, or one of its columns, would take with it or break */
+export async function getDropImpact(
+    connectionId: string,
+    objectName: string,
+    columnName?: string
+): Promise<DropImpact> {
+    return invoke<DropImpact>('get_drop_impact', { connectionId, objectName, columnName });
+}
+
+/** Impact of the DROP statements in a script, to confirm before running it */
+export async function getStatementImpact(connectionId: string, sql: string): Promise<DropImpact[]> {
+    return invoke<DropImpact[]>('get_statement_impact', { connectionId, sql });
+}
+
+/** Descriptions kept in the sidecar file, by object name */
+export async function getObjectDescriptions(
+    connectionId: string
+): Promise<Record<string, { description?: string; columns?: Record<string, string> }>> {
+    return invoke('get_object_descriptions', { connectionId });
+}
+
+/** Describe an object or one of its columns; an empty description removes it */
+export async function setObjectDescription(
+    connectionId: string,
+    objectName: string,
+    description: string,
+    columnName?: string
+): Promise<void> {
+    return invoke('set_object_description', { connectionId, objectName, columnName, description });
+}
+
+/** Create a table from a structured definition; returns the generated DDL */
+export async function createTable(
+    connectionId: string,
+    definition: TableDefinition,
+    dryRun = false
+): Promise<string> {
+    return invoke<string>('create_table', { connectionId, definition, dryRun });
+}
+
+export interface RenameResult {
+    statement: string;
+    updatedViews: string[];
+    updatedTriggers: string[];
+    partitionPolicies: string[];
+    sidecarUpdated: boolean;
+}
+
+/** Rename a table along with the views, triggers and app metadata that refer to it */
+export async function renameTable(connectionId: string, tableName: string, newName: string): Promise<RenameResult> {
+    return invoke<RenameResult>('rename_table', { connectionId, tableName, newName });
+}
+
+/** Rename a column along with the views, triggers and app metadata that refer to it */
+export async function renameColumn(
+    connectionId: string,
+    tableName: string,
+    columnName: string,
+    newName: string
+): Promise<RenameResult> {
+    return invoke<RenameResult>('rename_column', { connectionId, tableName, columnName, newName });
+}
+
+/** Rebuild a table into a new definition, keeping its rows */
+export async function alterTable(
+    connectionId: string,
+    tableName: string,
+    definition: TableDefinition,
+    renamedColumns?: Record<string, string>,
+    dryRun = false
+): Promise<{ script: string; warnings: string[]; applied: boolean }> {
+    return invoke('alter_table', { connectionId, tableName, definition, renamedColumns, dryRun });
+}
+
 // ============================================
 // Stats Commands
 // ============================================
@@ -7,10 +7,13 @@ export const ColumnInfoSchema = z.object({
     nullable: z.boolean().optional(),
     primaryKey: z.boolean().optional(),
     defaultValue: z.any().nullable().optional(),
+    hidden: z.boolean().optional(),
+    generated: z.enum(['virtual', 'stored']).nullable().optional(),
     foreignKey: z.object({
+        constraintId: z.number().optional(),
         table: z.string(),
         column: z.string(),
-    }).optional(),
+    }).nullable().optional(),
 });
 
 export type ColumnInfo = z.infer<typeof ColumnInfoSchema>;
@@ -25,11 +28,37 @@ export const QueryResultSchema = z.object({
 
 export type QueryResult = z.infer<typeof QueryResultSchema>;
 
+export const ForeignKeyConstraintSchema = z.object({
+    id: z.number(),
+    table: z.string(),
+    referencedTable: z.string(),
+    columns: z.array(z.object({
+        from: z.string(),
+        to: z.string(),
+    })),
+    onUpdate: z.string(),
+    onDelete: z.string(),
+    matchType: z.string(),
+    deferrable: z.boolean(),
+    initiallyDeferred: z.boolean(),
+});
+
+export type ForeignKeyConstraint = z.infer<typeof ForeignKeyConstraintSchema>;
+
 export const TableInfoSchema = z.object({
     name: z.string(),
+    kind: z.enum(['table', 'virtual', 'shadow']).optional(),
+    strict: z.boolean().optional(),
+    withoutRowid: z.boolean().optional(),
+    module: z.string().nullable().optional(), // e.g. fts5, rtree
+    shadowTables: z.array(z.string()).optional(),
+    shadowOf: z.string().nullable().optional(),
     columns: z.array(ColumnInfoSchema),
-    rowCount: z.number(),
+    rowCount: z.number().nullable(), // null when not counted
+    rowCountEstimated: z.boolean().optional(),
     sizeBytes: z.number(),
+    foreignKeys: z.array(ForeignKeyConstraintSchema).optional(),
+    referencedBy: z.array(ForeignKeyConstraintSchema).optional(),
 });
 
 export type TableInfo = z.infer<typeof TableInfoSchema>;
@@ -41,11 +70,25 @@ export const ViewInfoSchema = z.object({
 
 export type ViewInfo = z.infer<typeof ViewInfoSchema>;
 
+export const IndexColumnSchema = z.object({
+    name: z.string().nullable(), // null for expressions
+    expression: z.string().nullable(),
+    descending: z.boolean(),
+    collation: z.string(),
+});
+
+export type IndexColumn = z.infer<typeof IndexColumnSchema>;
+
 export const IndexInfoSchema = z.object({
     name: z.string(),
     tableName: z.string(),
     columns: z.array(z.string()),
+    keyColumns: z.array(IndexColumnSchema).optional(),
     isUnique: z.boolean(),
+    origin: z.enum(['createIndex', 'unique', 'primaryKey']).optional(),
+    isPartial: z.boolean().optional(),
+    whereClause: z.string().nullable().optional(),
+    sql: z.string().nullable().optional(),
 });
 
 export type IndexInfo = z.infer<typeof IndexInfoSchema>;
@@ -55,11 +98,24 @@ export const TriggerInfoSchema = z.object({
     tableName: z.string(),
     timing: z.string(), // Backend may return empty string
     event: z.string(),  // Backend may return empty string
+    updateColumns: z.array(z.string()).optional(),
+    forEachRow: z.boolean().optional(),
+    whenClause: z.string().nullable().optional(),
+    body: z.array(z.string()).optional(),
     sql: z.string(),
 });
 
 export type TriggerInfo = z.infer<typeof TriggerInfoSchema>;
 
+export const DisabledTriggerSchema = z.object({
+    name: z.string(),
+    tableName: z.string(),
+    sql: z.string(),
+    disabledAt: z.number(),
+});
+
+export type DisabledTrigger = z.infer<typeof DisabledTriggerSchema>;
+
 export const SchemaInfoSchema = z.object({
     tables: z.array(TableInfoSchema),
     views: z.array(ViewInfoSchema),
@@ -68,3 +124,52 @@ export const SchemaInfoSchema = z.object({
 });
 
 export type SchemaInfo = z.infer<typeof SchemaInfoSchema>;
+
+export const ColumnDefinitionSchema = z.object({
+    name: z.string(),
+    dataType: z.string().optional(),
+    primaryKey: z.boolean().optional(),
+    autoincrement: z.boolean().optional(),
+    notNull: z.boolean().optional(),
+    unique: z.boolean().optional(),
+    defaultValue: z.string().nullable().optional(), // literal or expression
+    collation: z.string().nullable().optional(),
+    check: z.string().nullable().optional(),
+    generated: z.object({
+        expression: z.string(),
+        kind: z.enum(['virtual', 'stored']),
+    }).nullable().optional(),
+});
+
+export type ColumnDefinition = z.infer<typeof ColumnDefinitionSchema>;
+
+const ForeignKeyActionSchema = z.enum(['NO ACTION', 'RESTRICT', 'SET NULL', 'SET DEFAULT', 'CASCADE']);
+
+export const TableConstraintSchema = z.discriminatedUnion('kind', [
+    z.object({ kind: z.literal('primaryKey'), name: z.string().nullable().optional(), columns: z.array(z.string()) }),
+    z.object({ kind: z.literal('unique'), name: z.string().nullable().optional(), columns: z.array(z.string()) }),
+    z.object({ kind: z.literal('check'), name: z.string().nullable().optional(), expression: z.string() }),
+    z.object({
+        kind: z.literal('foreignKey'),
+        name: z.string().nullable().optional(),
+        columns: z.array(z.string()),
+        referencedTable: z.string(),
+        referencedColumns: z.array(z.string()).optional(), // empty for the parent's primary key
+        onUpdate: ForeignKeyActionSchema.optional(),
+        onDelete: ForeignKeyActionSchema.optional(),
+        deferrable: z.boolean().optional(),
+        initiallyDeferred: z.boolean().optional(),
+    }),
+]);
+
+export type TableConstraint = z.infer<typeof TableConstraintSchema>;
+
+export const TableDefinitionSchema = z.object({
+    name: z.string(),
+    columns: z.array(ColumnDefinitionSchema),
+    constraints: z.array(TableConstraintSchema).optional(),
+    strict: z.boolean().optional(),
+    withoutRowid: z.boolean().optional(),
+});
+
+export type TableDefinition = z.infer<typeof TableDefinitionSchema>;
@@ -15,6 +15,20 @@ import {
 } from '@/schemas/query';
 import { z } from 'zod';
 import { UnlistenFn } from '@tauri-apps/api/event';
+import type { DropImpact } from '@/lib/tauri';
+
+/** One side of a schema diff */
+export type SchemaSource =
+    | { kind: 'connection'; connectionId: string }
+    | { kind: 'file'; path: string }
+    | { kind: 'snapshot'; path: string };
+
+/** A confirmed rename of a table (`table` null) or of a column of `table` */
+export interface SchemaRename {
+    table: string | null;
+    from: string;
+    to: string;
+}
 
 export class DatabaseService extends ApiService {
     // Database lifecycle
@@ -47,13 +61,26 @@ export class DatabaseService extends ApiService {
         return this.request('explain_query', { connectionId, sql }, z.string());
     }
 
+    /** Impact of the DROP and ALTER TABLE ... DROP COLUMN statements in `sql` */
+    async getStatementImpact(connectionId: string, sql: string): Promise<DropImpact[]> {
+        return this.rawRequest('get_statement_impact', { connectionId, sql });
+    }
+
     // Schema
     async getSchema(connectionId: string): Promise<SchemaInfo> {
         return this.request('get_schema', { connectionId }, SchemaInfoSchema);
     }
 
-    async diffSchemas(connectionId: string, targetPath: string): Promise<any> {
-        return this.rawRequest('diff_schemas', { sourceConnectionId: connectionId, targetPath });
+    async diffSchemas(source: SchemaSource, target: SchemaSource, renames: SchemaRename[] = []): Promise<any> {
+        return this.rawRequest('diff_schemas', { source, target, renames });
+    }
+
+    async applySchemaDiff(source: SchemaSource, target: SchemaSource, renames: SchemaRename[] = [], dryRun = false): Promise<any> {
+        return this.rawRequest('apply_schema_diff', { source, target, renames, dryRun });
+    }
+
+    async saveSchemaSnapshot(connectionId: string, outputPath: string): Promise<void> {
+        return this.rawRequest('save_schema_snapshot', { connectionId, outputPath });
     }
 
     async getTableInfo(connectionId: string, tableName: string): Promise<TableInfo> {
@@ -121,6 +121,22 @@ export const useQueryStore = create<QueryState>((set, get) => ({
 
         if (!activeTab || !activeTab.content) return;
 
+        // Confirm statements that drop objects or columns others depend on
+        if (/\b(DROP|ALTER)\b/i.test(activeTab.content)) {
+            try {
+                const impacts = await dbService.getStatementImpact(connectionId, activeTab.content);
+                const warnings = impacts.flatMap(impact => impact.warnings);
+                if (warnings.length > 0) {
+                    const message = `This script will also affect:\n\n`
+                        + warnings.map(w => `   • ${w}`).join('\n')
+                        + `\n\nRun it anyway?`;
+                    if (!confirm(message)) return;
+                }
+            } catch (e) {
+                console.error('Failed to analyze dependencies:', e);
+            }
+        }
+
         // Clear previous result/error and set loading
         set((state) => ({
             tabs: state.tabs.map(t => t.id === activeTabId
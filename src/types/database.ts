@@ -1,4 +1,4 @@
-import { QueryResult, ColumnInfo, SchemaInfo, TableInfo, ViewInfo, IndexInfo, TriggerInfo } from '@/schemas/query';
+import { QueryResult, ColumnInfo, SchemaInfo, TableInfo, ViewInfo, IndexInfo, TriggerInfo, TableDefinition } from '@/schemas/query';
 import { DatabaseConnection } from '@/schemas/database';
 
 export type {
@@ -9,6 +9,7 @@ export type {
     ViewInfo,
     IndexInfo,
     TriggerInfo,
+    TableDefinition,
     DatabaseConnection
 };
 